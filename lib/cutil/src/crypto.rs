@@ -1,6 +1,19 @@
-use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
-use anyhow::{anyhow, Context, Result};
-use crypto_hash::{hex_digest, Algorithm};
+use aes::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit, block_padding::Pkcs7};
+use aes_gcm::{
+    Aes256Gcm, Nonce,
+    aead::{Aead, KeyInit},
+};
+use anyhow::{Context, Result, anyhow, bail};
+use argon2::Argon2;
+use crypto_hash::{Algorithm, hex_digest};
+
+pub const KEY_LEN: usize = 32;
+pub const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+// Prefix of the texts sealed by `Cipher`, the version allows a later change of
+// the algorithm
+const SEALED_PREFIX: &str = "aead1:";
 
 type Aes128CbcEnc = cbc::Encryptor<aes::Aes128>;
 type Aes128CbcDec = cbc::Decryptor<aes::Aes128>;
@@ -44,6 +57,125 @@ pub fn decrypt(password: &str, encrypt_text: &str) -> Result<Vec<u8>> {
     }
 }
 
+// Key of 32 bytes derived from a password with Argon2id. The salt should be
+// random and at least 8 bytes long, `SALT_LEN` is used by this module.
+pub fn derive_key(password: &str, salt: &[u8]) -> Result<[u8; KEY_LEN]> {
+    let mut key = [0_u8; KEY_LEN];
+
+    Argon2::default()
+        .hash_password_into(password.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow!("derive key failed. {e}"))?;
+
+    Ok(key)
+}
+
+pub fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0_u8; N];
+    rand::fill(&mut bytes[..]);
+    bytes
+}
+
+// AES-256-GCM with a random nonce for each text. A sealed text is
+// `aead1:` followed by the hex of the nonce and the ciphertext with its tag,
+// so any change of it is detected by `open`.
+#[derive(Clone)]
+pub struct Cipher {
+    key: [u8; KEY_LEN],
+}
+
+impl std::fmt::Debug for Cipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Cipher { .. }")
+    }
+}
+
+impl Cipher {
+    pub fn new(key: [u8; KEY_LEN]) -> Self {
+        Self { key }
+    }
+
+    pub fn random() -> Self {
+        Self::new(random_bytes())
+    }
+
+    pub fn from_password(password: &str, salt: &[u8]) -> Result<Self> {
+        Ok(Self::new(derive_key(password, salt)?))
+    }
+
+    // The key as hex, e.g. to write a key file
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.key)
+    }
+
+    pub fn from_hex(text: &str) -> Result<Self> {
+        let key = hex::decode(text.trim()).context("Decoding key failed")?;
+        let key = <[u8; KEY_LEN]>::try_from(key.as_slice())
+            .map_err(|_| anyhow!("key should be {KEY_LEN} bytes"))?;
+
+        Ok(Self::new(key))
+    }
+
+    pub fn is_sealed(text: &str) -> bool {
+        text.starts_with(SEALED_PREFIX)
+    }
+
+    pub fn seal(&self, plain_text: &[u8]) -> Result<String> {
+        let nonce = random_bytes::<NONCE_LEN>();
+        let cipher_text = self
+            .aes()
+            .encrypt(Nonce::from_slice(&nonce), plain_text)
+            .map_err(|_| anyhow!("encrypt failed"))?;
+
+        Ok(format!(
+            "{SEALED_PREFIX}{}{}",
+            hex::encode(nonce),
+            hex::encode(cipher_text)
+        ))
+    }
+
+    // Fails if the text is not sealed with the same key or it is changed
+    pub fn open(&self, sealed_text: &str) -> Result<Vec<u8>> {
+        let Some(text) = sealed_text.strip_prefix(SEALED_PREFIX) else {
+            bail!("not a sealed text");
+        };
+
+        let bytes = hex::decode(text).context("Decoding sealed text failed")?;
+        if bytes.len() < NONCE_LEN {
+            bail!("sealed text is too short");
+        }
+
+        let (nonce, cipher_text) = bytes.split_at(NONCE_LEN);
+        self.aes()
+            .decrypt(Nonce::from_slice(nonce), cipher_text)
+            .map_err(|_| anyhow!("decrypt failed, wrong key or damaged data"))
+    }
+
+    fn aes(&self) -> Aes256Gcm {
+        Aes256Gcm::new(&self.key.into())
+    }
+}
+
+// Password based counterparts of `encrypt` and `decrypt`. The random salt is
+// kept in the result, so every call derives a new key.
+pub fn seal_with_password(password: &str, plain_text: &[u8]) -> Result<String> {
+    let salt = random_bytes::<SALT_LEN>();
+    let sealed = Cipher::from_password(password, &salt)?.seal(plain_text)?;
+
+    Ok(format!("{}:{sealed}", hex::encode(salt)))
+}
+
+pub fn open_with_password(password: &str, sealed_text: &str) -> Result<Vec<u8>> {
+    let (salt, sealed) = sealed_text
+        .split_once(':')
+        .ok_or(anyhow!("missing salt of the sealed text"))?;
+
+    Cipher::from_password(
+        password,
+        &hex::decode(salt).context("Decoding salt failed")?,
+    )?
+    .open(sealed)
+}
+
 pub fn hash(text: &str) -> String {
     hex_digest(
         Algorithm::MD5,
@@ -77,11 +209,53 @@ mod tests {
     fn test_encrypt_decrypt() -> Result<()> {
         for i in 1..100 {
             let (text, password) = (random_string(i + 10), random_string(i));
-            let enc_text = encrypt(&password, &text.as_bytes())?;
+            let enc_text = encrypt(&password, text.as_bytes())?;
             let dec_text = decrypt(&password, &enc_text)?;
             assert_eq!(text.as_bytes(), dec_text)
         }
 
         Ok(())
     }
+
+    #[test]
+    fn test_cipher() -> Result<()> {
+        let cipher = Cipher::random();
+
+        for i in [0, 1, 100, 5000] {
+            let text = random_string(i);
+            let sealed = cipher.seal(text.as_bytes())?;
+            assert!(Cipher::is_sealed(&sealed));
+            assert_ne!(sealed, cipher.seal(text.as_bytes())?);
+            assert_eq!(cipher.open(&sealed)?, text.as_bytes());
+        }
+
+        // Flipping a bit of the tag always changes the sealed text
+        let sealed = cipher.seal(b"hello")?;
+        let mut bytes = hex::decode(&sealed[SEALED_PREFIX.len()..])?;
+        *bytes.last_mut().unwrap() ^= 0x01;
+        let damaged = format!("{SEALED_PREFIX}{}", hex::encode(bytes));
+        assert!(cipher.open(&damaged).is_err());
+        assert!(Cipher::random().open(&sealed).is_err());
+        assert!(cipher.open("hello").is_err());
+
+        let key = Cipher::from_hex(&cipher.to_hex())?;
+        assert_eq!(key.open(&sealed)?, b"hello");
+        assert!(Cipher::from_hex("abcd").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_password() -> Result<()> {
+        let salt = random_bytes::<SALT_LEN>();
+        assert_eq!(derive_key("secret", &salt)?, derive_key("secret", &salt)?);
+        assert_ne!(derive_key("secret", &salt)?, derive_key("secret!", &salt)?);
+        assert!(derive_key("secret", b"short").is_err());
+
+        let sealed = seal_with_password("secret", b"api key")?;
+        assert_eq!(open_with_password("secret", &sealed)?, b"api key");
+        assert!(open_with_password("wrong", &sealed).is_err());
+
+        Ok(())
+    }
 }
@@ -1,8 +1,8 @@
 use anyhow::Result;
 use bytes::Bytes;
 use reqwest::{
-    header::{HeaderMap, ACCEPT, CACHE_CONTROL, USER_AGENT},
     Client, Url,
+    header::{ACCEPT, CACHE_CONTROL, HeaderMap, USER_AGENT},
 };
 use std::{ffi::OsStr, path::Path, time::Duration};
 
@@ -42,6 +42,15 @@ pub fn timestamp() -> i64 {
     Local::now().timestamp()
 }
 
+// Empty if the timestamp is out of range
+pub fn timestamp_to_local(timestamp: i64, format: &str) -> String {
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|datetime| datetime.format(format).to_string())
+        .unwrap_or_default()
+}
+
 pub fn get_calendar_matrix(year: i32, month: u32) -> Result<Vec<Vec<Date>>> {
     let mut matrix: Vec<Vec<Date>> = vec![vec![]; 6];
 
@@ -62,15 +71,15 @@ pub fn get_calendar_matrix(year: i32, month: u32) -> Result<Vec<Vec<Date>>> {
     let start_date = first_day_month - Duration::days(first_day_col as i64);
 
     let mut current_date = start_date;
-    for row in 0..6 {
-        matrix[row] = Vec::with_capacity(7);
+    for row in matrix.iter_mut() {
+        *row = Vec::with_capacity(7);
         for _ in 0..7 {
-            matrix[row].push(Date {
+            row.push(Date {
                 year: current_date.year(),
                 month: current_date.month(),
                 day: current_date.day(),
             });
-            current_date = current_date + Duration::days(1);
+            current_date += Duration::days(1);
         }
     }
 
@@ -92,6 +101,11 @@ pub fn diff_dates_to_days(start_date: &str, end_date: &str) -> Result<i64> {
     Ok((end_timestamp - start_timestamp) / (24 * 60 * 60))
 }
 
+pub fn date_str_add_days(date_str: &str, days: i64) -> Result<String> {
+    let date = NaiveDate::parse_from_str(date_str, "%Y-%m-%d")? + Duration::days(days);
+    Ok(date.format("%Y-%m-%d").to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -139,4 +153,11 @@ mod tests {
     fn test_diff_dates_to_days() {
         assert_eq!(diff_dates_to_days("2005-12-09", "2005-12-10").unwrap(), 1);
     }
+
+    #[test]
+    fn test_date_str_add_days() {
+        assert_eq!(date_str_add_days("2005-12-31", 1).unwrap(), "2006-01-01");
+        assert_eq!(date_str_add_days("2005-03-01", -1).unwrap(), "2005-02-28");
+        assert!(date_str_add_days("2005-09-xxx", 1).is_err());
+    }
 }
@@ -0,0 +1,177 @@
+// At-rest encryption of the `data` column. The rows of the encrypted tables
+// are sealed when they are written, and the sealed rows of any table are
+// opened when they are read. Plain rows are still read as they are, so the
+// encryption can be turned on or off and then applied by `rewrite`.
+//
+// The generated columns and the full text index only see the plain json, so
+// the sealed rows get NULL columns and are not indexed.
+
+use super::{SqliteStorage, storage::ComEntry};
+use anyhow::{Result, bail};
+use cutil::crypto::Cipher;
+use std::sync::Mutex;
+
+#[derive(Default)]
+struct State {
+    cipher: Option<Cipher>,
+    tables: Vec<String>,
+}
+
+// The encryption setting of a `SqliteStorage`
+#[derive(Default)]
+pub struct Crypt {
+    state: Mutex<State>,
+}
+
+impl std::fmt::Debug for Crypt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Crypt")
+            .field("tables", &self.state.lock().unwrap().tables)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Crypt {
+    pub(crate) fn is_encrypted(&self, table: &str) -> bool {
+        self.state
+            .lock()
+            .unwrap()
+            .tables
+            .iter()
+            .any(|item| item == table)
+    }
+
+    pub(crate) fn seal(&self, table: &str, data: &str) -> Result<String> {
+        let state = self.state.lock().unwrap();
+
+        if !state.tables.iter().any(|item| item == table) {
+            return Ok(data.to_string());
+        }
+
+        match state.cipher.as_ref() {
+            Some(cipher) => cipher.seal(data.as_bytes()),
+            None => bail!("table `{table}` is encrypted, unlock it first"),
+        }
+    }
+
+    pub(crate) fn open(&self, mut entry: ComEntry) -> Result<ComEntry> {
+        if Cipher::is_sealed(&entry.data) {
+            let state = self.state.lock().unwrap();
+            let Some(cipher) = state.cipher.as_ref() else {
+                bail!("the data is encrypted, unlock it first");
+            };
+
+            entry.data = String::from_utf8(cipher.open(&entry.data)?)?;
+        }
+
+        Ok(entry)
+    }
+}
+
+impl SqliteStorage {
+    // The key of the sealed rows. `None` leaves them unreadable.
+    pub fn set_cipher(&self, cipher: Option<Cipher>) {
+        self.crypt.state.lock().unwrap().cipher = cipher;
+    }
+
+    pub fn has_cipher(&self) -> bool {
+        self.crypt.state.lock().unwrap().cipher.is_some()
+    }
+
+    // The tables whose rows are sealed on write
+    pub fn set_encrypted_tables(&self, tables: &[&str]) {
+        self.crypt.state.lock().unwrap().tables =
+            tables.iter().map(|table| table.to_string()).collect();
+    }
+
+    pub fn is_encrypted(&self, table: &str) -> bool {
+        self.crypt.is_encrypted(table)
+    }
+
+    // Writes every row of `tables` again with the current setting, e.g. after
+    // the encryption is turned on or off or the key is changed. The sealed
+    // rows are opened with `old`, or the current key if it is `None`. All rows
+    // are rewritten in one transaction, so a failure leaves the tables as they
+    // were. Returns the number of the rewritten rows.
+    pub async fn rewrite(&self, tables: &[&str], old: Option<&Cipher>) -> Result<usize> {
+        let mut items = vec![];
+
+        for table in tables {
+            let entries = sqlx::query_as::<_, ComEntry>(&format!("SELECT uuid, data FROM {table}"))
+                .fetch_all(&self.pool)
+                .await?;
+
+            for mut entry in entries {
+                entry = match old {
+                    Some(cipher) if Cipher::is_sealed(&entry.data) => ComEntry {
+                        data: String::from_utf8(cipher.open(&entry.data)?)?,
+                        ..entry
+                    },
+                    _ => self.crypt.open(entry)?,
+                };
+
+                items.push((*table, entry.uuid, self.crypt.seal(table, &entry.data)?));
+            }
+        }
+
+        let mut tx = self.pool.begin().await?;
+        for (table, uuid, data) in items.iter() {
+            sqlx::query(&format!("UPDATE {table} SET data=? WHERE uuid=?"))
+                .bind(data)
+                .bind(uuid)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+
+        Ok(items.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Storage;
+
+    const TABLE_NAME: &str = "crypt_test";
+
+    async fn raw_data(storage: &SqliteStorage, uuid: &str) -> Result<String> {
+        let (data,): (String,) =
+            sqlx::query_as(&format!("SELECT data FROM {TABLE_NAME} WHERE uuid=?"))
+                .bind(uuid)
+                .fetch_one(storage.pool())
+                .await?;
+        Ok(data)
+    }
+
+    #[tokio::test]
+    async fn test_rewrite() -> Result<()> {
+        let storage = SqliteStorage::open_in_memory().await?;
+        storage.new_table(TABLE_NAME).await?;
+        storage.insert(TABLE_NAME, "uuid-1", "data-1").await?;
+
+        let cipher = Cipher::random();
+        storage.set_cipher(Some(cipher.clone()));
+        storage.set_encrypted_tables(&[TABLE_NAME]);
+
+        assert_eq!(storage.rewrite(&[TABLE_NAME], None).await?, 1);
+        storage.insert(TABLE_NAME, "uuid-2", "data-2").await?;
+        assert!(Cipher::is_sealed(&raw_data(&storage, "uuid-1").await?));
+        assert!(Cipher::is_sealed(&raw_data(&storage, "uuid-2").await?));
+        assert_eq!(storage.select(TABLE_NAME, "uuid-2").await?.data, "data-2");
+
+        // A new key opens the rows sealed by the old one
+        storage.set_cipher(Some(Cipher::random()));
+        assert!(storage.select_all(TABLE_NAME).await.is_err());
+        assert_eq!(storage.rewrite(&[TABLE_NAME], Some(&cipher)).await?, 2);
+        assert_eq!(storage.select_all(TABLE_NAME).await?[0].data, "data-1");
+
+        storage.set_cipher(None);
+        assert!(storage.select(TABLE_NAME, "uuid-1").await.is_err());
+        assert!(storage.update(TABLE_NAME, "uuid-1", "data").await.is_err());
+
+        storage.set_cipher(Some(Cipher::random()));
+        assert!(storage.rewrite(&[TABLE_NAME], None).await.is_err());
+        Ok(())
+    }
+}
@@ -1,152 +1,240 @@
-use super::{pool, ComEntry};
-use anyhow::Result;
-
-pub async fn new(table: &str) -> Result<()> {
-    sqlx::query(&format!(
+use super::{
+    SqliteStorage,
+    storage::{ComEntry, Storage, WriteOp},
+};
+use anyhow::{Result, bail};
+use async_trait::async_trait;
+use sqlx::{Executor, Sqlite};
+
+pub fn create_table_sql(table: &str) -> String {
+    format!(
         "CREATE TABLE IF NOT EXISTS {table} (
              id INTEGER PRIMARY KEY,
              uuid TEXT NOT NULL UNIQUE,
              data TEXT NOT NULL
              )"
-    ))
-    .execute(&pool().await)
-    .await?;
-
-    Ok(())
+    )
 }
 
-pub async fn delete(table: &str, uuid: &str) -> Result<()> {
-    sqlx::query(&format!("DELETE FROM {table} WHERE uuid=?"))
-        .bind(uuid)
-        .execute(&pool().await)
-        .await?;
-    Ok(())
-}
+impl SqliteStorage {
+    pub async fn new_table(&self, table: &str) -> Result<()> {
+        sqlx::query(&create_table_sql(table))
+            .execute(&self.pool)
+            .await?;
 
-pub async fn delete_all(table: &str) -> Result<()> {
-    sqlx::query(&format!("DELETE FROM {table}"))
-        .execute(&pool().await)
-        .await?;
-    Ok(())
-}
+        Ok(())
+    }
 
-pub async fn insert(table: &str, uuid: &str, data: &str) -> Result<()> {
-    sqlx::query(&format!("INSERT INTO {table} (uuid, data) VALUES (?, ?)"))
-        .bind(uuid)
-        .bind(data)
-        .execute(&pool().await)
-        .await?;
-    Ok(())
+    // Runs `op` on the pool or in a transaction. A move takes two
+    // statements, so it only runs in a transaction.
+    async fn write<'e, E>(&self, executor: E, op: &WriteOp) -> Result<()>
+    where
+        E: Executor<'e, Database = Sqlite>,
+    {
+        match op {
+            WriteOp::Insert { table, uuid, data } => {
+                sqlx::query(&format!("INSERT INTO {table} (uuid, data) VALUES (?, ?)"))
+                    .bind(uuid)
+                    .bind(self.crypt.seal(table, data)?)
+                    .execute(executor)
+                    .await?;
+            }
+            WriteOp::Update { table, uuid, data } => {
+                sqlx::query(&format!("UPDATE {table} SET data=? WHERE uuid=?"))
+                    .bind(self.crypt.seal(table, data)?)
+                    .bind(uuid)
+                    .execute(executor)
+                    .await?;
+            }
+            WriteOp::Delete { table, uuid } => {
+                sqlx::query(&format!("DELETE FROM {table} WHERE uuid=?"))
+                    .bind(uuid)
+                    .execute(executor)
+                    .await?;
+            }
+            WriteOp::Move { .. } => bail!("a move should run in a transaction"),
+        }
+
+        Ok(())
+    }
 }
 
-pub async fn update(table: &str, uuid: &str, data: &str) -> Result<()> {
-    sqlx::query(&format!("UPDATE {table} SET data=? WHERE uuid=?"))
-        .bind(data)
-        .bind(uuid)
-        .execute(&pool().await)
-        .await?;
+#[async_trait]
+impl Storage for SqliteStorage {
+    async fn insert(&self, table: &str, uuid: &str, data: &str) -> Result<()> {
+        self.write(&self.pool, &WriteOp::insert(table, uuid, data))
+            .await
+    }
 
-    Ok(())
-}
+    async fn update(&self, table: &str, uuid: &str, data: &str) -> Result<()> {
+        self.write(&self.pool, &WriteOp::update(table, uuid, data))
+            .await
+    }
 
-pub async fn select(table: &str, uuid: &str) -> Result<ComEntry> {
-    Ok(
-        sqlx::query_as::<_, ComEntry>(&format!("SELECT * FROM {table} WHERE uuid=?"))
-            .bind(uuid)
-            .fetch_one(&pool().await)
-            .await?,
-    )
-}
+    async fn delete(&self, table: &str, uuid: &str) -> Result<()> {
+        self.write(&self.pool, &WriteOp::delete(table, uuid)).await
+    }
 
-pub async fn select_all(table: &str) -> Result<Vec<ComEntry>> {
-    Ok(
-        sqlx::query_as::<_, ComEntry>(&format!("SELECT * FROM {table}"))
-            .fetch_all(&pool().await)
-            .await?,
-    )
-}
+    async fn delete_all(&self, table: &str) -> Result<()> {
+        sqlx::query(&format!("DELETE FROM {table}"))
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
 
-pub async fn row_counts(table: &str) -> Result<i64> {
-    let count: (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) FROM {table}"))
-        .fetch_one(&pool().await)
-        .await?;
+    async fn select(&self, table: &str, uuid: &str) -> Result<ComEntry> {
+        self.crypt.open(
+            sqlx::query_as::<_, ComEntry>(&format!("SELECT uuid, data FROM {table} WHERE uuid=?"))
+                .bind(uuid)
+                .fetch_one(&self.pool)
+                .await?,
+        )
+    }
 
-    Ok(count.0)
-}
+    async fn select_all(&self, table: &str) -> Result<Vec<ComEntry>> {
+        sqlx::query_as::<_, ComEntry>(&format!("SELECT uuid, data FROM {table}"))
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|entry| self.crypt.open(entry))
+            .collect()
+    }
+
+    // The sealed rows have no generated columns, so all the rows of the
+    // encrypted tables are returned
+    async fn select_where(
+        &self,
+        table: &str,
+        condition: &str,
+        binds: &[String],
+    ) -> Result<Vec<ComEntry>> {
+        if self.crypt.is_encrypted(table) {
+            return self.select_all(table).await;
+        }
+
+        let sql = format!("SELECT uuid, data FROM {table} WHERE {condition} ORDER BY id");
+        let mut query = sqlx::query_as::<_, ComEntry>(&sql);
+
+        for bind in binds {
+            query = query.bind(bind);
+        }
+
+        query
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|entry| self.crypt.open(entry))
+            .collect()
+    }
+
+    async fn row_counts(&self, table: &str) -> Result<i64> {
+        let count: (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) FROM {table}"))
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(count.0)
+    }
 
-pub async fn is_exist(table: &str, uuid: &str) -> Result<()> {
-    select(table, uuid).await?;
-    Ok(())
+    // The row is not opened, so it works on the sealed rows without the key
+    async fn is_exist(&self, table: &str, uuid: &str) -> Result<()> {
+        sqlx::query(&format!("SELECT 1 FROM {table} WHERE uuid=?"))
+            .bind(uuid)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn transaction(&self, ops: Vec<WriteOp>) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        for op in ops.iter() {
+            let WriteOp::Move {
+                from,
+                to,
+                uuid,
+                data,
+            } = op
+            else {
+                self.write(&mut *tx, op).await?;
+                continue;
+            };
+
+            let deleted = sqlx::query(&format!("DELETE FROM {from} WHERE uuid=?"))
+                .bind(uuid)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+
+            if deleted == 0 {
+                bail!("`{uuid}` does not exist in {from}");
+            }
+
+            self.write(&mut *tx, &WriteOp::insert(to, uuid, data))
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use once_cell::sync::Lazy;
-    use tokio::sync::Mutex;
 
-    static MTX: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
-    const DB_PATH: &str = "/tmp/entry-db-test.db";
     const TABLE_NAME: &str = "test";
 
-    pub async fn init(db_path: &str) {
-        super::super::create_db(db_path).await.expect("create db");
-        new(TABLE_NAME).await.expect("account table failed");
+    pub async fn init() -> SqliteStorage {
+        let storage = SqliteStorage::open_in_memory().await.expect("open db");
+        storage
+            .new_table(TABLE_NAME)
+            .await
+            .expect("account table failed");
+        storage
     }
 
     #[tokio::test]
     async fn test_table_new() -> Result<()> {
-        let _mtx = MTX.lock().await;
-        init(DB_PATH).await;
+        let storage = init().await;
+        storage.new_table(TABLE_NAME).await?;
         Ok(())
     }
 
     #[tokio::test]
     async fn test_delete_all() -> Result<()> {
-        let _mtx = MTX.lock().await;
-        init(DB_PATH).await;
-        new(TABLE_NAME).await?;
-        delete_all(TABLE_NAME).await?;
+        let storage = init().await;
+        storage.insert(TABLE_NAME, "uuid-1", "data-1").await?;
+        storage.delete_all(TABLE_NAME).await?;
+        assert_eq!(storage.row_counts(TABLE_NAME).await?, 0);
         Ok(())
     }
 
     #[tokio::test]
     async fn test_delete_one() -> Result<()> {
-        let _mtx = MTX.lock().await;
-        init(DB_PATH).await;
-        new(TABLE_NAME).await?;
-
-        delete_all(TABLE_NAME).await?;
-        insert(TABLE_NAME, "uuid-1", "data-1").await?;
-        delete(TABLE_NAME, "uuid-1").await?;
+        let storage = init().await;
+        storage.insert(TABLE_NAME, "uuid-1", "data-1").await?;
+        storage.delete(TABLE_NAME, "uuid-1").await?;
+        assert!(storage.is_exist(TABLE_NAME, "uuid-1").await.is_err());
         Ok(())
     }
 
     #[tokio::test]
     async fn test_insert() -> Result<()> {
-        let _mtx = MTX.lock().await;
-        init(DB_PATH).await;
-        new(TABLE_NAME).await?;
-        delete_all(TABLE_NAME).await?;
-
-        insert(TABLE_NAME, "uuid-1", "data-1").await?;
-        insert(TABLE_NAME, "uuid-2", "data-2").await?;
-
+        let storage = init().await;
+        storage.insert(TABLE_NAME, "uuid-1", "data-1").await?;
+        storage.insert(TABLE_NAME, "uuid-2", "data-2").await?;
+        assert!(storage.insert(TABLE_NAME, "uuid-1", "data").await.is_err());
         Ok(())
     }
 
     #[tokio::test]
     async fn test_update() -> Result<()> {
-        let _mtx = MTX.lock().await;
-        init(DB_PATH).await;
-        new(TABLE_NAME).await?;
-        delete_all(TABLE_NAME).await?;
-
-        insert(TABLE_NAME, "uuid-1", "data-1").await?;
-        update(TABLE_NAME, "uuid-1", "data-1-1").await?;
+        let storage = init().await;
+        storage.insert(TABLE_NAME, "uuid-1", "data-1").await?;
+        storage.update(TABLE_NAME, "uuid-1", "data-1-1").await?;
 
         assert_eq!(
-            select(TABLE_NAME, "uuid-1").await?.data,
+            storage.select(TABLE_NAME, "uuid-1").await?.data,
             "data-1-1".to_string()
         );
 
@@ -155,16 +243,11 @@ mod tests {
 
     #[tokio::test]
     async fn test_select_one() -> Result<()> {
-        let _mtx = MTX.lock().await;
+        let storage = init().await;
+        assert!(storage.select(TABLE_NAME, "uuid-1").await.is_err());
 
-        init(DB_PATH).await;
-        new(TABLE_NAME).await?;
-        delete_all(TABLE_NAME).await?;
-
-        assert!(select(TABLE_NAME, "uuid-1").await.is_err());
-
-        insert(TABLE_NAME, "uuid-1", "data-1").await?;
-        let item = select(TABLE_NAME, "uuid-1").await?;
+        storage.insert(TABLE_NAME, "uuid-1", "data-1").await?;
+        let item = storage.select(TABLE_NAME, "uuid-1").await?;
         assert_eq!(item.uuid, "uuid-1");
         assert_eq!(item.data, "data-1");
         Ok(())
@@ -172,16 +255,11 @@ mod tests {
 
     #[tokio::test]
     async fn test_select_all() -> Result<()> {
-        let _mtx = MTX.lock().await;
-
-        init(DB_PATH).await;
-        new(TABLE_NAME).await?;
-        delete_all(TABLE_NAME).await?;
+        let storage = init().await;
+        storage.insert(TABLE_NAME, "uuid-1", "data-1").await?;
+        storage.insert(TABLE_NAME, "uuid-2", "data-2").await?;
 
-        insert(TABLE_NAME, "uuid-1", "data-1").await?;
-        insert(TABLE_NAME, "uuid-2", "data-2").await?;
-
-        let v = select_all(TABLE_NAME).await?;
+        let v = storage.select_all(TABLE_NAME).await?;
         assert_eq!(v[0].uuid, "uuid-1");
         assert_eq!(v[0].data, "data-1");
         assert_eq!(v[1].uuid, "uuid-2");
@@ -191,28 +269,82 @@ mod tests {
 
     #[tokio::test]
     async fn test_row_counts() -> Result<()> {
-        let _mtx = MTX.lock().await;
-        init(DB_PATH).await;
-        new(TABLE_NAME).await?;
-        delete_all(TABLE_NAME).await?;
+        let storage = init().await;
+        assert!(storage.row_counts(TABLE_NAME).await? == 0);
+        storage.insert(TABLE_NAME, "uuid-1", "data-1").await?;
+        assert!(storage.row_counts(TABLE_NAME).await? == 1);
+        Ok(())
+    }
 
-        assert!(row_counts(TABLE_NAME).await? == 0);
-        insert(TABLE_NAME, "uuid-1", "data-1").await?;
-        assert!(row_counts(TABLE_NAME).await? == 1);
+    #[tokio::test]
+    async fn test_is_exist() -> Result<()> {
+        let storage = init().await;
+        storage.insert(TABLE_NAME, "uuid-1", "data-1").await?;
 
+        assert!(storage.is_exist(TABLE_NAME, "uuid-0").await.is_err());
+        assert!(storage.is_exist(TABLE_NAME, "uuid-1").await.is_ok());
         Ok(())
     }
 
     #[tokio::test]
-    async fn test_is_exist() -> Result<()> {
-        let _mtx = MTX.lock().await;
-        init(DB_PATH).await;
-        new(TABLE_NAME).await?;
-        delete_all(TABLE_NAME).await?;
-        insert(TABLE_NAME, "uuid-1", "data-1").await?;
-
-        assert!(is_exist(TABLE_NAME, "uuid-0").await.is_err());
-        assert!(is_exist(TABLE_NAME, "uuid-1").await.is_ok());
+    async fn test_transaction() -> Result<()> {
+        let storage = init().await;
+        storage.new_table("other").await?;
+        storage.insert(TABLE_NAME, "uuid-1", "data-1").await?;
+
+        storage
+            .transaction(vec![
+                WriteOp::delete(TABLE_NAME, "uuid-1"),
+                WriteOp::insert("other", "uuid-1", "data-1"),
+            ])
+            .await?;
+        assert_eq!(storage.row_counts(TABLE_NAME).await?, 0);
+        assert_eq!(storage.select("other", "uuid-1").await?.data, "data-1");
+
+        // The duplicated uuid fails the insert, so the delete is rolled back
+        assert!(
+            storage
+                .transaction(vec![
+                    WriteOp::delete("other", "uuid-1"),
+                    WriteOp::insert(TABLE_NAME, "uuid-2", "data-2"),
+                    WriteOp::insert(TABLE_NAME, "uuid-2", "data-2"),
+                ])
+                .await
+                .is_err()
+        );
+        assert_eq!(storage.row_counts("other").await?, 1);
+        assert_eq!(storage.row_counts(TABLE_NAME).await?, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_move_row() -> Result<()> {
+        let storage = init().await;
+        storage.new_table("other").await?;
+        storage.insert(TABLE_NAME, "uuid-1", "data-1").await?;
+
+        storage
+            .move_row(TABLE_NAME, "other", "uuid-1", "data-1-1")
+            .await?;
+        assert_eq!(storage.row_counts(TABLE_NAME).await?, 0);
+        assert_eq!(storage.select("other", "uuid-1").await?.data, "data-1-1");
+
+        assert!(
+            storage
+                .move_row(TABLE_NAME, "other", "uuid-1", "data")
+                .await
+                .is_err()
+        );
+
+        // The row stays in `from` if it already exists in `to`
+        storage.insert(TABLE_NAME, "uuid-1", "data-1").await?;
+        assert!(
+            storage
+                .move_row(TABLE_NAME, "other", "uuid-1", "data")
+                .await
+                .is_err()
+        );
+        assert!(storage.is_exist(TABLE_NAME, "uuid-1").await.is_ok());
         Ok(())
     }
 }
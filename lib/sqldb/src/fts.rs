@@ -0,0 +1,158 @@
+// Full text search tables of the entry tables. The index of `table` lives in
+// `{table}_fts` and uses the rowid of the indexed row. The trigram tokenizer
+// makes the search case-insensitive and able to find the substrings of texts
+// without spaces between words, e.g. Chinese.
+
+pub fn table_name(table: &str) -> String {
+    format!("{table}_fts")
+}
+
+// Creates the index of `table` and the triggers keeping it in sync. `columns`
+// are the indexed column names and the sql expressions computing them, in
+// which `{data}` is replaced by the `data` column of the indexed row. The rows
+// with invalid json are not indexed.
+pub fn create_sqls(table: &str, columns: &[(&str, &str)]) -> Vec<String> {
+    let fts = table_name(table);
+    let names = columns
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let exprs = |data: &str| {
+        columns
+            .iter()
+            .map(|(_, expr)| expr.replace("{data}", data))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let insert_new = format!(
+        "INSERT INTO {fts} (rowid, {names}) SELECT NEW.id, {} WHERE json_valid(NEW.data);",
+        exprs("NEW.data")
+    );
+    let delete_old = format!("DELETE FROM {fts} WHERE rowid = OLD.id;");
+
+    vec![
+        format!(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS {fts} USING fts5({names}, tokenize = 'trigram')"
+        ),
+        format!(
+            "INSERT INTO {fts} (rowid, {names}) SELECT id, {} FROM {table} WHERE json_valid(data)",
+            exprs("data")
+        ),
+        format!(
+            "CREATE TRIGGER IF NOT EXISTS {fts}_insert AFTER INSERT ON {table}
+             BEGIN {insert_new} END"
+        ),
+        format!(
+            "CREATE TRIGGER IF NOT EXISTS {fts}_delete AFTER DELETE ON {table}
+             BEGIN {delete_old} END"
+        ),
+        format!(
+            "CREATE TRIGGER IF NOT EXISTS {fts}_update AFTER UPDATE ON {table}
+             BEGIN {delete_old} {insert_new} END"
+        ),
+    ]
+}
+
+// Condition of the rows of `table` containing `text` in any indexed column.
+// The trigram index only works for the texts with at least 3 characters, the
+// shorter ones fall back to `LIKE`. Returns the condition and its bind values.
+pub fn contains_condition(table: &str, columns: &[&str], text: &str) -> (String, Vec<String>) {
+    let fts = table_name(table);
+
+    if text.chars().count() >= 3 {
+        (
+            format!("id IN (SELECT rowid FROM {fts} WHERE {fts} MATCH ?)"),
+            vec![format!("\"{}\"", text.replace('"', "\"\""))],
+        )
+    } else {
+        let pattern = format!(
+            "%{}%",
+            text.replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        );
+
+        let likes = columns
+            .iter()
+            .map(|column| format!("{column} LIKE ? ESCAPE '\\'"))
+            .collect::<Vec<_>>()
+            .join(" OR ");
+
+        (
+            format!("id IN (SELECT rowid FROM {fts} WHERE {likes})"),
+            vec![pattern; columns.len()],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SqliteStorage, Storage};
+    use anyhow::Result;
+
+    const TABLE_NAME: &str = "fts_test";
+    const COLUMNS: [(&str, &str); 2] = [
+        ("title", "json_extract({data}, '$.title')"),
+        (
+            "tags",
+            "(SELECT group_concat(value, ' ') FROM json_each({data}, '$.tags'))",
+        ),
+    ];
+
+    async fn search(storage: &SqliteStorage, text: &str) -> Result<Vec<String>> {
+        let (condition, binds) = contains_condition(TABLE_NAME, &["title", "tags"], text);
+
+        Ok(storage
+            .select_where(TABLE_NAME, &condition, &binds)
+            .await?
+            .into_iter()
+            .map(|item| item.uuid)
+            .collect())
+    }
+
+    #[tokio::test]
+    async fn test_search() -> Result<()> {
+        let storage = SqliteStorage::open_in_memory().await?;
+        storage.new_table(TABLE_NAME).await?;
+        storage
+            .insert(
+                TABLE_NAME,
+                "uuid-1",
+                r#"{"title": "Learn Rust", "tags": ["work"]}"#,
+            )
+            .await?;
+        storage.insert(TABLE_NAME, "uuid-2", "not json").await?;
+
+        for sql in create_sqls(TABLE_NAME, &COLUMNS) {
+            sqlx::query(&sql).execute(storage.pool()).await?;
+        }
+
+        storage
+            .insert(
+                TABLE_NAME,
+                "uuid-3",
+                r#"{"title": "学习深度学习", "tags": ["学习"]}"#,
+            )
+            .await?;
+
+        assert_eq!(search(&storage, "RUST").await?, vec!["uuid-1"]);
+        assert_eq!(search(&storage, "深度学").await?, vec!["uuid-3"]);
+        assert_eq!(search(&storage, "学习").await?, vec!["uuid-3"]);
+        assert!(search(&storage, "json").await?.is_empty());
+
+        storage
+            .update(TABLE_NAME, "uuid-1", r#"{"title": "Learn Go"}"#)
+            .await?;
+        assert!(search(&storage, "rust").await?.is_empty());
+        assert!(search(&storage, "wo").await?.is_empty());
+
+        storage.delete(TABLE_NAME, "uuid-3").await?;
+        assert!(search(&storage, "学习").await?.is_empty());
+
+        Ok(())
+    }
+}
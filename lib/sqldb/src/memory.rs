@@ -0,0 +1,246 @@
+use super::storage::{ComEntry, Storage, WriteOp};
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+use std::{collections::HashMap, sync::Mutex};
+
+type Tables = HashMap<String, Vec<ComEntry>>;
+
+// Keeps the tables in memory, e.g. for the tests. The tables are created on
+// their first insert and nothing is persisted.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    tables: Mutex<Tables>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+fn apply(tables: &mut Tables, op: WriteOp) -> Result<()> {
+    match op {
+        WriteOp::Insert { table, uuid, data } => {
+            let items = tables.entry(table.clone()).or_default();
+            if items.iter().any(|item| item.uuid == uuid) {
+                bail!("`{uuid}` already exists in {table}");
+            }
+
+            items.push(ComEntry { uuid, data });
+        }
+        WriteOp::Update { table, uuid, data } => {
+            if let Some(item) = tables
+                .get_mut(&table)
+                .and_then(|items| items.iter_mut().find(|item| item.uuid == uuid))
+            {
+                item.data = data;
+            }
+        }
+        WriteOp::Delete { table, uuid } => {
+            if let Some(items) = tables.get_mut(&table) {
+                items.retain(|item| item.uuid != uuid);
+            }
+        }
+        WriteOp::Move {
+            from,
+            to,
+            uuid,
+            data,
+        } => {
+            let Some(index) = tables
+                .get(&from)
+                .and_then(|items| items.iter().position(|item| item.uuid == uuid))
+            else {
+                bail!("`{uuid}` does not exist in {from}");
+            };
+
+            tables.get_mut(&from).unwrap().remove(index);
+            apply(
+                tables,
+                WriteOp::Insert {
+                    table: to,
+                    uuid,
+                    data,
+                },
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+#[async_trait]
+impl Storage for MemoryStorage {
+    async fn insert(&self, table: &str, uuid: &str, data: &str) -> Result<()> {
+        apply(
+            &mut self.tables.lock().unwrap(),
+            WriteOp::insert(table, uuid, data),
+        )
+    }
+
+    async fn update(&self, table: &str, uuid: &str, data: &str) -> Result<()> {
+        apply(
+            &mut self.tables.lock().unwrap(),
+            WriteOp::update(table, uuid, data),
+        )
+    }
+
+    async fn delete(&self, table: &str, uuid: &str) -> Result<()> {
+        apply(
+            &mut self.tables.lock().unwrap(),
+            WriteOp::delete(table, uuid),
+        )
+    }
+
+    async fn delete_all(&self, table: &str) -> Result<()> {
+        self.tables.lock().unwrap().remove(table);
+        Ok(())
+    }
+
+    async fn select(&self, table: &str, uuid: &str) -> Result<ComEntry> {
+        self.tables
+            .lock()
+            .unwrap()
+            .get(table)
+            .and_then(|items| items.iter().find(|item| item.uuid == uuid))
+            .cloned()
+            .ok_or(anyhow!("`{uuid}` does not exist in {table}"))
+    }
+
+    async fn select_all(&self, table: &str) -> Result<Vec<ComEntry>> {
+        Ok(self
+            .tables
+            .lock()
+            .unwrap()
+            .get(table)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn select_where(
+        &self,
+        table: &str,
+        _condition: &str,
+        _binds: &[String],
+    ) -> Result<Vec<ComEntry>> {
+        self.select_all(table).await
+    }
+
+    async fn row_counts(&self, table: &str) -> Result<i64> {
+        Ok(self
+            .tables
+            .lock()
+            .unwrap()
+            .get(table)
+            .map(|items| items.len() as i64)
+            .unwrap_or_default())
+    }
+
+    async fn is_exist(&self, table: &str, uuid: &str) -> Result<()> {
+        self.select(table, uuid).await.map(|_| ())
+    }
+
+    // The ops are applied to a copy, which replaces the tables on success
+    async fn transaction(&self, ops: Vec<WriteOp>) -> Result<()> {
+        let mut tables = self.tables.lock().unwrap();
+        let mut copy = tables.clone();
+
+        for op in ops {
+            apply(&mut copy, op)?;
+        }
+
+        *tables = copy;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TABLE_NAME: &str = "test";
+
+    #[tokio::test]
+    async fn test_entry() -> Result<()> {
+        let storage = MemoryStorage::new();
+        assert!(storage.select_all(TABLE_NAME).await?.is_empty());
+
+        storage.insert(TABLE_NAME, "uuid-1", "data-1").await?;
+        storage.insert(TABLE_NAME, "uuid-2", "data-2").await?;
+        assert!(storage.insert(TABLE_NAME, "uuid-1", "data").await.is_err());
+
+        storage.update(TABLE_NAME, "uuid-1", "data-1-1").await?;
+        storage.update(TABLE_NAME, "uuid-0", "data-0").await?;
+        assert_eq!(storage.select(TABLE_NAME, "uuid-1").await?.data, "data-1-1");
+        assert!(storage.is_exist(TABLE_NAME, "uuid-0").await.is_err());
+
+        storage.delete(TABLE_NAME, "uuid-1").await?;
+        let items = storage.select_all(TABLE_NAME).await?;
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].uuid, "uuid-2");
+
+        storage.delete_all(TABLE_NAME).await?;
+        assert_eq!(storage.row_counts(TABLE_NAME).await?, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_transaction() -> Result<()> {
+        let storage = MemoryStorage::new();
+        storage.insert(TABLE_NAME, "uuid-1", "data-1").await?;
+
+        storage
+            .transaction(vec![
+                WriteOp::delete(TABLE_NAME, "uuid-1"),
+                WriteOp::insert("other", "uuid-1", "data-1"),
+            ])
+            .await?;
+        assert_eq!(storage.row_counts(TABLE_NAME).await?, 0);
+        assert_eq!(storage.row_counts("other").await?, 1);
+
+        // The duplicated insert fails, so the delete is not applied either
+        assert!(
+            storage
+                .transaction(vec![
+                    WriteOp::delete("other", "uuid-1"),
+                    WriteOp::insert(TABLE_NAME, "uuid-2", "data-2"),
+                    WriteOp::insert(TABLE_NAME, "uuid-2", "data-2"),
+                ])
+                .await
+                .is_err()
+        );
+        assert_eq!(storage.row_counts("other").await?, 1);
+        assert_eq!(storage.row_counts(TABLE_NAME).await?, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_move_row() -> Result<()> {
+        let storage = MemoryStorage::new();
+        storage.insert(TABLE_NAME, "uuid-1", "data-1").await?;
+
+        storage
+            .move_row(TABLE_NAME, "other", "uuid-1", "data-1-1")
+            .await?;
+        assert_eq!(storage.row_counts(TABLE_NAME).await?, 0);
+        assert_eq!(storage.select("other", "uuid-1").await?.data, "data-1-1");
+
+        assert!(
+            storage
+                .move_row(TABLE_NAME, "other", "uuid-1", "data")
+                .await
+                .is_err()
+        );
+
+        // The row stays in `from` if it already exists in `to`
+        storage.insert(TABLE_NAME, "uuid-1", "data-1").await?;
+        assert!(
+            storage
+                .move_row(TABLE_NAME, "other", "uuid-1", "data")
+                .await
+                .is_err()
+        );
+        assert!(storage.is_exist(TABLE_NAME, "uuid-1").await.is_ok());
+        Ok(())
+    }
+}
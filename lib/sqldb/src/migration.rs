@@ -0,0 +1,150 @@
+use super::SqliteStorage;
+use anyhow::{Context, Result, bail};
+
+pub const SCHEMA_VERSION_TABLE: &str = "schema_version";
+
+#[derive(Debug, Clone)]
+pub struct Migration {
+    pub version: i64,
+    pub description: String,
+    pub sqls: Vec<String>,
+}
+
+impl Migration {
+    pub fn new(version: i64, description: impl Into<String>, sqls: Vec<String>) -> Self {
+        Self {
+            version,
+            description: description.into(),
+            sqls,
+        }
+    }
+}
+
+// Applies the migrations newer than the current schema version. Each migration
+// runs in its own transaction, so a failed migration leaves the database at
+// the previous version. Returns the schema version after the run.
+pub async fn run(storage: &SqliteStorage, migrations: &[Migration]) -> Result<i64> {
+    if migrations.windows(2).any(|w| w[0].version >= w[1].version) {
+        bail!("migrations should be ordered by strictly increasing versions");
+    }
+
+    let pool = storage.pool();
+
+    sqlx::query(&format!(
+        "CREATE TABLE IF NOT EXISTS {SCHEMA_VERSION_TABLE} (
+             version INTEGER PRIMARY KEY,
+             description TEXT NOT NULL,
+             applied_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+             )"
+    ))
+    .execute(pool)
+    .await?;
+
+    let mut current_version = version(storage).await?;
+    let pending = migrations
+        .iter()
+        .filter(|m| m.version > current_version)
+        .collect::<Vec<_>>();
+
+    for migration in pending {
+        let mut tx = pool.begin().await?;
+
+        for sql in migration.sqls.iter() {
+            sqlx::query(sql).execute(&mut *tx).await.with_context(|| {
+                format!(
+                    "migration {} ({}) failed",
+                    migration.version, migration.description
+                )
+            })?;
+        }
+
+        sqlx::query(&format!(
+            "INSERT INTO {SCHEMA_VERSION_TABLE} (version, description) VALUES (?, ?)"
+        ))
+        .bind(migration.version)
+        .bind(&migration.description)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        current_version = migration.version;
+    }
+
+    Ok(current_version)
+}
+
+// 0 means no migration has been applied yet
+pub async fn version(storage: &SqliteStorage) -> Result<i64> {
+    let version: (Option<i64>,) =
+        sqlx::query_as(&format!("SELECT MAX(version) FROM {SCHEMA_VERSION_TABLE}"))
+            .fetch_one(storage.pool())
+            .await?;
+
+    Ok(version.0.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Storage, entry};
+
+    const TABLE_NAME: &str = "migration_test";
+
+    async fn init() -> SqliteStorage {
+        SqliteStorage::open_in_memory().await.expect("open db")
+    }
+
+    fn migrations() -> Vec<Migration> {
+        vec![
+            Migration::new(1, "create table", vec![entry::create_table_sql(TABLE_NAME)]),
+            Migration::new(
+                2,
+                "add title column",
+                vec![format!(
+                    "ALTER TABLE {TABLE_NAME} ADD COLUMN title TEXT
+                     GENERATED ALWAYS AS (json_extract(data, '$.title')) VIRTUAL"
+                )],
+            ),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_run() -> Result<()> {
+        let storage = init().await;
+
+        let migrations = migrations();
+        assert_eq!(run(&storage, &migrations[..1]).await?, 1);
+        storage
+            .insert(TABLE_NAME, "uuid-1", r#"{"title": "hello"}"#)
+            .await?;
+
+        assert_eq!(run(&storage, &migrations).await?, 2);
+        assert_eq!(run(&storage, &migrations).await?, 2);
+        assert_eq!(version(&storage).await?, 2);
+
+        let title: (String,) =
+            sqlx::query_as(&format!("SELECT title FROM {TABLE_NAME} WHERE uuid=?"))
+                .bind("uuid-1")
+                .fetch_one(storage.pool())
+                .await?;
+        assert_eq!(title.0, "hello");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_failed() -> Result<()> {
+        let storage = init().await;
+
+        let mut migrations = migrations();
+        migrations[1].sqls.push("INVALID SQL".to_string());
+
+        assert!(run(&storage, &migrations).await.is_err());
+        assert_eq!(version(&storage).await?, 1);
+
+        migrations.swap(0, 1);
+        assert!(run(&storage, &migrations).await.is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+use super::crypt::Crypt;
+use anyhow::Result;
+use sqlx::{
+    Pool,
+    migrate::MigrateDatabase,
+    sqlite::{Sqlite, SqlitePoolOptions},
+};
+use std::sync::Arc;
+
+const MAX_CONNECTIONS: u32 = 3;
+
+// A sqlite database. The clones share the connections and the encryption
+// setting of the database.
+#[derive(Debug, Clone)]
+pub struct SqliteStorage {
+    pub(crate) pool: Pool<Sqlite>,
+    pub(crate) crypt: Arc<Crypt>,
+}
+
+impl SqliteStorage {
+    pub async fn open(db_path: &str) -> Result<Self> {
+        Sqlite::create_database(db_path).await?;
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(MAX_CONNECTIONS)
+            .connect(&format!("sqlite:{}", db_path))
+            .await?;
+
+        Ok(Self::with_pool(pool))
+    }
+
+    // Every connection to `:memory:` opens a new database, so the pool
+    // keeps a single connection for the whole life of the storage
+    pub async fn open_in_memory() -> Result<Self> {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:")
+            .await?;
+
+        Ok(Self::with_pool(pool))
+    }
+
+    fn with_pool(pool: Pool<Sqlite>) -> Self {
+        Self {
+            pool,
+            crypt: Arc::new(Crypt::default()),
+        }
+    }
+
+    pub fn pool(&self) -> &Pool<Sqlite> {
+        &self.pool
+    }
+
+    pub async fn is_table_exist(&self, table_name: &str) -> Result<()> {
+        sqlx::query("SELECT name FROM sqlite_master WHERE type='table' AND name=?")
+            .bind(table_name)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn drop_table(&self, table_name: &str) -> Result<()> {
+        sqlx::query(&format!("DROP TABLE {}", table_name))
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub async fn init() -> SqliteStorage {
+        let storage = SqliteStorage::open_in_memory().await.expect("open db");
+        storage
+            .new_table("test")
+            .await
+            .expect("account table failed");
+        storage
+    }
+
+    #[tokio::test]
+    async fn test_db_is_table_exist() -> Result<()> {
+        let storage = init().await;
+        assert!(storage.is_table_exist("hello").await.is_err());
+        assert!(storage.is_table_exist("test").await.is_ok());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_db_drop_table() -> Result<()> {
+        let storage = init().await;
+        assert!(storage.drop_table("hello").await.is_err());
+        assert!(storage.drop_table("test").await.is_ok());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_db_open() -> Result<()> {
+        let db_path = std::env::temp_dir().join("sqldb-open-test.db");
+        _ = std::fs::remove_file(&db_path);
+
+        let storage = SqliteStorage::open(&db_path.to_string_lossy()).await?;
+        storage.new_table("test").await?;
+        assert!(storage.is_table_exist("test").await.is_ok());
+
+        _ = std::fs::remove_file(&db_path);
+        Ok(())
+    }
+}
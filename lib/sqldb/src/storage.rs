@@ -0,0 +1,114 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+// A row of a table, `data` is the json of the entry
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "sqlite", derive(sqlx::FromRow))]
+pub struct ComEntry {
+    pub uuid: String,
+    pub data: String,
+}
+
+// A write of `Storage::transaction`
+#[derive(Debug, Clone)]
+pub enum WriteOp {
+    Insert {
+        table: String,
+        uuid: String,
+        data: String,
+    },
+    Update {
+        table: String,
+        uuid: String,
+        data: String,
+    },
+    Delete {
+        table: String,
+        uuid: String,
+    },
+
+    // Deletes the row from `from` and inserts it with `data` into `to`. It
+    // fails if the row is not in `from`.
+    Move {
+        from: String,
+        to: String,
+        uuid: String,
+        data: String,
+    },
+}
+
+impl WriteOp {
+    pub fn insert(table: &str, uuid: &str, data: &str) -> Self {
+        Self::Insert {
+            table: table.to_string(),
+            uuid: uuid.to_string(),
+            data: data.to_string(),
+        }
+    }
+
+    pub fn update(table: &str, uuid: &str, data: &str) -> Self {
+        Self::Update {
+            table: table.to_string(),
+            uuid: uuid.to_string(),
+            data: data.to_string(),
+        }
+    }
+
+    pub fn delete(table: &str, uuid: &str) -> Self {
+        Self::Delete {
+            table: table.to_string(),
+            uuid: uuid.to_string(),
+        }
+    }
+
+    pub fn move_row(from: &str, to: &str, uuid: &str, data: &str) -> Self {
+        Self::Move {
+            from: from.to_string(),
+            to: to.to_string(),
+            uuid: uuid.to_string(),
+            data: data.to_string(),
+        }
+    }
+}
+
+// The tables are uuid keyed json rows, so a backend only needs to keep the
+// rows of each table in their insertion order. A backend without sql returns
+// all the rows from `select_where`, the callers always filter the loaded
+// entries again.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    async fn insert(&self, table: &str, uuid: &str, data: &str) -> Result<()>;
+
+    // Nothing is changed if the row does not exist
+    async fn update(&self, table: &str, uuid: &str, data: &str) -> Result<()>;
+
+    async fn delete(&self, table: &str, uuid: &str) -> Result<()>;
+
+    async fn delete_all(&self, table: &str) -> Result<()>;
+
+    async fn select(&self, table: &str, uuid: &str) -> Result<ComEntry>;
+
+    async fn select_all(&self, table: &str) -> Result<Vec<ComEntry>>;
+
+    // `condition` is a sql expression with `?` placeholders for the `binds`
+    async fn select_where(
+        &self,
+        table: &str,
+        condition: &str,
+        binds: &[String],
+    ) -> Result<Vec<ComEntry>>;
+
+    async fn row_counts(&self, table: &str) -> Result<i64>;
+
+    async fn is_exist(&self, table: &str, uuid: &str) -> Result<()>;
+
+    // Applies all the `ops` in order or none of them
+    async fn transaction(&self, ops: Vec<WriteOp>) -> Result<()>;
+
+    // Moves a row between tables in one transaction, see `WriteOp::Move`
+    async fn move_row(&self, from: &str, to: &str, uuid: &str, data: &str) -> Result<()> {
+        self.transaction(vec![WriteOp::move_row(from, to, uuid, data)])
+            .await
+    }
+}
@@ -0,0 +1,6 @@
+#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+#[tokio::main]
+async fn main() {
+    extern crate tasklog;
+    tasklog::cli_main().await;
+}
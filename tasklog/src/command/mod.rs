@@ -0,0 +1,1606 @@
+use crate::{
+    core::{
+        TaskStore, change, field, plan,
+        project::{self, Filter},
+        record,
+        report::{self, Report},
+        search::{self, Query},
+        security, session,
+        sort::{self, SortKey},
+        stats::{self, Aggregate, Period, Range},
+        transfer::{Bundle, Conflict, Format},
+    },
+    db::{
+        WriteOp,
+        def::{
+            ChangeEntry, FieldEntry, FieldKind, ProjectEntry, RecordEntry, RecordPlanEntry,
+            RecordPriority, RecordState, SessionEntry,
+        },
+    },
+};
+use anyhow::{Context, Result, bail};
+use clap::{Parser, Subcommand};
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+const MAX_TAGS: usize = 3;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "tasklog-cli",
+    version,
+    about = "Manage the tasklog records from a terminal.",
+    long_about = None
+)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Add a new record
+    Add {
+        /// Task name
+        title: String,
+
+        /// Start date, `YYYY-MM-DD`. Defaults to today
+        #[arg(short, long)]
+        start: Option<String>,
+
+        /// End date, `YYYY-MM-DD`. Defaults to the day after the start date
+        #[arg(short, long)]
+        end: Option<String>,
+
+        /// Tag of the record, can be repeated up to 3 times
+        #[arg(short, long = "tag")]
+        tags: Vec<String>,
+
+        /// Repeat rule, e.g. `daily`, `weekly:mo,fr`, `monthly:15`, `every:3` or `FREQ=WEEKLY;BYDAY=MO`
+        #[arg(short, long)]
+        repeat: Option<String>,
+
+        /// `none`, `low`, `medium`, `high` or `urgent`
+        #[arg(short, long)]
+        priority: Option<String>,
+
+        /// Effort estimate, e.g. `2h 30m`, `1.5h` or `45m`
+        #[arg(long)]
+        estimate: Option<String>,
+
+        /// Value of a custom field as `name=value`, can be repeated
+        #[arg(long = "field")]
+        fields: Vec<String>,
+
+        /// Uuid prefix of a record which should be finished first, can be repeated
+        #[arg(short, long = "blocked-by")]
+        blocked_by: Vec<String>,
+
+        /// Name or uuid prefix of the project of the record
+        #[arg(long)]
+        project: Option<String>,
+    },
+
+    /// List records
+    Ls {
+        /// List the archived records instead
+        #[arg(short, long)]
+        archive: bool,
+
+        /// `newest`, `priority`, `due`, `start`, `title`, `estimate` or the name of a custom field
+        #[arg(long, default_value = "newest")]
+        sort: String,
+
+        /// Print records as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// List the records of a project only, `none` for the records without a project
+        #[arg(long)]
+        project: Option<String>,
+    },
+
+    /// Update the title, dates or tags of a record
+    Update {
+        /// Record uuid or an unique prefix of it
+        uuid: String,
+
+        #[arg(long)]
+        title: Option<String>,
+
+        #[arg(short, long)]
+        start: Option<String>,
+
+        #[arg(short, long)]
+        end: Option<String>,
+
+        /// Replace the tags of the record
+        #[arg(short, long = "tag")]
+        tags: Vec<String>,
+
+        /// Replace the repeat rule of the record, an empty rule stops repeating
+        #[arg(short, long)]
+        repeat: Option<String>,
+
+        /// `none`, `low`, `medium`, `high` or `urgent`
+        #[arg(short, long)]
+        priority: Option<String>,
+
+        /// Effort estimate, e.g. `2h 30m`. An empty estimate removes it
+        #[arg(long)]
+        estimate: Option<String>,
+
+        /// Set a custom field as `name=value`, an empty value removes it. Can be repeated
+        #[arg(long = "field")]
+        fields: Vec<String>,
+
+        /// Add a record which should be finished first, can be repeated
+        #[arg(short, long = "blocked-by")]
+        blocked_by: Vec<String>,
+
+        /// Remove a "blocked by" link, can be repeated
+        #[arg(long)]
+        unblock: Vec<String>,
+
+        /// Move the record to a project, an empty name removes it from its project
+        #[arg(long)]
+        project: Option<String>,
+    },
+
+    /// Move a record to the running state
+    Start { uuid: String },
+
+    /// Move a record to the finished state
+    Finish { uuid: String },
+
+    /// Move a record to the giveup state
+    Giveup { uuid: String },
+
+    /// Move a record to the archive
+    Archive {
+        uuid: String,
+
+        #[command(flatten)]
+        links: LinkArgs,
+    },
+
+    /// Move an archived record back to the record list
+    Recover { uuid: String },
+
+    /// Move a record to the trash
+    Rm {
+        uuid: String,
+
+        /// Remove an archived record instead
+        #[arg(short, long)]
+        archive: bool,
+
+        #[command(flatten)]
+        links: LinkArgs,
+    },
+
+    /// Show the changes of a record, oldest first
+    Log {
+        uuid: String,
+
+        /// Show the changes of an archived record instead
+        #[arg(short, long)]
+        archive: bool,
+    },
+
+    /// Search records and archives, e.g. `tag:work state:running priority:high due<2026-11-01 "exact phrase"`
+    Search {
+        /// Words, quoted phrases, `tag:x`, `state:x`, `priority:x`, `due<date`, `start>=date`,
+        /// `estimate<=2h` and `field:name=value`
+        #[arg(required = true)]
+        query: Vec<String>,
+
+        /// Search the archived records only
+        #[arg(short, long)]
+        archive: bool,
+
+        /// Print records as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Export the records and archives as JSON, CSV, a Markdown checklist or iCalendar todos
+    Export {
+        /// `json`, `csv`, `md` or `ics`. Defaults to the extension of the output file, or `json`
+        #[arg(short, long)]
+        format: Option<String>,
+
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Import records and archives from a file, `-` for stdin
+    Import {
+        file: PathBuf,
+
+        /// `json`, `csv`, `md` or `ics`. Defaults to the extension of the file, or `json`
+        #[arg(short, long)]
+        format: Option<String>,
+
+        /// What to do with an entry whose uuid already exists: `skip`, `overwrite` or `duplicate`.
+        /// Defaults to `overwrite` for `ics` files and `skip` for the others
+        #[arg(short, long)]
+        conflict: Option<String>,
+    },
+
+    /// Write a status report of a period as Markdown or a self-contained HTML page
+    Report {
+        /// `week`, `month`, `90days` or `all`. Defaults to `week`
+        #[arg(short, long, conflicts_with_all = ["from", "to"])]
+        range: Option<String>,
+
+        /// The first day of a custom period, `YYYY-MM-DD`. Defaults to the first record
+        #[arg(long)]
+        from: Option<String>,
+
+        /// The last day of a custom period, `YYYY-MM-DD`. Defaults to today
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Report the records of a project only, `none` for the records without a project
+        #[arg(short, long)]
+        project: Option<String>,
+
+        /// `md` or `html`. Defaults to the extension of the output file, or `md`
+        #[arg(short, long)]
+        format: Option<String>,
+
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Print the count and the days spent of the records and archives by state, tag, week or month
+    Stats {
+        /// `state`, `tag`, `week` or `month`
+        #[arg(long, default_value = "state")]
+        by: String,
+
+        /// Count the records of a project only, `none` for the records without a project
+        #[arg(short, long)]
+        project: Option<String>,
+    },
+
+    /// Manage the projects, archiving a project archives all its records
+    Project {
+        #[command(subcommand)]
+        command: ProjectCommand,
+    },
+
+    /// Manage the custom fields of the database
+    Field {
+        #[command(subcommand)]
+        command: FieldCommand,
+    },
+
+    /// Manage the plan of a record
+    Plan {
+        #[command(subcommand)]
+        command: PlanCommand,
+    },
+
+    /// List, restore or permanently delete the removed records
+    Trash {
+        #[command(subcommand)]
+        command: TrashCommand,
+    },
+
+    /// Inspect, repair or export the entries which can not be loaded
+    Corrupt {
+        #[command(subcommand)]
+        command: CorruptCommand,
+    },
+
+    /// Track the time spent on the records
+    Timer {
+        #[command(subcommand)]
+        command: TimerCommand,
+    },
+
+    /// Manage the key of the secrets and the database encryption.
+    /// Set `TASKLOG_PASSWORD` to unlock a master password
+    Secret {
+        #[command(subcommand)]
+        command: SecretCommand,
+    },
+}
+
+// What to do with the records blocked by an archived or removed record. It
+// fails if neither is given and such records exist.
+#[derive(clap::Args, Debug)]
+#[group(multiple = false)]
+struct LinkArgs {
+    /// Remove the links of the records blocked by it
+    #[arg(long)]
+    unlink: bool,
+
+    /// Keep the links, they do not block until the record is back
+    #[arg(long)]
+    keep_links: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum SecretCommand {
+    /// Show the key and the encryption setting
+    Status,
+
+    /// Set a master password, which is read from the first line of stdin
+    Password,
+
+    /// Use a key file instead of the master password. It is created if it does not exist
+    KeyFile {
+        /// Defaults to the `.key` file next to the config file
+        path: Option<String>,
+    },
+
+    /// Encrypt the data of the records and archives
+    EncryptDb {
+        /// `on` or `off`
+        #[arg(value_parser = ["on", "off"])]
+        value: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum TimerCommand {
+    /// Start the timer of a record. The running timer is stopped first
+    Start {
+        uuid: String,
+
+        /// Note of the work session
+        #[arg(short, long, default_value = "")]
+        note: String,
+    },
+
+    /// Stop the running timer
+    Stop,
+
+    /// Show the running timer
+    Status,
+
+    /// List the work sessions of a record
+    Ls {
+        uuid: String,
+
+        /// List the sessions of an archived record instead
+        #[arg(short, long)]
+        archive: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ProjectCommand {
+    /// List the projects with the count of their records and archives
+    Ls,
+
+    /// Add a project
+    Add {
+        name: String,
+
+        /// `#rrggbb` or `blue`, `green`, `orange`, `red`, `purple`, `gray`. Defaults to blue
+        #[arg(short, long, default_value = "")]
+        color: String,
+
+        #[arg(short, long, default_value = "")]
+        description: String,
+    },
+
+    /// Rename a project or change its color or description
+    Edit {
+        /// Name or uuid prefix of the project
+        project: String,
+
+        #[arg(short, long)]
+        name: Option<String>,
+
+        #[arg(short, long)]
+        color: Option<String>,
+
+        #[arg(short, long)]
+        description: Option<String>,
+    },
+
+    /// Remove a project. Its records and archives are kept without a project
+    Rm { project: String },
+
+    /// Move a project with all its records to the archive
+    Archive { project: String },
+
+    /// Move an archived project with all its records back
+    Recover { project: String },
+}
+
+#[derive(Subcommand, Debug)]
+enum FieldCommand {
+    /// List the custom fields
+    Ls,
+
+    /// Add a custom field
+    Add {
+        name: String,
+
+        /// `text`, `number`, `date` or `select`
+        #[arg(short, long, default_value = "text", value_parser = ["text", "number", "date", "select"])]
+        kind: String,
+
+        /// Option of a select field, can be repeated
+        #[arg(short, long = "option")]
+        options: Vec<String>,
+    },
+
+    /// Remove a custom field. The values of the records are kept
+    Rm { name: String },
+}
+
+#[derive(Subcommand, Debug)]
+enum PlanCommand {
+    /// Append a plan step to a record
+    Add {
+        uuid: String,
+        detail: String,
+
+        /// Add it as the last sub-step of this step. The index starts from 1
+        #[arg(long)]
+        under: Option<usize>,
+
+        #[command(flatten)]
+        meta: PlanMetaArgs,
+    },
+
+    /// List the plan steps of a record
+    Ls { uuid: String },
+
+    /// Change the detail, due date, estimate or note of a plan step. The index starts from 1
+    Edit {
+        uuid: String,
+        index: usize,
+
+        #[arg(long)]
+        detail: Option<String>,
+
+        #[command(flatten)]
+        meta: PlanMetaArgs,
+    },
+
+    /// Make a plan step a sub-step of the step before it. The index starts from 1
+    Indent { uuid: String, index: usize },
+
+    /// Move a plan step one level up. The index starts from 1
+    Outdent { uuid: String, index: usize },
+
+    /// Mark a plan step with its sub-steps as finished. The index starts from 1
+    Done { uuid: String, index: usize },
+
+    /// Mark a plan step with its sub-steps as unfinished. The index starts from 1
+    Undone { uuid: String, index: usize },
+
+    /// Remove a plan step with its sub-steps. The index starts from 1
+    Rm { uuid: String, index: usize },
+}
+
+// An empty value removes the due date, estimate or note of a plan step
+#[derive(clap::Args, Debug)]
+struct PlanMetaArgs {
+    /// Due date of the step, `YYYY-MM-DD`
+    #[arg(long)]
+    due: Option<String>,
+
+    /// Effort estimate of the step, e.g. `2h 30m`. It weights the progress of the record
+    #[arg(long)]
+    estimate: Option<String>,
+
+    #[arg(long)]
+    note: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum TrashCommand {
+    /// List the removed records, newest first
+    Ls {
+        /// Print the entries as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Move a removed record back to the records or archives
+    Restore { uuid: String },
+
+    /// Delete a removed record and its work sessions permanently
+    Rm { uuid: String },
+
+    /// Delete all the removed records permanently
+    Clear,
+
+    /// Delete the records removed more than `days` ago
+    Purge { days: u32 },
+}
+
+#[derive(Subcommand, Debug)]
+enum CorruptCommand {
+    /// List the corrupt entries
+    Ls,
+
+    /// Print the raw data of a corrupt entry
+    Show { id: String },
+
+    /// Print the corrupt entries as JSON
+    Export {
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Restore a corrupt entry with the fixed JSON read from a file, `-` for stdin
+    Repair { id: String, file: PathBuf },
+
+    /// Restore the corrupt entries which can be loaded now
+    Retry,
+
+    /// Remove a corrupt entry permanently
+    Rm { id: String },
+}
+
+pub async fn run() -> Result<()> {
+    let store = TaskStore::new();
+
+    match Args::parse().command {
+        Command::Add {
+            title,
+            start,
+            end,
+            tags,
+            repeat,
+            priority,
+            estimate,
+            fields,
+            blocked_by,
+            project,
+        } => {
+            let start_date = match start {
+                Some(date) => check_date(date)?,
+                None => record::today(),
+            };
+
+            let end_date = match end {
+                Some(date) => check_date(date)?,
+                None => cutil::time::date_str_add_days(&start_date, 1)?,
+            };
+
+            let mut entry = RecordEntry {
+                start_date,
+                end_date,
+                title,
+                tags: check_tags(tags)?,
+                state: RecordState::Running,
+                recurrence: repeat.unwrap_or_default(),
+                priority: check_priority(priority)?.unwrap_or_default(),
+                estimate_seconds: session::parse_duration(&estimate.unwrap_or_default())?,
+                ..Default::default()
+            };
+            check_dates(&entry)?;
+            set_fields(&mut entry, fields)?;
+            for uuid in blocked_by {
+                entry.blocked_by.push(find_link(&store, &uuid).await?);
+            }
+
+            if let Some(project) = project {
+                entry.project = store.find_project(&project).await?.uuid;
+            }
+
+            print_entry(&store.add(entry).await?);
+        }
+        Command::Ls {
+            archive,
+            sort,
+            json,
+            project,
+        } => {
+            let mut entries = if archive {
+                store.archives().await?
+            } else {
+                store.records().await?
+            };
+
+            if let Some(project) = project {
+                let projects = store.projects().await?;
+                let filter = project_filter(&projects, &project)?;
+                entries.retain(|entry| filter.matches(entry, &projects));
+            }
+            sort::sort(&mut entries, &SortKey::parse(&sort), &store.fields().await?);
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&entries)?);
+            } else {
+                entries.iter().for_each(print_entry);
+            }
+        }
+        Command::Update {
+            uuid,
+            title,
+            start,
+            end,
+            tags,
+            repeat,
+            priority,
+            estimate,
+            fields,
+            blocked_by,
+            unblock,
+            project,
+        } => {
+            let mut entry = store.find_record(&uuid).await?;
+
+            if let Some(title) = title {
+                entry.title = title;
+            }
+
+            if let Some(date) = start {
+                entry.start_date = check_date(date)?;
+            }
+
+            if let Some(date) = end {
+                entry.end_date = check_date(date)?;
+            }
+
+            if !tags.is_empty() {
+                entry.tags = check_tags(tags)?;
+            }
+
+            if let Some(repeat) = repeat {
+                entry.recurrence = repeat;
+            }
+
+            if let Some(priority) = check_priority(priority)? {
+                entry.priority = priority;
+            }
+
+            if let Some(estimate) = estimate {
+                entry.estimate_seconds = session::parse_duration(&estimate)?;
+            }
+
+            check_dates(&entry)?;
+            set_fields(&mut entry, fields)?;
+
+            for uuid in blocked_by {
+                entry.blocked_by.push(find_link(&store, &uuid).await?);
+            }
+
+            // A link to a removed record can only be matched by its uuid prefix
+            for prefix in unblock {
+                let count = entry.blocked_by.len();
+                entry.blocked_by.retain(|item| !item.starts_with(&prefix));
+
+                if entry.blocked_by.len() == count {
+                    bail!("`{}` is not blocked by `{prefix}`", entry.title);
+                }
+            }
+
+            match project {
+                Some(project) if project.trim().is_empty() => entry.project.clear(),
+                Some(project) => entry.project = store.find_project(&project).await?.uuid,
+                None => (),
+            }
+
+            print_entry(&store.update(entry).await?);
+        }
+        Command::Start { uuid } => change_state(&store, &uuid, RecordState::Running).await?,
+        Command::Finish { uuid } => change_state(&store, &uuid, RecordState::Finished).await?,
+        Command::Giveup { uuid } => change_state(&store, &uuid, RecordState::Giveup).await?,
+        Command::Archive { uuid, links } => {
+            let mut entry = store.find_record(&uuid).await?;
+            let (unlinked, mut ops) = resolve_links(&store, &entry, &links).await?;
+            let (next, next_ops) = TaskStore::recur(&mut entry)?;
+            ops.extend(next_ops);
+
+            let entry = store.archive(entry, ops).await?;
+            print_unlinked(&unlinked);
+            print_entry(&entry);
+            print_next_occurrence(next.as_ref());
+        }
+        Command::Recover { uuid } => {
+            let entry = store.find_archive(&uuid).await?;
+            print_entry(&store.recover(entry).await?);
+        }
+        Command::Rm {
+            uuid,
+            archive,
+            links,
+        } => {
+            let (entry, unlinked) = if archive {
+                let entry = store.find_archive(&uuid).await?;
+                let (unlinked, ops) = resolve_links(&store, &entry, &links).await?;
+                store.remove_archive(&entry.uuid, ops).await?;
+                (entry, unlinked)
+            } else {
+                let entry = store.find_record(&uuid).await?;
+                let (unlinked, ops) = resolve_links(&store, &entry, &links).await?;
+                store.remove(&entry.uuid, ops).await?;
+                (entry, unlinked)
+            };
+
+            print_unlinked(&unlinked);
+            print_entry(&entry);
+        }
+        Command::Log { uuid, archive } => {
+            let entry = if archive {
+                store.find_archive(&uuid).await?
+            } else {
+                store.find_record(&uuid).await?
+            };
+
+            let changes = store.changes(&entry.uuid).await?;
+            let projects = store.projects().await?;
+            changes
+                .iter()
+                .for_each(|item| print_change(item, &projects));
+
+            println!(
+                "deadline extensions: {}",
+                changes
+                    .iter()
+                    .filter(|item| change::is_deadline_extension(item))
+                    .count()
+            );
+        }
+        Command::Search {
+            query,
+            archive,
+            json,
+        } => {
+            let query = Query::parse(&query.join(" "))?;
+            let records = if archive {
+                vec![]
+            } else {
+                store.search_records(&query).await?
+            };
+            let archives = store.search_archives(&query).await?;
+
+            if json {
+                let entries = serde_json::json!({ "records": records, "archives": archives });
+                println!("{}", serde_json::to_string_pretty(&entries)?);
+            } else {
+                records.iter().for_each(print_entry);
+
+                if !archives.is_empty() {
+                    if !records.is_empty() {
+                        println!();
+                    }
+                    println!("archive:");
+                    archives.iter().for_each(print_entry);
+                }
+            }
+        }
+        Command::Export { format, output } => {
+            let format = check_format(format, output.as_deref())?;
+            let data = store.export().await?.export(format)?;
+
+            match output {
+                Some(path) => std::fs::write(&path, data)
+                    .with_context(|| format!("write `{}` failed", path.display()))?,
+                None => print!("{data}"),
+            }
+        }
+        Command::Import {
+            file,
+            format,
+            conflict,
+        } => {
+            let format = check_format(format, Some(&file))?;
+            let conflict = match conflict {
+                Some(conflict) => Conflict::parse(&conflict)?,
+                None => format.default_conflict(),
+            };
+            let bundle = Bundle::import(&read_input(&file)?, format)?;
+            let summary = store.import(bundle, conflict).await?;
+
+            println!(
+                "added {}, overwritten {}, skipped {}",
+                summary.added, summary.overwritten, summary.skipped
+            );
+        }
+        Command::Report {
+            range,
+            from,
+            to,
+            project,
+            format,
+            output,
+        } => {
+            let range = if from.is_some() || to.is_some() {
+                let (from, to) = (
+                    from.map(check_date).transpose()?.unwrap_or_default(),
+                    to.map(check_date).transpose()?.unwrap_or_default(),
+                );
+                if !from.is_empty() && !to.is_empty() && from > to {
+                    bail!("the last day should not be before the first day");
+                }
+                Range::Custom(from, to)
+            } else {
+                check_range(range)?
+            };
+
+            let mut entries = store.statistic_entries().await?;
+            if let Some(project) = project {
+                let projects = store.projects().await?;
+                let filter = project_filter(&projects, &project)?;
+                entries.retain(|entry| filter.matches(entry, &projects));
+            }
+
+            let today = chrono::Local::now().date_naive();
+            let report = Report::new(
+                &entries,
+                &store.all_sessions().await?,
+                report::period(&range, &entries, today),
+                today,
+                cutil::time::timestamp(),
+            );
+
+            let format = match format {
+                Some(format) => report::Format::parse(&format)?,
+                None => output
+                    .as_deref()
+                    .and_then(report::Format::from_path)
+                    .unwrap_or_default(),
+            };
+            let data = report.render(format, &report::default_color);
+
+            match output {
+                Some(path) => std::fs::write(&path, data)
+                    .with_context(|| format!("write `{}` failed", path.display()))?,
+                None => print!("{data}"),
+            }
+        }
+        Command::Stats { by, project } => {
+            let mut entries = store.statistic_entries().await?;
+            if let Some(project) = project {
+                let projects = store.projects().await?;
+                let filter = project_filter(&projects, &project)?;
+                entries.retain(|entry| filter.matches(entry, &projects));
+            }
+
+            let now = cutil::time::timestamp();
+            let by_period = |period| {
+                stats::by_period(&entries, period, now)
+                    .into_iter()
+                    .map(|(start, item)| (start.format(record::DATE_FORMAT).to_string(), item))
+                    .collect::<Vec<_>>()
+            };
+
+            let items = match by.to_lowercase().as_str() {
+                "state" => stats::by_state(&entries, now)
+                    .into_iter()
+                    .map(|(state, item)| (state.as_str().to_string(), item))
+                    .collect::<Vec<_>>(),
+                "tag" => stats::by_tag(&entries, now)
+                    .into_iter()
+                    .map(|(tag, item)| {
+                        let tag = if tag.is_empty() {
+                            "no tag".to_string()
+                        } else {
+                            tag
+                        };
+                        (tag, item)
+                    })
+                    .collect(),
+                "week" => by_period(Period::Week),
+                "month" => by_period(Period::Month),
+                _ => bail!("unknown group `{by}`, expect state, tag, week or month"),
+            };
+
+            println!(
+                "{:<16}  {:>5}  {:>6}  {:>6}  {:>6}  {:>6}  {:>8}",
+                "", "count", "days", "mean", "median", "p90", "tracked"
+            );
+            for (name, item) in items.iter() {
+                print_aggregate(name, item);
+            }
+            print_aggregate("total", &stats::aggregate(&entries, now));
+        }
+        Command::Project { command } => run_project(&store, command).await?,
+        Command::Field { command } => run_field(&store, command).await?,
+        Command::Plan { command } => run_plan(&store, command).await?,
+        Command::Trash { command } => run_trash(&store, command).await?,
+        Command::Corrupt { command } => run_corrupt(&store, command).await?,
+        Command::Timer { command } => run_timer(&store, command).await?,
+        Command::Secret { command } => run_secret(command).await?,
+    }
+
+    Ok(())
+}
+
+async fn run_project(store: &TaskStore, command: ProjectCommand) -> Result<()> {
+    match command {
+        ProjectCommand::Ls => {
+            let (records, archives) = (store.records().await?, store.archives().await?);
+            for entry in store.projects().await? {
+                let count = |entries: &[RecordEntry]| {
+                    entries
+                        .iter()
+                        .filter(|item| item.project == entry.uuid)
+                        .count()
+                };
+
+                print_project(&entry, count(&records), count(&archives));
+            }
+        }
+        ProjectCommand::Add {
+            name,
+            color,
+            description,
+        } => {
+            let entry = ProjectEntry {
+                name,
+                color,
+                description,
+                ..Default::default()
+            };
+
+            println!("{}", store.add_project(entry).await?.uuid);
+        }
+        ProjectCommand::Edit {
+            project,
+            name,
+            color,
+            description,
+        } => {
+            let mut entry = store.find_project(&project).await?;
+
+            if let Some(name) = name {
+                entry.name = name;
+            }
+
+            if let Some(color) = color {
+                entry.color = color;
+            }
+
+            if let Some(description) = description {
+                entry.description = description;
+            }
+
+            let entry = store.update_project(entry).await?;
+            println!("{}  {}  {}", entry.uuid, entry.name, entry.color);
+        }
+        ProjectCommand::Rm { project } => {
+            let entry = store.find_project(&project).await?;
+            let count = store.remove_project(&entry.uuid).await?;
+            println!(
+                "removed `{}`, {count} records left without a project",
+                entry.name
+            );
+        }
+        ProjectCommand::Archive { project } => {
+            let entry = store.find_project(&project).await?;
+            store
+                .archive_project(&entry.uuid)
+                .await?
+                .iter()
+                .for_each(print_entry);
+        }
+        ProjectCommand::Recover { project } => {
+            let entry = store.find_project(&project).await?;
+            store
+                .recover_project(&entry.uuid)
+                .await?
+                .iter()
+                .for_each(print_entry);
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_field(store: &TaskStore, command: FieldCommand) -> Result<()> {
+    match command {
+        FieldCommand::Ls => store.fields().await?.iter().for_each(print_field),
+        FieldCommand::Add {
+            name,
+            kind,
+            options,
+        } => {
+            let kind = match kind.as_str() {
+                "number" => FieldKind::Number,
+                "date" => FieldKind::Date,
+                "select" => FieldKind::Select,
+                _ => FieldKind::Text,
+            };
+
+            let entry = FieldEntry {
+                name,
+                kind,
+                options,
+                ..Default::default()
+            };
+
+            print_field(&store.add_field(entry).await?);
+        }
+        FieldCommand::Rm { name } => print_field(&store.remove_field(&name).await?),
+    }
+
+    Ok(())
+}
+
+async fn run_plan(store: &TaskStore, command: PlanCommand) -> Result<()> {
+    match command {
+        PlanCommand::Add {
+            uuid,
+            detail,
+            under,
+            meta,
+        } => {
+            let mut entry = store.find_record(&uuid).await?;
+            let mut item = RecordPlanEntry {
+                detail,
+                ..Default::default()
+            };
+            set_plan_meta(&mut item, meta)?;
+
+            match under {
+                Some(index) => {
+                    plan_index(&entry, index)?;
+                    item.level = entry.plan[index - 1].level + 1;
+                    let position = plan::subtree_end(&entry.plan, index - 1);
+                    entry.plan.insert(position, item);
+                }
+                None => entry.plan.push(item),
+            }
+
+            print_plan(&store.update(entry).await?);
+        }
+        PlanCommand::Ls { uuid } => print_plan(&store.find_record(&uuid).await?),
+        PlanCommand::Edit {
+            uuid,
+            index,
+            detail,
+            meta,
+        } => {
+            let mut entry = store.find_record(&uuid).await?;
+            plan_index(&entry, index)?;
+
+            let item = &mut entry.plan[index - 1];
+            if let Some(detail) = detail {
+                item.detail = detail;
+            }
+            set_plan_meta(item, meta)?;
+
+            print_plan(&store.update(entry).await?);
+        }
+        PlanCommand::Indent { uuid, index } => {
+            let mut entry = store.find_record(&uuid).await?;
+            plan_index(&entry, index)?;
+
+            if !plan::indent(&mut entry.plan, index - 1) {
+                bail!("plan step {index} can not be indented");
+            }
+
+            print_plan(&store.update(entry).await?);
+        }
+        PlanCommand::Outdent { uuid, index } => {
+            let mut entry = store.find_record(&uuid).await?;
+            plan_index(&entry, index)?;
+
+            if !plan::outdent(&mut entry.plan, index - 1) {
+                bail!("plan step {index} is not a sub-step");
+            }
+
+            print_plan(&store.update(entry).await?);
+        }
+        PlanCommand::Done { uuid, index } => set_plan_finished(store, &uuid, index, true).await?,
+        PlanCommand::Undone { uuid, index } => {
+            set_plan_finished(store, &uuid, index, false).await?
+        }
+        PlanCommand::Rm { uuid, index } => {
+            let mut entry = store.find_record(&uuid).await?;
+
+            plan_index(&entry, index)?;
+            plan::remove(&mut entry.plan, index - 1);
+
+            print_plan(&store.update(entry).await?);
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_trash(store: &TaskStore, command: TrashCommand) -> Result<()> {
+    match command {
+        TrashCommand::Ls { json } => {
+            let entries = store.trash_entries().await?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&entries)?);
+            } else {
+                for entry in entries {
+                    print!(
+                        "{}  {:<7}  ",
+                        cutil::time::timestamp_to_local(entry.deleted_at, "%Y-%m-%d %H:%M"),
+                        entry.table
+                    );
+                    print_entry(&entry.entry);
+                }
+            }
+        }
+        TrashCommand::Restore { uuid } => {
+            let entry = store.find_trash(&uuid).await?;
+            print_entry(&store.restore_trash(&entry.entry.uuid).await?.entry);
+        }
+        TrashCommand::Rm { uuid } => {
+            let entry = store.find_trash(&uuid).await?;
+            store.remove_trash(&entry.entry.uuid).await?;
+            print_entry(&entry.entry);
+        }
+        TrashCommand::Clear => store.remove_all_trash().await?,
+        TrashCommand::Purge { days } => {
+            if days == 0 {
+                bail!("days should be greater than 0");
+            }
+
+            println!("deleted {} entries", store.purge_trash(days).await?);
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_corrupt(store: &TaskStore, command: CorruptCommand) -> Result<()> {
+    match command {
+        CorruptCommand::Ls => {
+            for entry in store.corrupt_entries().await? {
+                println!(
+                    "{:<8}  {:<7}  {}  {}  {}",
+                    entry.id.chars().take(8).collect::<String>(),
+                    entry.table,
+                    entry.created_at,
+                    entry.uuid,
+                    entry.error
+                );
+            }
+        }
+        CorruptCommand::Show { id } => println!("{}", store.find_corrupt(&id).await?.data),
+        CorruptCommand::Export { output } => {
+            let data = serde_json::to_string_pretty(&store.corrupt_entries().await?)?;
+
+            match output {
+                Some(path) => std::fs::write(&path, data)
+                    .with_context(|| format!("write `{}` failed", path.display()))?,
+                None => println!("{data}"),
+            }
+        }
+        CorruptCommand::Repair { id, file } => {
+            let entry = store.find_corrupt(&id).await?;
+
+            print_entry(&store.repair_corrupt(&entry, &read_input(&file)?).await?);
+        }
+        CorruptCommand::Retry => {
+            println!("restored {} entries", store.retry_corrupt().await?);
+        }
+        CorruptCommand::Rm { id } => {
+            let entry = store.find_corrupt(&id).await?;
+            store.remove_corrupt(&entry.id).await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_timer(store: &TaskStore, command: TimerCommand) -> Result<()> {
+    match command {
+        TimerCommand::Start { uuid, note } => {
+            let entry = store.find_record(&uuid).await?;
+            print_session(&store.start_timer(&entry.uuid, &note).await?);
+        }
+        TimerCommand::Stop => match store.stop_timer().await? {
+            Some(session) => print_session(&session),
+            None => println!("no timer is running"),
+        },
+        TimerCommand::Status => match store.running_session().await? {
+            Some(session) => {
+                print_entry(&store.find_record(&session.record_uuid).await?);
+                print_session(&session);
+            }
+            None => println!("no timer is running"),
+        },
+        TimerCommand::Ls { uuid, archive } => {
+            let entry = if archive {
+                store.find_archive(&uuid).await?
+            } else {
+                store.find_record(&uuid).await?
+            };
+
+            let sessions = store.sessions(&entry.uuid).await?;
+            sessions.iter().for_each(print_session);
+
+            println!(
+                "total: {}",
+                session::format_duration(session::tracked_seconds(
+                    &sessions,
+                    cutil::time::timestamp()
+                ))
+            );
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_secret(command: SecretCommand) -> Result<()> {
+    match command {
+        SecretCommand::Status => {
+            let status = security::status();
+
+            println!("locked: {}", status.is_locked);
+            match status.key_file {
+                Some(path) => println!("key: file {}", path.display()),
+                None => println!("key: master password"),
+            }
+            println!("database encrypted: {}", status.is_database_encrypted);
+        }
+        SecretCommand::Password => {
+            let mut password = String::default();
+            std::io::stdin().read_line(&mut password)?;
+
+            let password = password.trim_end_matches(['\r', '\n']);
+            security::set_master_password(Some(password), None).await?;
+            println!("the master password is set");
+        }
+        SecretCommand::KeyFile { path } => {
+            security::set_master_password(None, path.as_deref()).await?;
+
+            if let Some(path) = security::status().key_file {
+                println!("key file: {}", path.display());
+            }
+        }
+        SecretCommand::EncryptDb { value } => {
+            let counts = security::set_database_encryption(value == "on").await?;
+            println!("database encryption {value}, rewritten {counts} rows");
+        }
+    }
+
+    Ok(())
+}
+
+async fn set_plan_finished(
+    store: &TaskStore,
+    uuid: &str,
+    index: usize,
+    is_finished: bool,
+) -> Result<()> {
+    let mut entry = store.find_record(uuid).await?;
+
+    plan_index(&entry, index)?;
+    plan::set_finished(&mut entry.plan, index - 1, is_finished);
+
+    print_plan(&store.update(entry).await?);
+    Ok(())
+}
+
+async fn change_state(store: &TaskStore, uuid: &str, state: RecordState) -> Result<()> {
+    let entry = store.find_record(uuid).await?;
+    let (entry, next) = store.transition(entry, state).await?;
+
+    print_entry(&entry);
+    print_next_occurrence(next.as_ref());
+    Ok(())
+}
+
+// Returns the zero padded date, which the date filters of the search rely on
+fn check_date(date: String) -> Result<String> {
+    cutil::time::date_str_add_days(&date, 0)
+        .with_context(|| format!("invalid date `{date}`, expect `YYYY-MM-DD`"))
+}
+
+// The ranges of a report, a custom one is given by its days
+fn check_range(name: Option<String>) -> Result<Range> {
+    match name.as_deref().unwrap_or("week") {
+        name @ ("week" | "month" | "90days" | "all") => Ok(Range::parse(name, "", "")),
+        name => bail!("unknown range `{name}`, expect week, month, 90days or all"),
+    }
+}
+
+// `none` is the records without a project
+fn project_filter(projects: &[ProjectEntry], project: &str) -> Result<Filter> {
+    if project.eq_ignore_ascii_case("none") {
+        Ok(Filter::NoProject)
+    } else {
+        Ok(Filter::Project(
+            project::find(projects, project)?.uuid.clone(),
+        ))
+    }
+}
+
+// The explicit format wins over the extension of the file
+fn check_format(format: Option<String>, path: Option<&Path>) -> Result<Format> {
+    match format {
+        Some(format) => Format::parse(&format),
+        None => Ok(path.and_then(Format::from_path).unwrap_or_default()),
+    }
+}
+
+// `-` reads from stdin
+fn read_input(file: &Path) -> Result<String> {
+    if file.as_os_str() == "-" {
+        let mut data = String::default();
+        std::io::stdin().read_to_string(&mut data)?;
+        Ok(data)
+    } else {
+        std::fs::read_to_string(file).with_context(|| format!("read `{}` failed", file.display()))
+    }
+}
+
+fn check_dates(entry: &RecordEntry) -> Result<()> {
+    if cutil::time::diff_dates_to_days(&entry.start_date, &entry.end_date)? <= 0 {
+        bail!("end date should greater than start date");
+    }
+
+    Ok(())
+}
+
+fn check_tags(tags: Vec<String>) -> Result<Vec<String>> {
+    if tags.len() > MAX_TAGS {
+        bail!("too many tags. Only support {MAX_TAGS} tags");
+    }
+
+    Ok(tags)
+}
+
+// A link can point to a record or an archive
+async fn find_link(store: &TaskStore, uuid_prefix: &str) -> Result<String> {
+    match store.find_record(uuid_prefix).await {
+        Ok(entry) => Ok(entry.uuid),
+        Err(_) => Ok(store.find_archive(uuid_prefix).await?.uuid),
+    }
+}
+
+// The unlinked records and the ops which unlink them, written with the move of
+// `entry`
+async fn resolve_links(
+    store: &TaskStore,
+    entry: &RecordEntry,
+    links: &LinkArgs,
+) -> Result<(Vec<RecordEntry>, Vec<WriteOp>)> {
+    let dependents = store.dependents(&entry.uuid).await?;
+    if dependents.is_empty() || links.keep_links {
+        return Ok((vec![], vec![]));
+    }
+
+    if !links.unlink {
+        let titles = dependents
+            .iter()
+            .map(|item| format!("`{}`", item.title))
+            .collect::<Vec<_>>()
+            .join(", ");
+        bail!(
+            "{titles} blocked by `{}`, use --unlink or --keep-links",
+            entry.title
+        );
+    }
+
+    store.unlink_ops(&entry.uuid).await
+}
+
+fn print_unlinked(entries: &[RecordEntry]) {
+    for item in entries {
+        println!("unlinked:");
+        print_entry(item);
+    }
+}
+
+fn check_priority(priority: Option<String>) -> Result<Option<RecordPriority>> {
+    priority
+        .map(|item| search::parse_priority(&item))
+        .transpose()
+}
+
+// The values are checked against the field definitions by the store
+fn set_fields(entry: &mut RecordEntry, fields: Vec<String>) -> Result<()> {
+    for item in fields {
+        let (name, value) = field::parse_assignment(&item)?;
+        field::set_value(entry, &name, &value);
+    }
+
+    Ok(())
+}
+
+fn plan_index(entry: &RecordEntry, index: usize) -> Result<()> {
+    if index == 0 || index > entry.plan.len() {
+        bail!("plan index should be in 1..={}", entry.plan.len());
+    }
+
+    Ok(())
+}
+
+// The due date is checked when the record is saved
+fn set_plan_meta(item: &mut RecordPlanEntry, meta: PlanMetaArgs) -> Result<()> {
+    if let Some(due) = meta.due {
+        item.due_date = due;
+    }
+
+    if let Some(estimate) = meta.estimate {
+        item.estimate_seconds = session::parse_duration(&estimate)?;
+    }
+
+    if let Some(note) = meta.note {
+        item.note = note;
+    }
+
+    Ok(())
+}
+
+// The plan count is of the steps without sub-steps
+fn print_entry(entry: &RecordEntry) {
+    let (finished, total) = plan::counts(&entry.plan);
+
+    println!(
+        "{:<8}  {:<10}  {:<6}  {} -> {}  [{finished}/{total}]  {}{}{}{}{}{}",
+        entry.uuid.chars().take(8).collect::<String>(),
+        entry.state.as_str(),
+        entry.priority.as_str(),
+        entry.start_date,
+        entry.end_date,
+        entry.title,
+        entry
+            .tags
+            .iter()
+            .map(|tag| format!(" #{tag}"))
+            .collect::<String>(),
+        if entry.estimate_seconds > 0 {
+            format!("  ~{}", session::format_duration(entry.estimate_seconds))
+        } else {
+            String::default()
+        },
+        entry
+            .fields
+            .iter()
+            .map(|item| format!("  {}={}", item.name, item.value))
+            .collect::<String>(),
+        if entry.recurrence.is_empty() {
+            String::default()
+        } else {
+            format!("  ({})", entry.recurrence)
+        },
+        if entry.blocked_by.is_empty() {
+            String::default()
+        } else {
+            format!(
+                "  {}blocked by {}",
+                if entry.is_blocked { "" } else { "was " },
+                entry
+                    .blocked_by
+                    .iter()
+                    .map(|uuid| uuid.chars().take(8).collect::<String>())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        },
+    );
+}
+
+fn print_project(entry: &ProjectEntry, records: usize, archives: usize) {
+    println!(
+        "{:<8}  {}  {:<16}  {records} records, {archives} archived{}{}",
+        entry.uuid.chars().take(8).collect::<String>(),
+        entry.color,
+        entry.name,
+        if entry.is_archived {
+            "  (archived)"
+        } else {
+            ""
+        },
+        if entry.description.is_empty() {
+            String::default()
+        } else {
+            format!("  {}", entry.description)
+        }
+    );
+}
+
+fn print_field(entry: &FieldEntry) {
+    println!(
+        "{:<16}  {:<6}  {}",
+        entry.name,
+        entry.kind.as_str(),
+        entry.options.join(", ")
+    );
+}
+
+fn print_next_occurrence(entry: Option<&RecordEntry>) {
+    if let Some(entry) = entry {
+        println!("next occurrence:");
+        print_entry(entry);
+    }
+}
+
+// The days spent of the records which started, `-` if there is none
+fn print_aggregate(name: &str, item: &Aggregate) {
+    let days = |value: Option<f64>| value.map_or("-".to_string(), |value| format!("{value:.1}"));
+
+    println!(
+        "{:<16}  {:>5}  {:>6}  {:>6}  {:>6}  {:>6}  {:>8}",
+        name,
+        item.count,
+        item.days.total(),
+        days(item.days.mean()),
+        days(item.days.median()),
+        days(item.days.percentile(90.0)),
+        session::format_duration(item.tracked_seconds.total())
+    );
+}
+
+fn print_session(item: &SessionEntry) {
+    const FORMAT: &str = "%Y-%m-%d %H:%M";
+
+    let end = if session::is_running(item) {
+        "running".to_string()
+    } else {
+        cutil::time::timestamp_to_local(item.end, FORMAT)
+    };
+
+    println!(
+        "{:<8}  {} -> {:<16}  {:>8}  {}",
+        item.uuid.chars().take(8).collect::<String>(),
+        cutil::time::timestamp_to_local(item.start, FORMAT),
+        end,
+        session::format_duration(session::duration(item, cutil::time::timestamp())),
+        item.note
+    );
+}
+
+// The project uuids are shown as names, a removed project as its uuid prefix
+fn print_change(item: &ChangeEntry, projects: &[ProjectEntry]) {
+    let value = |value: &str| {
+        if value.is_empty() {
+            "-".to_string()
+        } else if item.field == change::FIELD_PROJECT {
+            match project::name_of(projects, value) {
+                name if name.is_empty() => value.chars().take(8).collect(),
+                name => name,
+            }
+        } else {
+            value.to_string()
+        }
+    };
+
+    println!(
+        "{}  {:<10}  {} -> {}",
+        cutil::time::timestamp_to_local(item.timestamp, "%Y-%m-%d %H:%M"),
+        item.field,
+        value(&item.old_value),
+        value(&item.new_value)
+    );
+}
+
+fn print_plan(entry: &RecordEntry) {
+    print_entry(entry);
+
+    for (index, item) in entry.plan.iter().enumerate() {
+        println!(
+            "  {:>3}. {}[{}] {}{}{}{}",
+            index + 1,
+            "  ".repeat(item.level as usize),
+            if item.is_finished { "x" } else { " " },
+            item.detail,
+            if item.due_date.is_empty() {
+                String::default()
+            } else {
+                format!("  due {}", item.due_date)
+            },
+            if item.estimate_seconds > 0 {
+                format!("  ~{}", session::format_duration(item.estimate_seconds))
+            } else {
+                String::default()
+            },
+            if item.note.is_empty() {
+                String::default()
+            } else {
+                format!("  ({})", item.note)
+            }
+        );
+    }
+}
@@ -1,5 +1,9 @@
-use super::data::{self, Config};
+use super::{
+    data::{self, Config},
+    secret,
+};
 use anyhow::{Context, Result};
+use cutil::crypto::Cipher;
 use log::debug;
 use once_cell::sync::Lazy;
 use std::{fs, path::PathBuf, sync::Mutex};
@@ -75,6 +79,61 @@ pub fn model() -> data::Model {
     CONFIG.lock().unwrap().model.clone()
 }
 
+pub fn security() -> data::Security {
+    CONFIG.lock().unwrap().security.clone()
+}
+
+pub fn statistic() -> data::Statistic {
+    CONFIG.lock().unwrap().statistic.clone()
+}
+
+// `None` if the secrets are locked
+pub fn secret_key() -> Option<Cipher> {
+    secret::key()
+}
+
+// `None` if a master password is used
+pub fn key_file() -> Option<PathBuf> {
+    secret::key_file(&CONFIG.lock().unwrap())
+}
+
+pub fn unlock(password: &str) -> Result<()> {
+    let mut config = CONFIG.lock().unwrap();
+    let cipher = secret::password_key(&config.security, password)?;
+
+    secret::check_key(&config.security, &cipher)?;
+    secret::open(&mut config, &cipher)?;
+    secret::set_key(Some(cipher));
+    Ok(())
+}
+
+// A new key from the master password or the key file, see `secret::new_key`
+pub fn new_key(password: Option<&str>, key_file: Option<&str>) -> Result<(data::Security, Cipher)> {
+    if secret::key().is_none() {
+        anyhow::bail!("the secrets are locked, enter the master password first");
+    }
+
+    secret::new_key(&CONFIG.lock().unwrap(), password, key_file)
+}
+
+// Seals the secrets with the new key. The old key and security setting are
+// restored if it fails.
+pub fn save_with_key(security: data::Security, cipher: Cipher) -> Result<()> {
+    let mut config = CONFIG.lock().unwrap();
+    let (old_security, old_key) = (config.security.clone(), secret::key());
+
+    config.security = security;
+    secret::set_key(Some(cipher));
+
+    if let Err(e) = config.save() {
+        config.security = old_security;
+        secret::set_key(old_key);
+        return Err(e);
+    }
+
+    Ok(())
+}
+
 #[cfg(feature = "database")]
 pub fn db_path() -> PathBuf {
     CONFIG.lock().unwrap().db_path.clone()
@@ -128,6 +187,7 @@ impl Config {
         let app_dirs = AppDirs::new(Some(&pkg_name), true).unwrap();
         self.init_config(&app_dirs)?;
         self.load().with_context(|| "load config file failed")?;
+        self.init_secret();
         debug!("{:?}", self);
         Ok(())
     }
@@ -155,6 +215,7 @@ impl Config {
                     self.appid = c.appid;
                     self.preference = c.preference;
                     self.model = c.model;
+                    self.security = c.security;
                     Ok(())
                 }
                 Err(_) => {
@@ -187,8 +248,51 @@ impl Config {
         }
     }
 
+    // The key file is read at once, a master password is entered later by
+    // `unlock`. A plain api key of an old config is sealed now.
+    fn init_secret(&mut self) {
+        let Some(path) = secret::key_file(self) else {
+            return;
+        };
+
+        let cipher = match secret::load_key_file(&self.security, &path)
+            .and_then(|cipher| secret::check_key(&self.security, &cipher).map(|_| cipher))
+        {
+            Ok(cipher) => cipher,
+            Err(e) => {
+                log::warn!("load secret key failed: {e:?}");
+                return;
+            }
+        };
+
+        if let Err(e) = secret::open(self, &cipher) {
+            log::warn!("{e:?}");
+            return;
+        }
+
+        let is_legacy = self.security.key_check.is_empty()
+            || (self.model.api_key_encrypted.is_empty() && !self.model.api_key.is_empty());
+
+        if self.security.key_check.is_empty() {
+            match secret::key_check(&cipher) {
+                Ok(check) => self.security.key_check = check,
+                Err(e) => log::warn!("{e:?}"),
+            }
+        }
+
+        secret::set_key(Some(cipher));
+
+        if is_legacy && let Err(e) = self.save() {
+            log::warn!("{e:?}");
+        }
+    }
+
+    // The secrets are sealed, they are never written in plain text
     pub fn save(&self) -> Result<()> {
-        match toml::to_string_pretty(self) {
+        let mut conf = self.clone();
+        secret::seal(&mut conf, secret::key().as_ref())?;
+
+        match toml::to_string_pretty(&conf) {
             Ok(text) => Ok(fs::write(&self.config_path, text)
                 .with_context(|| "save config failed".to_string())?),
             Err(e) => anyhow::bail!(format!("convert config from toml format failed. {e:?}")),
@@ -25,6 +25,12 @@ pub struct Config {
     pub preference: Preference,
 
     pub model: Model,
+
+    #[serde(default)]
+    pub security: Security,
+
+    #[serde(default)]
+    pub statistic: Statistic,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Derivative)]
@@ -52,15 +58,67 @@ pub struct Preference {
     pub no_frame: bool,
 
     pub is_dark: bool,
+
+    // The removed entries are deleted from the trash after these days on
+    // startup, 0 keeps them forever
+    #[derivative(Default(value = "30"))]
+    #[serde(default = "trash_retention_days_default")]
+    pub trash_retention_days: u32,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
 pub struct Model {
     pub api_base_url: String,
     pub model_name: String,
+
+    // Only kept in memory, a plain key of an old config is still read
+    #[serde(skip_serializing)]
     pub api_key: String,
+
+    pub api_key_encrypted: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Security {
+    // The key file, empty for the `<config>.key` next to the config file
+    pub key_file: String,
+
+    // The hex salt of the master password, empty if the key file is used
+    pub password_salt: String,
+
+    // A known text sealed with the key to check the password or key file
+    pub key_check: String,
+
+    pub encrypt_database: bool,
+}
+
+// The scope of the statistic panel, see `core::stats`
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Statistic {
+    // `all`, `week`, `month`, `90days` or `custom`
+    pub range: String,
+
+    // The custom range, `YYYY-MM-DD`
+    pub start_date: String,
+    pub end_date: String,
+
+    pub tags: Vec<String>,
+
+    // The names of the states, e.g. `Running`
+    pub states: Vec<String>,
+
+    // The uuid of the project, `none` for the records without a project and
+    // empty for all records
+    pub project: String,
 }
 
 pub fn appid_default() -> String {
     Uuid::new_v4().to_string()
 }
+
+pub fn trash_retention_days_default() -> u32 {
+    30
+}
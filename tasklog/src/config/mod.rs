@@ -1,7 +1,13 @@
 mod conf;
 mod data;
+mod secret;
 
-pub use conf::{all, app_name, init, is_first_run, model, preference, save};
+pub use conf::{
+    all, app_name, init, is_first_run, key_file, model, new_key, preference, save, save_with_key,
+    secret_key, security, statistic, unlock,
+};
+
+pub use data::Statistic;
 
 #[cfg(feature = "database")]
 pub use conf::db_path;
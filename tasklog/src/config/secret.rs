@@ -0,0 +1,204 @@
+// The secrets of the config, e.g. the api key, are sealed with a key which is
+// either derived from a master password or read from a key file. The key file
+// is created with a random key when it is needed for the first time.
+
+use super::data::{Config, Security};
+use anyhow::{Context, Result, bail};
+use cutil::crypto::{self, Cipher};
+use once_cell::sync::Lazy;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+const KEY_CHECK: &str = "tasklog";
+
+// `None` until the key file is read or the master password is entered
+static KEY: Lazy<Mutex<Option<Cipher>>> = Lazy::new(|| Mutex::new(None));
+
+pub fn key() -> Option<Cipher> {
+    KEY.lock().unwrap().clone()
+}
+
+pub fn set_key(cipher: Option<Cipher>) {
+    *KEY.lock().unwrap() = cipher;
+}
+
+// `None` if a master password is used
+pub fn key_file(conf: &Config) -> Option<PathBuf> {
+    if !conf.security.password_salt.is_empty() {
+        None
+    } else if conf.security.key_file.is_empty() {
+        Some(conf.config_path.with_extension("key"))
+    } else {
+        Some(PathBuf::from(&conf.security.key_file))
+    }
+}
+
+// A missing key file is only created if nothing is sealed yet, otherwise the
+// sealed secrets could never be opened again.
+pub fn load_key_file(security: &Security, path: &Path) -> Result<Cipher> {
+    if path.exists() {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("read key file `{}` failed", path.display()))?;
+        return Cipher::from_hex(&text)
+            .with_context(|| format!("invalid key file `{}`", path.display()));
+    }
+
+    if !security.key_check.is_empty() {
+        bail!("key file `{}` is missing", path.display());
+    }
+
+    let cipher = Cipher::random();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, cipher.to_hex())
+        .with_context(|| format!("write key file `{}` failed", path.display()))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    }
+
+    Ok(cipher)
+}
+
+pub fn password_key(security: &Security, password: &str) -> Result<Cipher> {
+    if security.password_salt.is_empty() {
+        bail!("no master password is set");
+    }
+
+    let salt = hex::decode(&security.password_salt).context("invalid password salt")?;
+    Cipher::from_password(password, &salt)
+}
+
+pub fn key_check(cipher: &Cipher) -> Result<String> {
+    cipher.seal(KEY_CHECK.as_bytes())
+}
+
+// An empty check is accepted, nothing is sealed with the key yet
+pub fn check_key(security: &Security, cipher: &Cipher) -> Result<()> {
+    if security.key_check.is_empty() {
+        return Ok(());
+    }
+
+    match cipher.open(&security.key_check) {
+        Ok(text) if text == KEY_CHECK.as_bytes() => Ok(()),
+        _ => bail!("wrong password or key file"),
+    }
+}
+
+// A key from `password`, or from `key_file` if there is no password. An empty
+// `key_file` is the default one next to the config file.
+pub fn new_key(
+    conf: &Config,
+    password: Option<&str>,
+    key_file: Option<&str>,
+) -> Result<(Security, Cipher)> {
+    let mut security = Security {
+        encrypt_database: conf.security.encrypt_database,
+        ..Default::default()
+    };
+
+    let cipher = match password {
+        Some(password) => {
+            if password.is_empty() {
+                bail!("the master password is empty");
+            }
+
+            let salt = crypto::random_bytes::<{ crypto::SALT_LEN }>();
+            security.password_salt = hex::encode(salt);
+            Cipher::from_password(password, &salt)?
+        }
+        None => {
+            security.key_file = key_file.unwrap_or_default().trim().to_string();
+
+            let path = key_file_of(conf, &security);
+            load_key_file(&security, &path)?
+        }
+    };
+
+    security.key_check = key_check(&cipher)?;
+    Ok((security, cipher))
+}
+
+fn key_file_of(conf: &Config, security: &Security) -> PathBuf {
+    let conf = Config {
+        config_path: conf.config_path.clone(),
+        security: security.clone(),
+        ..Default::default()
+    };
+
+    key_file(&conf).unwrap_or_default()
+}
+
+// Opens the sealed secrets of `conf` in place
+pub fn open(conf: &mut Config, cipher: &Cipher) -> Result<()> {
+    if !conf.model.api_key_encrypted.is_empty() {
+        let api_key = cipher
+            .open(&conf.model.api_key_encrypted)
+            .context("open api key failed")?;
+        conf.model.api_key = String::from_utf8(api_key)?;
+    }
+
+    Ok(())
+}
+
+// Seals the secrets of `conf` in place before it is written. Without a key
+// the sealed secrets are kept as they are.
+pub fn seal(conf: &mut Config, cipher: Option<&Cipher>) -> Result<()> {
+    match cipher {
+        Some(cipher) => {
+            conf.model.api_key_encrypted = if conf.model.api_key.is_empty() {
+                String::default()
+            } else {
+                cipher.seal(conf.model.api_key.as_bytes())?
+            };
+        }
+        None if !conf.model.api_key.is_empty() => {
+            bail!("the secrets are locked, enter the master password first")
+        }
+        None => (),
+    }
+
+    conf.model.api_key.clear();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_open() -> Result<()> {
+        let mut conf = Config::default();
+        let (security, cipher) = new_key(&conf, Some("password"), None)?;
+        conf.security = security;
+        conf.model.api_key = "sk-123".to_string();
+
+        seal(&mut conf, Some(&cipher))?;
+        assert!(conf.model.api_key.is_empty());
+        assert!(Cipher::is_sealed(&conf.model.api_key_encrypted));
+
+        let key = password_key(&conf.security, "password")?;
+        check_key(&conf.security, &key)?;
+        open(&mut conf, &key)?;
+        assert_eq!(conf.model.api_key, "sk-123");
+
+        let key = password_key(&conf.security, "wrong")?;
+        assert!(check_key(&conf.security, &key).is_err());
+
+        // Locked, the sealed key is kept
+        conf.model.api_key.clear();
+        let sealed = conf.model.api_key_encrypted.clone();
+        seal(&mut conf, None)?;
+        assert_eq!(conf.model.api_key_encrypted, sealed);
+
+        conf.model.api_key = "sk-456".to_string();
+        assert!(seal(&mut conf, None).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,399 @@
+// The audit trail of the records. Every saved edit of a record is compared
+// with the stored one and each changed field is logged as a change entry.
+
+use super::{field, record::DATE_FORMAT, session};
+use crate::db::def::{ChangeEntry, RecordEntry, RecordPlanEntry, RecordState};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+pub const FIELD_TITLE: &str = "title";
+pub const FIELD_START_DATE: &str = "start_date";
+pub const FIELD_END_DATE: &str = "end_date";
+pub const FIELD_STATE: &str = "state";
+pub const FIELD_TAGS: &str = "tags";
+pub const FIELD_RECURRENCE: &str = "recurrence";
+pub const FIELD_PLAN: &str = "plan";
+pub const FIELD_PRIORITY: &str = "priority";
+pub const FIELD_ESTIMATE: &str = "estimate";
+pub const FIELD_BLOCKED_BY: &str = "blocked_by";
+
+// The values are the project uuids, the names may change later
+pub const FIELD_PROJECT: &str = "project";
+
+// A custom field is logged as `field:name`
+pub const CUSTOM_FIELD_PREFIX: &str = "field:";
+
+// The changed fields from `old` to `new`. A plan step is logged when it is
+// added, removed or its finished flag, due date or estimate is changed.
+// Moving or indenting it and editing its note are not changes.
+pub fn diff(old: &RecordEntry, new: &RecordEntry, timestamp: i64) -> Vec<ChangeEntry> {
+    let mut items = vec![];
+    let mut push = |field: &str, old_value: String, new_value: String| {
+        if old_value != new_value {
+            items.push(ChangeEntry {
+                uuid: Uuid::new_v4().to_string(),
+                record_uuid: new.uuid.clone(),
+                timestamp,
+                field: field.to_string(),
+                old_value,
+                new_value,
+            });
+        }
+    };
+
+    push(FIELD_TITLE, old.title.clone(), new.title.clone());
+    push(
+        FIELD_START_DATE,
+        old.start_date.clone(),
+        new.start_date.clone(),
+    );
+    push(FIELD_END_DATE, old.end_date.clone(), new.end_date.clone());
+    push(
+        FIELD_STATE,
+        old.state.as_str().to_string(),
+        new.state.as_str().to_string(),
+    );
+    push(FIELD_TAGS, old.tags.join(", "), new.tags.join(", "));
+    push(
+        FIELD_RECURRENCE,
+        old.recurrence.clone(),
+        new.recurrence.clone(),
+    );
+    push(
+        FIELD_PRIORITY,
+        old.priority.as_str().to_string(),
+        new.priority.as_str().to_string(),
+    );
+    push(
+        FIELD_ESTIMATE,
+        estimate_value(old.estimate_seconds),
+        estimate_value(new.estimate_seconds),
+    );
+
+    push(
+        FIELD_BLOCKED_BY,
+        links_value(&old.blocked_by),
+        links_value(&new.blocked_by),
+    );
+    push(FIELD_PROJECT, old.project.clone(), new.project.clone());
+
+    let mut names: Vec<&str> = vec![];
+    for item in old.fields.iter().chain(new.fields.iter()) {
+        if !names
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(&item.name))
+        {
+            names.push(&item.name);
+        }
+    }
+
+    for name in names {
+        push(
+            &format!("{CUSTOM_FIELD_PREFIX}{name}"),
+            field::value(old, name).unwrap_or_default().to_string(),
+            field::value(new, name).unwrap_or_default().to_string(),
+        );
+    }
+
+    let mut removed = old.plan.iter().collect::<Vec<_>>();
+    let mut added = vec![];
+
+    for item in new.plan.iter() {
+        match removed.iter().position(|old| old.detail == item.detail) {
+            Some(index) => {
+                let old_item = removed.remove(index);
+                push(FIELD_PLAN, plan_value(old_item), plan_value(item));
+            }
+            None => added.push(item),
+        }
+    }
+
+    for item in removed {
+        push(FIELD_PLAN, plan_value(item), String::default());
+    }
+
+    for item in added {
+        push(FIELD_PLAN, String::default(), plan_value(item));
+    }
+
+    items
+}
+
+// Empty if the record is not estimated
+fn estimate_value(seconds: i64) -> String {
+    if seconds > 0 {
+        session::format_duration(seconds)
+    } else {
+        String::default()
+    }
+}
+
+// The uuid prefixes, which the command line accepts
+fn links_value(uuids: &[String]) -> String {
+    uuids
+        .iter()
+        .map(|uuid| uuid.chars().take(8).collect::<String>())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// e.g. `[x] Collect data` or `[ ] Collect data (due 2025-03-05, ~2h 00m)`
+pub fn plan_value(item: &RecordPlanEntry) -> String {
+    let mut extras = vec![];
+    if !item.due_date.is_empty() {
+        extras.push(format!("due {}", item.due_date));
+    }
+    if item.estimate_seconds > 0 {
+        extras.push(format!(
+            "~{}",
+            session::format_duration(item.estimate_seconds)
+        ));
+    }
+
+    format!(
+        "[{}] {}{}",
+        if item.is_finished { "x" } else { " " },
+        item.detail,
+        if extras.is_empty() {
+            String::default()
+        } else {
+            format!(" ({})", extras.join(", "))
+        }
+    )
+}
+
+// The end date of the record was moved to a later date
+pub fn is_deadline_extension(change: &ChangeEntry) -> bool {
+    change.field == FIELD_END_DATE
+        && cutil::time::diff_dates_to_days(&change.old_value, &change.new_value)
+            .is_ok_and(|days| days > 0)
+}
+
+pub fn deadline_extensions_by_record(changes: &[ChangeEntry]) -> HashMap<String, i64> {
+    let mut items = HashMap::new();
+
+    for change in changes.iter().filter(|item| is_deadline_extension(item)) {
+        *items.entry(change.record_uuid.clone()).or_default() += 1;
+    }
+
+    items
+}
+
+// Fills the days the plan steps were added and finished. A step is found by
+// its detail, so a renamed step loses its history. The records finished
+// before their finished date was kept get it from the change of the state,
+// and their end date, which was moved to that day, gets back the planned one.
+pub fn fill_history(entries: &mut [RecordEntry], changes: &[ChangeEntry]) {
+    let mut changes_by_record: HashMap<&str, Vec<&ChangeEntry>> = HashMap::new();
+    for change in changes.iter() {
+        changes_by_record
+            .entry(&change.record_uuid)
+            .or_default()
+            .push(change);
+    }
+
+    let date =
+        |change: &ChangeEntry| cutil::time::timestamp_to_local(change.timestamp, DATE_FORMAT);
+
+    for entry in entries.iter_mut() {
+        let Some(changes) = changes_by_record.get_mut(entry.uuid.as_str()) else {
+            continue;
+        };
+        changes.sort_by_key(|item| item.timestamp);
+
+        if entry.state == RecordState::Finished
+            && entry.finished_date.is_empty()
+            && let Some(change) = changes.iter().rev().find(|item| {
+                item.field == FIELD_STATE && item.new_value == RecordState::Finished.as_str()
+            })
+        {
+            entry.finished_date = date(change);
+
+            if let Some(end_date) = changes.iter().find(|item| {
+                item.field == FIELD_END_DATE
+                    && item.timestamp == change.timestamp
+                    && item.new_value == entry.end_date
+            }) {
+                entry.end_date = end_date.old_value.clone();
+            }
+        }
+
+        for item in entry.plan.iter_mut() {
+            if let Some(change) = changes.iter().find(|change| {
+                change.field == FIELD_PLAN
+                    && change.old_value.is_empty()
+                    && is_plan_value_of(&change.new_value, &item.detail)
+            }) {
+                item.added_date = date(change);
+            }
+
+            if item.is_finished
+                && let Some(change) = changes.iter().rev().find(|change| {
+                    change.field == FIELD_PLAN
+                        && change.new_value.starts_with("[x] ")
+                        && is_plan_value_of(&change.new_value, &item.detail)
+                })
+            {
+                item.finished_date = date(change);
+            }
+        }
+    }
+}
+
+// A value of `plan_value` with the detail
+fn is_plan_value_of(value: &str, detail: &str) -> bool {
+    value.get(4..).is_some_and(|text| {
+        text == detail
+            || text
+                .strip_prefix(detail)
+                .is_some_and(|extras| extras.starts_with(" ("))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::def::{RecordField, RecordPriority};
+
+    #[test]
+    fn test_diff() {
+        let old = RecordEntry {
+            uuid: "uuid-1".to_string(),
+            title: "report".to_string(),
+            start_date: "2026-10-01".to_string(),
+            end_date: "2026-10-05".to_string(),
+            plan: vec![
+                RecordPlanEntry {
+                    detail: "draft".to_string(),
+                    ..Default::default()
+                },
+                RecordPlanEntry {
+                    detail: "review".to_string(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        assert!(diff(&old, &old, 100).is_empty());
+
+        let mut new = old.clone();
+        new.end_date = "2026-10-08".to_string();
+        new.state = RecordState::Giveup;
+        new.plan.reverse();
+        new.plan[1].is_finished = true;
+        new.plan.remove(0);
+        new.plan.push(RecordPlanEntry {
+            detail: "send".to_string(),
+            level: 1,
+            due_date: "2026-10-04".to_string(),
+            estimate_seconds: 3600,
+            note: "to the team".to_string(),
+            ..Default::default()
+        });
+        new.priority = RecordPriority::High;
+        new.estimate_seconds = 5400;
+        new.project = "project-1".to_string();
+        new.fields.push(RecordField {
+            name: "Area".to_string(),
+            value: "UI".to_string(),
+        });
+
+        let changes = diff(&old, &new, 100)
+            .into_iter()
+            .map(|item| (item.field, item.old_value, item.new_value))
+            .collect::<Vec<_>>();
+
+        let expected = [
+            (FIELD_END_DATE, "2026-10-05", "2026-10-08"),
+            (FIELD_STATE, "NotStarted", "Giveup"),
+            (FIELD_PRIORITY, "None", "High"),
+            (FIELD_ESTIMATE, "", "1h 30m"),
+            (FIELD_PROJECT, "", "project-1"),
+            ("field:Area", "", "UI"),
+            (FIELD_PLAN, "[ ] draft", "[x] draft"),
+            (FIELD_PLAN, "[ ] review", ""),
+            (FIELD_PLAN, "", "[ ] send (due 2026-10-04, ~1h 00m)"),
+        ]
+        .map(|(field, old, new)| (field.to_string(), old.to_string(), new.to_string()));
+
+        assert_eq!(changes, expected);
+    }
+
+    #[test]
+    fn test_deadline_extensions() {
+        let old = RecordEntry {
+            uuid: "uuid-1".to_string(),
+            end_date: "2026-10-05".to_string(),
+            ..Default::default()
+        };
+        let mut changes = vec![];
+
+        for end_date in ["2026-10-08", "2026-10-06", "2026-10-09"] {
+            let mut new = old.clone();
+            new.end_date = end_date.to_string();
+            changes.extend(diff(&old, &new, 100));
+        }
+
+        let mut other = old.clone();
+        other.uuid = "uuid-2".to_string();
+        other.end_date = "2026-10-01".to_string();
+        changes.extend(diff(&old, &other, 100));
+
+        let items = deadline_extensions_by_record(&changes);
+        assert_eq!(items.get("uuid-1"), Some(&3));
+        assert_eq!(items.get("uuid-2"), None);
+    }
+
+    #[test]
+    fn test_fill_history() {
+        // Noon of 2026-10-02 and 2026-10-03 in UTC, the same days in most
+        // time zones
+        let (day2, day3) = (1790942400, 1791028800);
+
+        let old = RecordEntry {
+            uuid: "uuid-1".to_string(),
+            title: "report".to_string(),
+            start_date: "2026-10-01".to_string(),
+            end_date: "2026-10-05".to_string(),
+            plan: vec![
+                RecordPlanEntry {
+                    detail: "draft".to_string(),
+                    ..Default::default()
+                },
+                RecordPlanEntry {
+                    detail: "review".to_string(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let mut added = old.clone();
+        added.plan.push(RecordPlanEntry {
+            detail: "send".to_string(),
+            due_date: "2026-10-04".to_string(),
+            ..Default::default()
+        });
+        // Finished the old way, the end date was moved to the finished day
+        let mut finished = added.clone();
+        finished.plan[0].is_finished = true;
+        finished.state = RecordState::Finished;
+        finished.end_date = "2026-10-03".to_string();
+
+        let mut changes = diff(&old, &added, day2);
+        changes.extend(diff(&added, &finished, day3));
+
+        let mut entries = [finished];
+        fill_history(&mut entries, &changes);
+
+        let entry = &entries[0];
+        assert_eq!(entry.finished_date, "2026-10-03");
+        assert_eq!(entry.end_date, "2026-10-05");
+        assert_eq!(entry.plan[0].added_date, "");
+        assert_eq!(entry.plan[0].finished_date, "2026-10-03");
+        assert_eq!(entry.plan[1].finished_date, "");
+        assert_eq!(entry.plan[2].added_date, "2026-10-02");
+
+        assert!(is_plan_value_of("[ ] send (due 2026-10-04)", "send"));
+        assert!(!is_plan_value_of("[ ] sender", "send"));
+    }
+}
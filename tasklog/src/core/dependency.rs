@@ -0,0 +1,226 @@
+// "Blocked by" links between the records. A record keeps the uuids of the
+// records which should be finished before it starts. A link to a record which
+// is archived or removed does not block, so it comes back if the record is
+// recovered.
+
+use crate::db::def::{RecordEntry, RecordState};
+use anyhow::{Result, bail};
+use std::collections::HashMap;
+
+// Drops the empty and repeated links and checks that `entry` does not block
+// itself. `entries` are the current records, `entry` replaces the one with
+// the same uuid.
+pub fn check(entry: &mut RecordEntry, entries: &[RecordEntry]) -> Result<()> {
+    let mut uuids: Vec<String> = vec![];
+    for uuid in entry.blocked_by.iter().map(|item| item.trim()) {
+        if !uuid.is_empty() && !uuids.iter().any(|item| item == uuid) {
+            uuids.push(uuid.to_string());
+        }
+    }
+    entry.blocked_by = uuids;
+
+    if entry.blocked_by.contains(&entry.uuid) {
+        bail!("`{}` can not be blocked by itself", entry.title);
+    }
+
+    let entry = &*entry;
+
+    // Walks the blockers depth first and keeps the path for the message
+    let mut path = vec![entry.uuid.as_str()];
+    let mut stack = vec![(entry.uuid.as_str(), 0)];
+    let mut visited: Vec<&str> = vec![];
+
+    while let Some((uuid, index)) = stack.pop() {
+        let Some(next) = links(uuid, entry, entries).get(index) else {
+            path.pop();
+            continue;
+        };
+        stack.push((uuid, index + 1));
+
+        if *next == entry.uuid {
+            path.push(next);
+            bail!("circular dependency: {}", titles(&path, entry, entries));
+        }
+
+        if !visited.contains(&next.as_str()) {
+            visited.push(next);
+            path.push(next);
+            stack.push((next, 0));
+        }
+    }
+
+    Ok(())
+}
+
+// An unfinished current record blocks the records linked to it
+pub fn blockers<'a>(entry: &RecordEntry, entries: &'a [RecordEntry]) -> Vec<&'a RecordEntry> {
+    entries
+        .iter()
+        .filter(|item| entry.blocked_by.contains(&item.uuid))
+        .filter(|item| item.state != RecordState::Finished)
+        .collect()
+}
+
+// The records which are linked to `uuid`
+pub fn dependents<'a>(uuid: &str, entries: &'a [RecordEntry]) -> Vec<&'a RecordEntry> {
+    entries
+        .iter()
+        .filter(|item| item.blocked_by.iter().any(|link| link == uuid))
+        .collect()
+}
+
+// Fills the blocked flag and the number of records each record blocks
+pub fn fill(entries: &mut [RecordEntry]) {
+    let mut blocking: HashMap<String, i64> = HashMap::new();
+    let mut blocked = vec![];
+
+    for entry in entries.iter() {
+        let blockers = blockers(entry, entries);
+        for item in blockers.iter() {
+            *blocking.entry(item.uuid.clone()).or_default() += 1;
+        }
+        blocked.push(!blockers.is_empty());
+    }
+
+    for (entry, is_blocked) in entries.iter_mut().zip(blocked) {
+        entry.is_blocked = is_blocked;
+        entry.blocking_count = blocking.get(&entry.uuid).copied().unwrap_or_default();
+    }
+}
+
+fn links<'a>(uuid: &str, entry: &'a RecordEntry, entries: &'a [RecordEntry]) -> &'a [String] {
+    if uuid == entry.uuid {
+        &entry.blocked_by
+    } else {
+        entries
+            .iter()
+            .find(|item| item.uuid == uuid)
+            .map_or(&[], |item| &item.blocked_by)
+    }
+}
+
+// `a -> b -> a`
+fn titles(path: &[&str], entry: &RecordEntry, entries: &[RecordEntry]) -> String {
+    path.iter()
+        .map(|uuid| {
+            if *uuid == entry.uuid {
+                format!("`{}`", entry.title)
+            } else {
+                entries
+                    .iter()
+                    .find(|item| item.uuid == *uuid)
+                    .map_or(format!("`{uuid}`"), |item| format!("`{}`", item.title))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let entries = vec![
+            RecordEntry {
+                uuid: "a".to_string(),
+                title: "A".to_string(),
+                state: RecordState::Running,
+                ..Default::default()
+            },
+            RecordEntry {
+                uuid: "b".to_string(),
+                title: "B".to_string(),
+                state: RecordState::Running,
+                blocked_by: vec!["a".to_string()],
+                ..Default::default()
+            },
+            RecordEntry {
+                uuid: "c".to_string(),
+                title: "C".to_string(),
+                state: RecordState::Running,
+                blocked_by: vec!["b".to_string(), "d".to_string()],
+                ..Default::default()
+            },
+            RecordEntry {
+                uuid: "d".to_string(),
+                title: "D".to_string(),
+                state: RecordState::Running,
+                ..Default::default()
+            },
+        ];
+
+        let mut item = RecordEntry {
+            blocked_by: vec![" a".to_string(), "a".to_string(), "".to_string()],
+            ..entries[3].clone()
+        };
+        check(&mut item, &entries).unwrap();
+        assert_eq!(item.blocked_by, vec!["a"]);
+
+        let mut item = RecordEntry {
+            blocked_by: vec!["c".to_string()],
+            ..entries[0].clone()
+        };
+        let e = check(&mut item, &entries).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "circular dependency: `A` -> `C` -> `B` -> `A`"
+        );
+
+        let mut item = RecordEntry {
+            blocked_by: vec!["a".to_string()],
+            ..entries[0].clone()
+        };
+        assert!(check(&mut item, &entries).is_err());
+
+        // A link to a record which no longer exists is kept
+        let mut item = RecordEntry {
+            blocked_by: vec!["x".to_string()],
+            ..entries[0].clone()
+        };
+        assert!(check(&mut item, &entries).is_ok());
+    }
+
+    #[test]
+    fn test_fill() {
+        let mut entries = vec![
+            RecordEntry {
+                uuid: "a".to_string(),
+                title: "A".to_string(),
+                state: RecordState::Finished,
+                ..Default::default()
+            },
+            RecordEntry {
+                uuid: "b".to_string(),
+                title: "B".to_string(),
+                state: RecordState::Running,
+                blocked_by: vec!["a".to_string()],
+                ..Default::default()
+            },
+            RecordEntry {
+                uuid: "c".to_string(),
+                title: "C".to_string(),
+                state: RecordState::NotStarted,
+                blocked_by: vec!["a".to_string(), "b".to_string(), "x".to_string()],
+                ..Default::default()
+            },
+            RecordEntry {
+                uuid: "d".to_string(),
+                title: "D".to_string(),
+                state: RecordState::NotStarted,
+                blocked_by: vec!["b".to_string()],
+                ..Default::default()
+            },
+        ];
+
+        fill(&mut entries);
+        let flags = entries
+            .iter()
+            .map(|item| (item.is_blocked, item.blocking_count))
+            .collect::<Vec<_>>();
+
+        assert_eq!(flags, vec![(false, 0), (false, 2), (true, 0), (true, 0)]);
+        assert_eq!(dependents("a", &entries).len(), 2);
+    }
+}
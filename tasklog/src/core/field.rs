@@ -0,0 +1,255 @@
+// Custom fields are defined per database in the field table. A record keeps
+// the values by the field name, the values of the removed or unknown fields
+// are kept as they are, so nothing is lost by an import from another database.
+
+use crate::db::def::{FieldEntry, FieldKind, RecordEntry, RecordField};
+use anyhow::{Result, bail};
+
+// Trims the name and the options. The name is written as `name=value` in the
+// exports and the search, so it can not contain `=`.
+pub fn check_field(entry: &FieldEntry) -> Result<FieldEntry> {
+    let name = entry.name.trim().to_string();
+    if name.is_empty() {
+        bail!("field name is empty");
+    }
+
+    if name.contains(['=', '\n', '\r']) {
+        bail!("field name `{name}` can not contain `=` or line breaks");
+    }
+
+    let mut options: Vec<String> = vec![];
+    if entry.kind == FieldKind::Select {
+        for option in entry.options.iter().map(|item| item.trim()) {
+            if !option.is_empty() && !options.iter().any(|item| item == option) {
+                options.push(option.to_string());
+            }
+        }
+
+        if options.is_empty() {
+            bail!("select field `{name}` has no options");
+        }
+    }
+
+    Ok(FieldEntry {
+        uuid: entry.uuid.clone(),
+        name,
+        kind: entry.kind,
+        options,
+    })
+}
+
+// Returns the value as it is stored. A date is zero padded and an option of
+// a select field takes the case of its definition.
+pub fn check_value(field: &FieldEntry, value: &str) -> Result<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(String::default());
+    }
+
+    match field.kind {
+        FieldKind::Text => Ok(value.to_string()),
+        FieldKind::Number => match value.parse::<f64>() {
+            Ok(number) if number.is_finite() => Ok(value.to_string()),
+            _ => bail!("`{value}` of field `{}` is not a number", field.name),
+        },
+        FieldKind::Date => cutil::time::date_str_add_days(value, 0).map_err(|_| {
+            anyhow::anyhow!(
+                "`{value}` of field `{}` is not a date, expect `YYYY-MM-DD`",
+                field.name
+            )
+        }),
+        FieldKind::Select => match field
+            .options
+            .iter()
+            .find(|option| option.eq_ignore_ascii_case(value))
+        {
+            Some(option) => Ok(option.clone()),
+            None => bail!(
+                "`{value}` of field `{}` should be one of {}",
+                field.name,
+                field.options.join(", ")
+            ),
+        },
+    }
+}
+
+// Checks the values of the defined fields and drops the empty ones. The
+// defined fields come first in the order of `fields`, a name set twice keeps
+// the last value.
+pub fn normalize(entry: &mut RecordEntry, fields: &[FieldEntry]) -> Result<()> {
+    let mut values: Vec<RecordField> = vec![];
+
+    for item in entry.fields.iter() {
+        let name = item.name.trim();
+        let value = match find(fields, name) {
+            Some(field) => check_value(field, &item.value)?,
+            None => item.value.trim().to_string(),
+        };
+
+        values.retain(|item| !item.name.eq_ignore_ascii_case(name));
+        if !name.is_empty() && !value.is_empty() {
+            values.push(RecordField {
+                name: find(fields, name)
+                    .map_or(name, |field| &field.name)
+                    .to_string(),
+                value,
+            });
+        }
+    }
+
+    let index = |item: &RecordField| {
+        fields
+            .iter()
+            .position(|field| field.name == item.name)
+            .unwrap_or(fields.len())
+    };
+
+    values.sort_by_key(index);
+    entry.fields = values;
+    Ok(())
+}
+
+// The field names are case-insensitive
+pub fn find<'a>(fields: &'a [FieldEntry], name: &str) -> Option<&'a FieldEntry> {
+    fields
+        .iter()
+        .find(|field| field.name.eq_ignore_ascii_case(name.trim()))
+}
+
+pub fn value<'a>(entry: &'a RecordEntry, name: &str) -> Option<&'a str> {
+    entry
+        .fields
+        .iter()
+        .find(|item| item.name.eq_ignore_ascii_case(name.trim()))
+        .map(|item| item.value.as_str())
+}
+
+// An empty value removes the field from the record
+pub fn set_value(entry: &mut RecordEntry, name: &str, value: &str) {
+    let position = entry
+        .fields
+        .iter()
+        .position(|item| item.name.eq_ignore_ascii_case(name.trim()));
+
+    match (position, value.is_empty()) {
+        (Some(index), true) => {
+            entry.fields.remove(index);
+        }
+        (Some(index), false) => entry.fields[index].value = value.to_string(),
+        (None, false) => entry.fields.push(RecordField {
+            name: name.trim().to_string(),
+            value: value.to_string(),
+        }),
+        (None, true) => (),
+    }
+}
+
+// `name=value` -> (`name`, `value`)
+pub fn parse_assignment(text: &str) -> Result<(String, String)> {
+    match text.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.trim().to_string()))
+        }
+        _ => bail!("invalid field `{text}`, expect `name=value`"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields() -> Vec<FieldEntry> {
+        vec![
+            FieldEntry {
+                name: "Sprint".to_string(),
+                kind: FieldKind::Number,
+                ..Default::default()
+            },
+            FieldEntry {
+                name: "Review".to_string(),
+                kind: FieldKind::Date,
+                ..Default::default()
+            },
+            FieldEntry {
+                name: "Area".to_string(),
+                kind: FieldKind::Select,
+                options: vec!["Backend".to_string(), "UI".to_string()],
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn test_check_field() {
+        let field = check_field(&FieldEntry {
+            name: " Area ".to_string(),
+            kind: FieldKind::Select,
+            options: vec![" UI".to_string(), "".to_string(), "UI".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(field.name, "Area");
+        assert_eq!(field.options, vec!["UI"]);
+
+        for (name, kind) in [
+            (" ", FieldKind::Text),
+            ("a=b", FieldKind::Text),
+            ("Area", FieldKind::Select),
+        ] {
+            let entry = FieldEntry {
+                name: name.to_string(),
+                kind,
+                ..Default::default()
+            };
+            assert!(check_field(&entry).is_err(), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_normalize() {
+        let fields = fields();
+        let mut entry = RecordEntry::default();
+
+        for (name, value) in [
+            ("area", "ui"),
+            ("Note", " kept "),
+            ("review", "2026-1-5"),
+            ("SPRINT", "3"),
+            ("sprint", "12.5"),
+            ("Empty", ""),
+        ] {
+            set_value(&mut entry, name, value);
+        }
+        set_value(&mut entry, "empty", "");
+
+        normalize(&mut entry, &fields).unwrap();
+        let values = entry
+            .fields
+            .iter()
+            .map(|item| (item.name.as_str(), item.value.as_str()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            values,
+            vec![
+                ("Sprint", "12.5"),
+                ("Review", "2026-01-05"),
+                ("Area", "UI"),
+                ("Note", "kept"),
+            ]
+        );
+        assert_eq!(value(&entry, "area"), Some("UI"));
+
+        for (name, value) in [("Sprint", "x"), ("Review", "soon"), ("Area", "Docs")] {
+            let mut entry = RecordEntry::default();
+            set_value(&mut entry, name, value);
+            assert!(normalize(&mut entry, &fields).is_err(), "{name}");
+        }
+
+        assert_eq!(
+            parse_assignment("Area = UI").unwrap(),
+            ("Area".to_string(), "UI".to_string())
+        );
+        assert!(parse_assignment("=UI").is_err());
+    }
+}
@@ -0,0 +1,139 @@
+// Undo and redo of the record changes. An operation keeps the snapshots of the
+// records it changed from before and after it, undoing writes the before
+// snapshots back and redoing writes the after ones.
+
+use crate::db::def::{RecordEntry, SessionEntry, TrashEntry};
+
+// Only the latest operations can be undone
+const MAX_OPERATIONS: usize = 100;
+
+#[derive(Debug, Clone)]
+pub enum Placement {
+    Absent,
+    Record(RecordEntry),
+    Archive(RecordEntry),
+    Trash(TrashEntry),
+}
+
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub placement: Placement,
+    pub sessions: Vec<SessionEntry>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub uuid: String,
+    pub before: Snapshot,
+    pub after: Snapshot,
+}
+
+impl Change {
+    // The sessions are only written back if the operation changed them, so
+    // the time tracked since then is kept
+    pub fn is_sessions_changed(&self) -> bool {
+        self.before.sessions != self.after.sessions
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Operation {
+    pub label: String,
+    pub changes: Vec<Change>,
+}
+
+#[derive(Debug, Default)]
+pub struct History {
+    undo: Vec<Operation>,
+    redo: Vec<Operation>,
+}
+
+impl History {
+    // A new operation drops the undone ones
+    pub fn push(&mut self, operation: Operation) {
+        if operation.changes.is_empty() {
+            return;
+        }
+
+        self.redo.clear();
+        self.undo.push(operation);
+
+        if self.undo.len() > MAX_OPERATIONS {
+            self.undo.remove(0);
+        }
+    }
+
+    // Moves the latest operation to the redo list. A failed undo is moved
+    // back by `redo`.
+    pub fn undo(&mut self) -> Option<Operation> {
+        let operation = self.undo.pop()?;
+        self.redo.push(operation.clone());
+        Some(operation)
+    }
+
+    pub fn redo(&mut self) -> Option<Operation> {
+        let operation = self.redo.pop()?;
+        self.undo.push(operation.clone());
+        Some(operation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn operation(label: &str) -> Operation {
+        Operation {
+            label: label.to_string(),
+            changes: vec![Change {
+                uuid: "uuid-1".to_string(),
+                before: Snapshot {
+                    placement: Placement::Absent,
+                    sessions: vec![],
+                },
+                after: Snapshot {
+                    placement: Placement::Record(RecordEntry::default()),
+                    sessions: vec![],
+                },
+            }],
+        }
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut history = History::default();
+        assert!(history.undo().is_none());
+
+        history.push(operation("first"));
+        history.push(operation("second"));
+        history.push(Operation {
+            label: "empty".to_string(),
+            changes: vec![],
+        });
+
+        assert_eq!(history.undo().unwrap().label, "second");
+        assert_eq!(history.undo().unwrap().label, "first");
+        assert!(history.undo().is_none());
+
+        assert_eq!(history.redo().unwrap().label, "first");
+        history.push(operation("third"));
+        assert!(history.redo().is_none());
+        assert_eq!(history.undo().unwrap().label, "third");
+        assert_eq!(history.undo().unwrap().label, "first");
+    }
+
+    #[test]
+    fn test_max_operations() {
+        let mut history = History::default();
+        for i in 0..=MAX_OPERATIONS {
+            history.push(operation(&i.to_string()));
+        }
+
+        let mut counts = 0;
+        while let Some(operation) = history.undo() {
+            assert_ne!(operation.label, "0");
+            counts += 1;
+        }
+        assert_eq!(counts, MAX_OPERATIONS);
+    }
+}
@@ -0,0 +1,489 @@
+use super::{
+    plan,
+    transfer::{self, Bundle},
+};
+use crate::db::def::{RecordEntry, RecordPriority, RecordState};
+use anyhow::{Result, bail};
+
+const PRODID: &str = "-//Heng30//tasklog//EN";
+
+// Marks the todos written from the archive, so they go back to the archive
+// when the file is imported
+const ARCHIVED_PROPERTY: &str = "X-TASKLOG-ARCHIVED";
+
+// Lines longer than this are folded, RFC 5545 3.1
+const MAX_LINE_OCTETS: usize = 75;
+
+// One VTODO per record. The dates are all-day values, the plan is a checklist
+// in DESCRIPTION and the repeat rule is written as RRULE as it is.
+pub fn write(bundle: &Bundle) -> String {
+    let stamp = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{PRODID}"),
+    ];
+
+    for (entries, is_archived) in [(&bundle.records, false), (&bundle.archives, true)] {
+        for entry in entries.iter() {
+            lines.extend(vtodo(entry, &stamp, is_archived));
+        }
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    lines
+        .iter()
+        .map(|line| fold(line))
+        .collect::<Vec<_>>()
+        .join("\r\n")
+        + "\r\n"
+}
+
+// Every VTODO and VEVENT becomes a record, the UID is kept as the uuid so a
+// re-imported file updates the records it created before
+pub fn read(text: &str) -> Result<Bundle> {
+    let mut bundle = Bundle::default();
+    let mut components: Vec<String> = vec![];
+    let mut current: Option<Component> = None;
+
+    for line in unfold(text) {
+        let Some((name, value)) = parse_line(&line) else {
+            continue;
+        };
+
+        match name.as_str() {
+            "BEGIN" => {
+                let kind = value.to_uppercase();
+                if components.last().is_some_and(|item| item == "VCALENDAR")
+                    && (kind == "VTODO" || kind == "VEVENT")
+                {
+                    current = Some(Component::new(&kind));
+                }
+                components.push(kind);
+            }
+            "END" => {
+                let kind = value.to_uppercase();
+                if components.pop().as_deref() != Some(kind.as_str()) {
+                    bail!("unexpected `END:{value}`");
+                }
+
+                if (kind == "VTODO" || kind == "VEVENT")
+                    && let Some(component) = current.take()
+                {
+                    let is_archived = component.is_archived;
+                    let entry = component.into_entry()?;
+
+                    if is_archived {
+                        bundle.archives.push(entry);
+                    } else {
+                        bundle.records.push(entry);
+                    }
+                }
+            }
+            _ => {
+                // The properties of the nested components, e.g. VALARM, are skipped
+                if let Some(component) = current.as_mut()
+                    && components.last() == Some(&component.kind)
+                {
+                    component.set(&name, &value);
+                }
+            }
+        }
+    }
+
+    if !components.is_empty() {
+        bail!("`{}` is not closed", components.join(", "));
+    }
+
+    Ok(bundle)
+}
+
+#[derive(Debug, Default)]
+struct Component {
+    kind: String,
+    uid: String,
+    summary: String,
+    start: String,
+    end: String,
+    status: String,
+    categories: Vec<String>,
+    description: String,
+    rrule: String,
+    priority: RecordPriority,
+    is_archived: bool,
+}
+
+impl Component {
+    fn new(kind: &str) -> Self {
+        Self {
+            kind: kind.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn set(&mut self, name: &str, value: &str) {
+        match name {
+            "UID" => self.uid = unescape(value),
+            "SUMMARY" => self.summary = unescape(value),
+            "DTSTART" => self.start = parse_date(value).unwrap_or_default(),
+            "DUE" | "DTEND" => self.end = parse_date(value).unwrap_or_default(),
+            "STATUS" => self.status = value.trim().to_uppercase(),
+            "CATEGORIES" => self.categories.extend(
+                split_list(value)
+                    .into_iter()
+                    .filter(|item| !item.is_empty()),
+            ),
+            "DESCRIPTION" => self.description = unescape(value),
+            "RRULE" => self.rrule = value.trim().to_string(),
+            "PRIORITY" => self.priority = parse_priority(value),
+            _ if name == ARCHIVED_PROPERTY => {
+                self.is_archived = value.trim().eq_ignore_ascii_case("TRUE")
+            }
+            _ => (),
+        }
+    }
+
+    // A todo without a start date starts the day before it is due, and the
+    // end date is moved after the start date if it is not
+    fn into_entry(self) -> Result<RecordEntry> {
+        let mut start = self.start;
+        let mut end = self.end;
+
+        if start.is_empty() && !end.is_empty() {
+            start = cutil::time::date_str_add_days(&end, -1)?;
+        }
+
+        if !start.is_empty()
+            && (end.is_empty() || cutil::time::diff_dates_to_days(&start, &end)? <= 0)
+        {
+            end = cutil::time::date_str_add_days(&start, 1)?;
+        }
+
+        let state = match (self.kind.as_str(), self.status.as_str()) {
+            ("VTODO", "IN-PROCESS") => RecordState::Running,
+            (_, "COMPLETED") => RecordState::Finished,
+            (_, "CANCELLED") => RecordState::Giveup,
+            _ => RecordState::NotStarted,
+        };
+
+        Ok(RecordEntry {
+            uuid: self.uid,
+            start_date: start,
+            end_date: end,
+            title: self.summary,
+            plan: self
+                .description
+                .lines()
+                .filter_map(transfer::parse_plan_item)
+                .collect(),
+            tags: self.categories,
+            state,
+            recurrence: self.rrule,
+            priority: self.priority,
+            ..Default::default()
+        })
+    }
+}
+
+fn vtodo(entry: &RecordEntry, stamp: &str, is_archived: bool) -> Vec<String> {
+    // A record timed out is still to be done, its state comes from the dates
+    let status = match entry.state {
+        RecordState::NotStarted | RecordState::Timeout => "NEEDS-ACTION",
+        RecordState::Running => "IN-PROCESS",
+        RecordState::Finished => "COMPLETED",
+        RecordState::Giveup => "CANCELLED",
+    };
+
+    let mut lines = vec![
+        "BEGIN:VTODO".to_string(),
+        format!("UID:{}", escape(&entry.uuid)),
+        format!("DTSTAMP:{stamp}"),
+        format!("SUMMARY:{}", escape(&entry.title)),
+        format!("STATUS:{status}"),
+    ];
+
+    if let Some(date) = format_date(&entry.start_date) {
+        lines.push(format!("DTSTART;VALUE=DATE:{date}"));
+    }
+
+    if let Some(date) = format_date(&entry.end_date) {
+        lines.push(format!("DUE;VALUE=DATE:{date}"));
+    }
+
+    if !entry.tags.is_empty() {
+        let tags = entry.tags.iter().map(|tag| escape(tag)).collect::<Vec<_>>();
+        lines.push(format!("CATEGORIES:{}", tags.join(",")));
+    }
+
+    if !entry.plan.is_empty() {
+        let plan = entry
+            .plan
+            .iter()
+            .map(|item| {
+                format!(
+                    "{}[{}] {}",
+                    transfer::indent(item),
+                    if item.is_finished { "x" } else { " " },
+                    item.detail.lines().collect::<Vec<_>>().join(" ")
+                )
+            })
+            .collect::<Vec<_>>();
+
+        lines.push(format!("DESCRIPTION:{}", escape(&plan.join("\n"))));
+        lines.push(format!(
+            "PERCENT-COMPLETE:{}",
+            (plan::progress(&entry.plan).unwrap_or_default() * 100.0) as u32
+        ));
+    }
+
+    if !entry.recurrence.is_empty() {
+        lines.push(format!("RRULE:{}", entry.recurrence));
+    }
+
+    if let Some(priority) = format_priority(entry.priority) {
+        lines.push(format!("PRIORITY:{priority}"));
+    }
+
+    if is_archived {
+        lines.push(format!("{ARCHIVED_PROPERTY}:TRUE"));
+    }
+
+    lines.push("END:VTODO".to_string());
+    lines
+}
+
+// RFC 5545 3.8.1.9: 1 is the highest, 5 is medium, 9 is the lowest and 0 is
+// undefined
+fn format_priority(priority: RecordPriority) -> Option<u8> {
+    match priority {
+        RecordPriority::None => None,
+        RecordPriority::Urgent => Some(1),
+        RecordPriority::High => Some(3),
+        RecordPriority::Medium => Some(5),
+        RecordPriority::Low => Some(7),
+    }
+}
+
+fn parse_priority(value: &str) -> RecordPriority {
+    match value.trim().parse::<u8>() {
+        Ok(1..=2) => RecordPriority::Urgent,
+        Ok(3..=4) => RecordPriority::High,
+        Ok(5) => RecordPriority::Medium,
+        Ok(6..=9) => RecordPriority::Low,
+        _ => RecordPriority::None,
+    }
+}
+
+// `2026-10-18` -> `20261018`
+fn format_date(date: &str) -> Option<String> {
+    chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .ok()
+        .map(|date| date.format("%Y%m%d").to_string())
+}
+
+// `20261018` or `20261018T090000Z` -> `2026-10-18`. The time and the time
+// zone are dropped, records only have dates.
+fn parse_date(value: &str) -> Option<String> {
+    let value = value.trim();
+    let date = chrono::NaiveDate::parse_from_str(value.get(..8)?, "%Y%m%d").ok()?;
+    Some(date.format("%Y-%m-%d").to_string())
+}
+
+// `NAME;PARAM=x:value` -> (`NAME`, `value`), the parameters are ignored. A
+// colon in a quoted parameter value does not end the name.
+fn parse_line(line: &str) -> Option<(String, String)> {
+    let mut in_quotes = false;
+    let index = line.char_indices().find_map(|(index, c)| match c {
+        '"' => {
+            in_quotes = !in_quotes;
+            None
+        }
+        ':' if !in_quotes => Some(index),
+        _ => None,
+    })?;
+
+    let head = &line[..index];
+    let name = head.split_once(';').map_or(head, |(name, _)| name);
+
+    Some((name.trim().to_uppercase(), line[index + 1..].to_string()))
+}
+
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+
+    for line in text.lines() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+
+        if let Some(rest) = line.strip_prefix([' ', '\t'])
+            && let Some(last) = lines.last_mut()
+        {
+            last.push_str(rest);
+        } else if !line.is_empty() {
+            lines.push(line.to_string());
+        }
+    }
+
+    lines
+}
+
+// Splits at the octet limit without breaking a character, the following
+// lines start with a space
+fn fold(line: &str) -> String {
+    let mut text = String::default();
+    let mut octets = 0;
+
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            text.push_str("\r\n ");
+            octets = 1;
+        }
+
+        text.push(c);
+        octets += c.len_utf8();
+    }
+
+    text
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut value = String::default();
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') | Some('N') => value.push('\n'),
+            Some(c) => value.push(c),
+            None => value.push('\\'),
+        }
+    }
+
+    value
+}
+
+// Splits a list value at the commas which are not escaped
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = vec![];
+    let mut item = String::default();
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                item.push(c);
+                if let Some(c) = chars.next() {
+                    item.push(c);
+                }
+            }
+            ',' => items.push(unescape(std::mem::take(&mut item).trim())),
+            _ => item.push(c),
+        }
+    }
+
+    items.push(unescape(item.trim()));
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::def::RecordPlanEntry;
+
+    #[test]
+    fn test_round_trip() -> Result<()> {
+        let bundle = Bundle {
+            records: vec![RecordEntry {
+                uuid: "uuid-1".to_string(),
+                start_date: "2026-10-12".to_string(),
+                end_date: "2026-10-13".to_string(),
+                title: format!("Write; report, {}", "很长的标题".repeat(10)),
+                plan: vec![RecordPlanEntry {
+                    detail: "collect data".to_string(),
+                    is_finished: true,
+                    ..Default::default()
+                }],
+                tags: vec!["work".to_string(), "a,b".to_string()],
+                state: RecordState::Running,
+                recurrence: "FREQ=WEEKLY;BYDAY=MO".to_string(),
+                priority: RecordPriority::High,
+                ..Default::default()
+            }],
+            archives: vec![RecordEntry {
+                uuid: "uuid-2".to_string(),
+                start_date: "2026-09-01".to_string(),
+                end_date: "2026-09-02".to_string(),
+                title: "Old".to_string(),
+                state: RecordState::Giveup,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let text = write(&bundle);
+        assert!(
+            text.lines()
+                .all(|line| line.trim_end().len() <= MAX_LINE_OCTETS)
+        );
+
+        let items = read(&text)?;
+        assert_eq!(
+            serde_json::to_value(&items)?,
+            serde_json::to_value(&bundle)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read() -> Result<()> {
+        let text = "BEGIN:VCALENDAR\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:event-1\r\n\
+            SUMMARY:Meet\r\n\
+            \tup\r\n\
+            DTSTART;TZID=\"Asia/Shanghai:x\":20261020T090000\r\n\
+            DTEND:20261020T100000Z\r\n\
+            BEGIN:VALARM\r\n\
+            DESCRIPTION:[ ] not a plan\r\n\
+            END:VALARM\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VTODO\r\n\
+            UID:todo-1\r\n\
+            DUE;VALUE=DATE:20261101\r\n\
+            STATUS:COMPLETED\r\n\
+            PRIORITY:2\r\n\
+            CATEGORIES:a\\,b,c\r\n\
+            END:VTODO\r\n\
+            END:VCALENDAR\r\n";
+
+        let bundle = read(text)?;
+        let event = &bundle.records[0];
+        assert_eq!(event.title, "Meetup");
+        assert_eq!(event.start_date, "2026-10-20");
+        assert_eq!(event.end_date, "2026-10-21");
+        assert!(event.plan.is_empty());
+        assert_eq!(event.state, RecordState::NotStarted);
+
+        let todo = &bundle.records[1];
+        assert_eq!(todo.start_date, "2026-10-31");
+        assert_eq!(todo.tags, vec!["a,b", "c"]);
+        assert_eq!(todo.state, RecordState::Finished);
+        assert_eq!(todo.priority, RecordPriority::Urgent);
+
+        assert!(read("BEGIN:VCALENDAR\nBEGIN:VTODO\nEND:VCALENDAR\n").is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,21 @@
+pub mod change;
+pub mod dependency;
+pub mod field;
+pub mod history;
+pub mod ical;
+pub mod plan;
+pub mod project;
+pub mod record;
+pub mod recurrence;
+pub mod report;
+pub mod search;
+pub mod session;
+pub mod sort;
+pub mod stats;
+pub mod store;
+pub mod transfer;
+
+#[cfg(feature = "database")]
+pub mod security;
+
+pub use store::TaskStore;
@@ -0,0 +1,363 @@
+// A plan is a tree kept as a flat list in the outline order. `level` is the
+// depth of an item, so the items after it with a deeper level are its
+// sub-steps. The plans saved before the sub-steps were added are all at level
+// 0 and load as they are.
+
+use crate::db::def::{RecordEntry, RecordPlanEntry};
+use anyhow::{Result, bail};
+
+// Deeper items do not fit the plan dialog
+pub const MAX_LEVEL: u32 = 4;
+
+// Trims the items, checks the due dates and fixes the levels and the finished
+// flags of the parents
+pub fn normalize(entry: &mut RecordEntry) -> Result<()> {
+    for (index, item) in entry.plan.iter_mut().enumerate() {
+        item.detail = item.detail.trim().to_string();
+        item.note = item.note.trim().to_string();
+        item.estimate_seconds = item.estimate_seconds.max(0);
+
+        let due_date = item.due_date.trim();
+        item.due_date = if due_date.is_empty() {
+            String::default()
+        } else {
+            match cutil::time::date_str_add_days(due_date, 0) {
+                Ok(date) => date,
+                _ => bail!(
+                    "due date `{due_date}` of plan step {} is not a date, expect `YYYY-MM-DD`",
+                    index + 1
+                ),
+            }
+        };
+    }
+
+    fix_levels(&mut entry.plan);
+    refresh_parents(&mut entry.plan);
+    Ok(())
+}
+
+// An item is at most one level deeper than the item before it
+fn fix_levels(plan: &mut [RecordPlanEntry]) {
+    let mut level = 0;
+
+    for (index, item) in plan.iter_mut().enumerate() {
+        item.level = if index == 0 {
+            0
+        } else {
+            item.level.min(level + 1).min(MAX_LEVEL)
+        };
+        level = item.level;
+    }
+}
+
+// The index after the last sub-step of the item
+pub fn subtree_end(plan: &[RecordPlanEntry], index: usize) -> usize {
+    let Some(item) = plan.get(index) else {
+        return plan.len();
+    };
+
+    plan.iter()
+        .skip(index + 1)
+        .position(|child| child.level <= item.level)
+        .map_or(plan.len(), |offset| index + 1 + offset)
+}
+
+pub fn has_children(plan: &[RecordPlanEntry], index: usize) -> bool {
+    subtree_end(plan, index) > index + 1
+}
+
+// Makes the item a sub-step of the item before it, its sub-steps move with it
+pub fn indent(plan: &mut [RecordPlanEntry], index: usize) -> bool {
+    if index == 0 || index >= plan.len() || plan[index].level > plan[index - 1].level {
+        return false;
+    }
+
+    let end = subtree_end(plan, index);
+    if plan[index..end].iter().any(|item| item.level >= MAX_LEVEL) {
+        return false;
+    }
+
+    plan[index..end].iter_mut().for_each(|item| item.level += 1);
+    true
+}
+
+// Moves the item one level up, its sub-steps move with it
+pub fn outdent(plan: &mut [RecordPlanEntry], index: usize) -> bool {
+    if index >= plan.len() || plan[index].level == 0 {
+        return false;
+    }
+
+    let end = subtree_end(plan, index);
+    plan[index..end].iter_mut().for_each(|item| item.level -= 1);
+    true
+}
+
+// Removes the item with its sub-steps
+pub fn remove(plan: &mut Vec<RecordPlanEntry>, index: usize) {
+    if index < plan.len() {
+        let end = subtree_end(plan, index);
+        plan.drain(index..end);
+    }
+}
+
+// Moves the item with its sub-steps to `to`, an index of the list before the
+// move. The moved items take the level of the item they are dropped on.
+pub fn move_item(plan: &mut Vec<RecordPlanEntry>, from: usize, to: usize) -> bool {
+    if from >= plan.len() {
+        return false;
+    }
+
+    let end = subtree_end(plan, from);
+    let to = to.min(plan.len() - 1);
+    if (from..end).contains(&to) {
+        return false;
+    }
+
+    let level = plan[to].level as i64 - plan[from].level as i64;
+    let mut items = plan.drain(from..end).collect::<Vec<_>>();
+    for item in items.iter_mut() {
+        item.level = (item.level as i64 + level).max(0) as u32;
+    }
+
+    // Dropped below the target, after its sub-steps
+    let position = if to > from {
+        let target = to - items.len();
+        subtree_end(plan, target)
+    } else {
+        to
+    };
+
+    plan.splice(position..position, items);
+    fix_levels(plan);
+
+    true
+}
+
+// Finishing an item finishes its sub-steps
+pub fn set_finished(plan: &mut [RecordPlanEntry], index: usize, is_finished: bool) {
+    if index >= plan.len() {
+        return;
+    }
+
+    let end = subtree_end(plan, index);
+    plan[index..end]
+        .iter_mut()
+        .for_each(|item| item.is_finished = is_finished);
+
+    refresh_parents(plan);
+}
+
+// A parent is finished when all its sub-steps are. The deepest ones are
+// refreshed first, so the result goes up the tree.
+pub fn refresh_parents(plan: &mut [RecordPlanEntry]) {
+    for index in (0..plan.len()).rev() {
+        let end = subtree_end(plan, index);
+        if end > index + 1 {
+            plan[index].is_finished = plan[index + 1..end].iter().all(|item| item.is_finished);
+        }
+    }
+}
+
+// The finished ratio of the leaf items, weighted by their estimates. A leaf
+// without an estimate weighs as the mean estimate of the others, and all the
+// leaves weigh the same if none is estimated. The estimate of a parent is
+// only a note, its sub-steps are counted instead. `None` for an empty plan.
+pub fn progress(plan: &[RecordPlanEntry]) -> Option<f32> {
+    let leaves = (0..plan.len())
+        .filter(|index| !has_children(plan, *index))
+        .map(|index| &plan[index])
+        .collect::<Vec<_>>();
+
+    if leaves.is_empty() {
+        return None;
+    }
+
+    let estimated = leaves
+        .iter()
+        .filter(|item| item.estimate_seconds > 0)
+        .map(|item| item.estimate_seconds)
+        .collect::<Vec<_>>();
+
+    let mean = if estimated.is_empty() {
+        1.0
+    } else {
+        estimated.iter().sum::<i64>() as f64 / estimated.len() as f64
+    };
+
+    let weight = |item: &RecordPlanEntry| {
+        if item.estimate_seconds > 0 {
+            item.estimate_seconds as f64
+        } else {
+            mean
+        }
+    };
+
+    let total = leaves.iter().map(|item| weight(item)).sum::<f64>();
+    let finished = leaves
+        .iter()
+        .filter(|item| item.is_finished)
+        .map(|item| weight(item))
+        .sum::<f64>();
+
+    Some((finished / total) as f32)
+}
+
+// The count of the finished leaf items and of all the leaf items
+pub fn counts(plan: &[RecordPlanEntry]) -> (usize, usize) {
+    let leaves = (0..plan.len())
+        .filter(|index| !has_children(plan, *index))
+        .map(|index| &plan[index])
+        .collect::<Vec<_>>();
+
+    (
+        leaves.iter().filter(|item| item.is_finished).count(),
+        leaves.len(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn details(plan: &[RecordPlanEntry]) -> Vec<String> {
+        plan.iter()
+            .map(|item| format!("{}{}", "-".repeat(item.level as usize), item.detail))
+            .collect()
+    }
+
+    #[test]
+    fn test_normalize() {
+        let mut entry = RecordEntry {
+            plan: vec![
+                RecordPlanEntry {
+                    detail: "a".to_string(),
+                    level: 2,
+                    ..Default::default()
+                },
+                RecordPlanEntry {
+                    detail: " b ".to_string(),
+                    level: 3,
+                    ..Default::default()
+                },
+                RecordPlanEntry {
+                    detail: "c".to_string(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        entry.plan[1].due_date = "2025-3-5".to_string();
+        normalize(&mut entry).unwrap();
+        assert_eq!(details(&entry.plan), ["a", "-b", "c"]);
+        assert_eq!(entry.plan[1].due_date, "2025-03-05");
+
+        entry.plan[0].due_date = "tomorrow".to_string();
+        assert!(normalize(&mut entry).is_err());
+
+        // A flat plan saved before the levels were added
+        let entry: RecordPlanEntry =
+            serde_json::from_str(r#"{"detail":"a","is_finished":true}"#).unwrap();
+        assert_eq!(entry.level, 0);
+        assert!(entry.is_finished);
+    }
+
+    #[test]
+    fn test_indent_and_move() -> Result<()> {
+        let mut plan = vec![
+            RecordPlanEntry {
+                detail: "a".to_string(),
+                ..Default::default()
+            },
+            RecordPlanEntry {
+                detail: "b".to_string(),
+                ..Default::default()
+            },
+            RecordPlanEntry {
+                detail: "c".to_string(),
+                ..Default::default()
+            },
+            RecordPlanEntry {
+                detail: "d".to_string(),
+                ..Default::default()
+            },
+        ];
+        assert!(!indent(&mut plan, 0));
+        assert!(indent(&mut plan, 1));
+        assert!(!indent(&mut plan, 1));
+        assert!(indent(&mut plan, 2));
+        assert!(indent(&mut plan, 2));
+        assert_eq!(details(&plan), ["a", "-b", "--c", "d"]);
+        assert_eq!(subtree_end(&plan, 0), 3);
+        assert!(has_children(&plan, 1));
+
+        assert!(outdent(&mut plan, 1));
+        assert_eq!(details(&plan), ["a", "b", "-c", "d"]);
+
+        // A finished parent is not finished any more with a new sub-step
+        set_finished(&mut plan, 1, true);
+        assert!(indent(&mut plan, 3));
+        let mut entry = RecordEntry {
+            plan: plan.clone(),
+            ..Default::default()
+        };
+        normalize(&mut entry)?;
+        assert!(!entry.plan[1].is_finished);
+        assert!(outdent(&mut plan, 3));
+        assert!(!outdent(&mut plan, 0));
+
+        assert!(move_item(&mut plan, 1, 3));
+        assert_eq!(details(&plan), ["a", "d", "b", "-c"]);
+        assert!(move_item(&mut plan, 2, 0));
+        assert_eq!(details(&plan), ["b", "-c", "a", "d"]);
+        assert!(!move_item(&mut plan, 0, 1));
+
+        remove(&mut plan, 0);
+        assert_eq!(details(&plan), ["a", "d"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_finished_and_progress() {
+        let mut plan = vec![
+            RecordPlanEntry {
+                detail: "a".to_string(),
+                ..Default::default()
+            },
+            RecordPlanEntry {
+                detail: "b".to_string(),
+                level: 1,
+                ..Default::default()
+            },
+            RecordPlanEntry {
+                detail: "c".to_string(),
+                level: 1,
+                ..Default::default()
+            },
+            RecordPlanEntry {
+                detail: "d".to_string(),
+                ..Default::default()
+            },
+        ];
+        assert_eq!(progress(&[]), None);
+        assert_eq!(progress(&plan), Some(0.0));
+
+        set_finished(&mut plan, 1, true);
+        assert!(!plan[0].is_finished);
+        assert_eq!(counts(&plan), (1, 3));
+
+        set_finished(&mut plan, 2, true);
+        assert!(plan[0].is_finished);
+        assert_eq!(progress(&plan), Some((2.0_f64 / 3.0) as f32));
+
+        set_finished(&mut plan, 0, false);
+        assert!(!plan[1].is_finished && !plan[2].is_finished);
+
+        // 1h of 6h is done, `c` weighs 2h as the mean of `b` and `d`
+        plan[1].estimate_seconds = 3600;
+        plan[3].estimate_seconds = 3 * 3600;
+        plan[0].estimate_seconds = 100 * 3600;
+        set_finished(&mut plan, 1, true);
+        assert_eq!(progress(&plan), Some((1.0_f64 / 6.0) as f32));
+    }
+}
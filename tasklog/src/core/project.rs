@@ -0,0 +1,218 @@
+// Projects group the records. A record keeps the uuid of its project, an
+// empty uuid or the uuid of a removed project means it has no project. An
+// archived project is hidden from the switcher and takes no new records.
+
+use crate::db::def::{ProjectEntry, RecordEntry};
+use anyhow::{Result, bail};
+
+// The colors offered for a new project, the first one is the default
+pub const COLORS: [(&str, &str); 6] = [
+    ("Blue", "#3b82f6"),
+    ("Green", "#22c55e"),
+    ("Orange", "#f97316"),
+    ("Red", "#ef4444"),
+    ("Purple", "#a855f7"),
+    ("Gray", "#6b7280"),
+];
+
+// Which records of the list are shown
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Filter {
+    #[default]
+    All,
+    NoProject,
+    Project(String),
+}
+
+impl Filter {
+    pub fn matches(&self, entry: &RecordEntry, projects: &[ProjectEntry]) -> bool {
+        match self {
+            Filter::All => true,
+            Filter::NoProject => find_by_uuid(projects, &entry.project).is_none(),
+            Filter::Project(uuid) => entry.project == *uuid,
+        }
+    }
+}
+
+// Trims the fields and checks the color. An empty color takes the default.
+pub fn check_project(entry: &ProjectEntry) -> Result<ProjectEntry> {
+    let name = entry.name.trim().to_string();
+    if name.is_empty() {
+        bail!("project name is empty");
+    }
+
+    if name.contains(['\n', '\r']) {
+        bail!("project name `{name}` can not contain line breaks");
+    }
+
+    let color = match entry.color.trim() {
+        "" => COLORS[0].1.to_string(),
+        color => match parse_color(color) {
+            Some(rgb) => color_hex(rgb),
+            None => bail!(
+                "unknown color `{color}`, expect `#rrggbb` or one of {}",
+                COLORS
+                    .iter()
+                    .map(|(name, _)| name.to_lowercase())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        },
+    };
+
+    Ok(ProjectEntry {
+        uuid: entry.uuid.clone(),
+        name,
+        color,
+        description: entry.description.trim().to_string(),
+        is_archived: entry.is_archived,
+    })
+}
+
+// A `#rrggbb` value or the name of one of `COLORS`
+pub fn parse_color(text: &str) -> Option<[u8; 3]> {
+    let text = text.trim();
+    if let Some((_, hex)) = COLORS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(text))
+    {
+        return parse_color(hex);
+    }
+
+    let hex = text.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+
+    let mut rgb = [0; 3];
+    for (index, item) in rgb.iter_mut().enumerate() {
+        *item = u8::from_str_radix(&hex[index * 2..index * 2 + 2], 16).ok()?;
+    }
+
+    Some(rgb)
+}
+
+pub fn color_hex(rgb: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2])
+}
+
+pub fn find_by_uuid<'a>(projects: &'a [ProjectEntry], uuid: &str) -> Option<&'a ProjectEntry> {
+    if uuid.is_empty() {
+        return None;
+    }
+
+    projects.iter().find(|item| item.uuid == uuid)
+}
+
+// The name is matched without case first, then the uuid prefix
+pub fn find<'a>(projects: &'a [ProjectEntry], name_or_uuid: &str) -> Result<&'a ProjectEntry> {
+    let text = name_or_uuid.trim();
+    if let Some(entry) = projects
+        .iter()
+        .find(|item| item.name.eq_ignore_ascii_case(text))
+    {
+        return Ok(entry);
+    }
+
+    let mut entries = projects
+        .iter()
+        .filter(|item| !text.is_empty() && item.uuid.starts_with(text))
+        .collect::<Vec<_>>();
+
+    match entries.len() {
+        0 => bail!("no project matches `{text}`"),
+        1 => Ok(entries.remove(0)),
+        n => bail!("{n} projects match `{text}`, use the name or a longer uuid prefix"),
+    }
+}
+
+// The name of the project of the record, empty if it has none
+pub fn name_of(projects: &[ProjectEntry], uuid: &str) -> String {
+    find_by_uuid(projects, uuid)
+        .map(|item| item.name.clone())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_project() {
+        let entry = check_project(&ProjectEntry {
+            name: " Website ".to_string(),
+            color: "Green".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(entry.name, "Website");
+        assert_eq!(entry.color, "#22c55e");
+
+        let entry = check_project(&ProjectEntry {
+            name: "Home".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(entry.color, COLORS[0].1);
+
+        let entry = check_project(&ProjectEntry {
+            name: "Home".to_string(),
+            color: "#A0B0C0".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(entry.color, "#a0b0c0");
+
+        assert!(
+            check_project(&ProjectEntry {
+                name: " ".to_string(),
+                ..Default::default()
+            })
+            .is_err()
+        );
+        assert!(
+            check_project(&ProjectEntry {
+                name: "Home".to_string(),
+                color: "#12345".to_string(),
+                ..Default::default()
+            })
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_find_and_filter() {
+        let projects = vec![
+            ProjectEntry {
+                uuid: "aa11".to_string(),
+                name: "Website".to_string(),
+                ..Default::default()
+            },
+            ProjectEntry {
+                uuid: "ab22".to_string(),
+                name: "Home".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        assert_eq!(find(&projects, "home").unwrap().uuid, "ab22");
+        assert_eq!(find(&projects, "aa").unwrap().name, "Website");
+        assert!(find(&projects, "a").is_err());
+        assert!(find(&projects, "x").is_err());
+
+        let entry = RecordEntry {
+            project: "ab22".to_string(),
+            ..Default::default()
+        };
+        let removed = RecordEntry {
+            project: "cc33".to_string(),
+            ..Default::default()
+        };
+
+        assert!(Filter::All.matches(&entry, &projects));
+        assert!(Filter::Project("ab22".to_string()).matches(&entry, &projects));
+        assert!(!Filter::NoProject.matches(&entry, &projects));
+        assert!(Filter::NoProject.matches(&removed, &projects));
+        assert!(Filter::NoProject.matches(&RecordEntry::default(), &projects));
+    }
+}
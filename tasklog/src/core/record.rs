@@ -0,0 +1,262 @@
+use super::plan;
+use crate::db::def::{RecordEntry, RecordState};
+
+pub const DATE_FORMAT: &str = "%Y-%m-%d";
+
+pub fn today() -> String {
+    cutil::time::local_now(DATE_FORMAT)
+}
+
+// Returns the state a record should be in at `now`. `None` means the current
+// state is still valid or the dates can not be parsed.
+pub fn calc_state(
+    start_date: &str,
+    end_date: &str,
+    current_state: RecordState,
+    now: i64,
+) -> Option<RecordState> {
+    let start_timestamp = cutil::time::date_str_to_timestamp(start_date);
+    let end_timestamp = cutil::time::date_str_to_timestamp(end_date);
+
+    match current_state {
+        RecordState::Giveup | RecordState::Finished => None,
+        RecordState::NotStarted => {
+            let end_timestamp = end_timestamp.ok()?;
+            if now > end_timestamp {
+                Some(RecordState::Timeout)
+            } else if now >= start_timestamp.ok()? {
+                Some(RecordState::Running)
+            } else {
+                None
+            }
+        }
+        RecordState::Running => {
+            if now < start_timestamp.ok()? {
+                Some(RecordState::NotStarted)
+            } else if now > end_timestamp.ok()? {
+                Some(RecordState::Timeout)
+            } else {
+                None
+            }
+        }
+        RecordState::Timeout => {
+            if now < start_timestamp.ok()? {
+                Some(RecordState::NotStarted)
+            } else if now <= end_timestamp.ok()? {
+                Some(RecordState::Running)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+pub fn refresh_state(entry: &mut RecordEntry, now: i64) {
+    if let Some(state) = calc_state(&entry.start_date, &entry.end_date, entry.state, now) {
+        entry.state = state;
+    }
+}
+
+// Moves the entry to a user selected state and adjusts its dates. Only the
+// running, finished and giveup states can be chosen by the user.
+pub fn update_state(entry: &mut RecordEntry, state: RecordState, today: &str) -> bool {
+    let old_state = entry.state;
+
+    match state {
+        RecordState::Running => {
+            entry.state = RecordState::Running;
+            entry.finished_date.clear();
+
+            match old_state {
+                RecordState::NotStarted => {
+                    entry.start_date = today.to_string();
+                }
+                // The end date of a record finished early is still ahead
+                RecordState::Finished | RecordState::Timeout | RecordState::Giveup
+                    if !cutil::time::diff_dates_to_days(today, &entry.end_date)
+                        .is_ok_and(|days| days >= 0) =>
+                {
+                    entry.end_date = today.to_string();
+                }
+                _ => (),
+            }
+        }
+
+        // The planned end date is kept to compare it with the finished date
+        RecordState::Finished => {
+            entry.state = state;
+            entry.finished_date = today.to_string();
+
+            if old_state == RecordState::NotStarted {
+                entry.start_date = today.to_string();
+            }
+        }
+        RecordState::Giveup => {
+            entry.state = state;
+            entry.end_date = today.to_string();
+            entry.finished_date.clear();
+
+            if old_state == RecordState::NotStarted {
+                entry.start_date = entry.end_date.clone();
+            }
+        }
+        _ => return false,
+    }
+
+    if let Ok(diff_days) = cutil::time::diff_dates_to_days(&entry.start_date, &entry.end_date)
+        && diff_days < 0
+    {
+        entry.end_date = entry.start_date.clone();
+    }
+
+    true
+}
+
+// Ratio of the finished plan items weighted by their estimates, or of the
+// elapsed days when the record has no plan.
+pub fn progress(entry: &RecordEntry, today: &str) -> f32 {
+    if let Some(progress) = plan::progress(&entry.plan) {
+        return progress;
+    }
+
+    if entry.state == RecordState::Finished {
+        return 1.0;
+    }
+
+    let elapsed_days = cutil::time::diff_dates_to_days(&entry.start_date, today)
+        .unwrap_or_default()
+        .max(0);
+
+    let total_days = cutil::time::diff_dates_to_days(&entry.start_date, &entry.end_date)
+        .unwrap_or_default()
+        .max(1);
+
+    if elapsed_days >= total_days {
+        1.0
+    } else {
+        elapsed_days as f32 / total_days as f32
+    }
+}
+
+pub fn remain_days(start_date: &str, end_date: &str) -> i64 {
+    cutil::time::diff_dates_to_days(start_date, end_date)
+        .unwrap_or_default()
+        .max(0)
+}
+
+// The day the work on the record ended, the finished date of a finished
+// record, else its end date
+pub fn spent_end_date(entry: &RecordEntry) -> &str {
+    if entry.state == RecordState::Finished && !entry.finished_date.is_empty() {
+        &entry.finished_date
+    } else {
+        &entry.end_date
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::def::RecordPlanEntry;
+
+    fn ts(date: &str) -> i64 {
+        cutil::time::date_str_to_timestamp(date).unwrap()
+    }
+
+    #[test]
+    fn test_calc_state() {
+        let (start, end) = ("2025-03-10", "2025-03-20");
+
+        assert_eq!(
+            calc_state(start, end, RecordState::NotStarted, ts("2025-03-12")),
+            Some(RecordState::Running)
+        );
+        assert_eq!(
+            calc_state(start, end, RecordState::NotStarted, ts("2025-03-01")),
+            None
+        );
+        assert_eq!(
+            calc_state(start, end, RecordState::Running, ts("2025-03-21")),
+            Some(RecordState::Timeout)
+        );
+        assert_eq!(
+            calc_state(start, end, RecordState::Timeout, ts("2025-03-15")),
+            Some(RecordState::Running)
+        );
+        assert_eq!(
+            calc_state(start, end, RecordState::Finished, ts("2025-03-21")),
+            None
+        );
+        assert_eq!(
+            calc_state("bad", end, RecordState::Running, ts("2025-03-12")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_update_state() {
+        let mut item = RecordEntry {
+            start_date: "2025-03-10".to_string(),
+            end_date: "2025-03-20".to_string(),
+            state: RecordState::NotStarted,
+            ..Default::default()
+        };
+        assert!(update_state(&mut item, RecordState::Finished, "2025-03-05"));
+        assert_eq!(item.state, RecordState::Finished);
+        assert_eq!(item.start_date, "2025-03-05");
+        assert_eq!(item.end_date, "2025-03-20");
+        assert_eq!(item.finished_date, "2025-03-05");
+
+        // Reopened before its planned end date
+        assert!(update_state(&mut item, RecordState::Running, "2025-03-08"));
+        assert_eq!(item.end_date, "2025-03-20");
+        assert_eq!(item.finished_date, "");
+
+        let mut item = RecordEntry {
+            start_date: "2025-03-10".to_string(),
+            end_date: "2025-03-20".to_string(),
+            state: RecordState::Timeout,
+            ..Default::default()
+        };
+        assert!(update_state(&mut item, RecordState::Finished, "2025-03-25"));
+        assert_eq!(item.end_date, "2025-03-20");
+        assert_eq!(item.finished_date, "2025-03-25");
+        assert!(update_state(&mut item, RecordState::Running, "2025-03-26"));
+        assert_eq!(item.end_date, "2025-03-26");
+
+        let mut item = RecordEntry {
+            start_date: "2025-03-10".to_string(),
+            end_date: "2025-03-20".to_string(),
+            state: RecordState::Running,
+            ..Default::default()
+        };
+        assert!(update_state(&mut item, RecordState::Giveup, "2025-03-08"));
+        assert_eq!(item.end_date, "2025-03-10");
+
+        assert!(!update_state(&mut item, RecordState::Timeout, "2025-03-08"));
+    }
+
+    #[test]
+    fn test_progress_and_days() {
+        let mut item = RecordEntry {
+            start_date: "2025-03-10".to_string(),
+            end_date: "2025-03-20".to_string(),
+            state: RecordState::Running,
+            ..Default::default()
+        };
+        assert_eq!(progress(&item, "2025-03-15"), 0.5);
+        assert_eq!(progress(&item, "2025-03-25"), 1.0);
+
+        item.plan = vec![
+            RecordPlanEntry {
+                detail: "a".to_string(),
+                is_finished: true,
+                ..Default::default()
+            },
+            RecordPlanEntry::default(),
+        ];
+        assert_eq!(progress(&item, "2025-03-15"), 0.5);
+
+        assert_eq!(remain_days("2025-03-20", "2025-03-10"), 0);
+    }
+}
@@ -0,0 +1,429 @@
+use super::record::{self, DATE_FORMAT};
+use crate::db::def::{RecordEntry, RecordPlanEntry, RecordState};
+use anyhow::{Context, Result, bail};
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use uuid::Uuid;
+
+const WEEKDAYS: [(&str, Weekday); 7] = [
+    ("MO", Weekday::Mon),
+    ("TU", Weekday::Tue),
+    ("WE", Weekday::Wed),
+    ("TH", Weekday::Thu),
+    ("FR", Weekday::Fri),
+    ("SA", Weekday::Sat),
+    ("SU", Weekday::Sun),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl Frequency {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+        }
+    }
+}
+
+// The repeat rule of a record. It is stored as a RRULE subset, e.g.
+// `FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,FR`. Without `weekdays` or `month_day`
+// the weekday or the day of the start date is used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recurrence {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub weekdays: Vec<Weekday>,
+
+    // 1 to 31, or -1 for the last day of the month
+    pub month_day: Option<i32>,
+}
+
+impl Recurrence {
+    fn new(frequency: Frequency) -> Self {
+        Self {
+            frequency,
+            interval: 1,
+            weekdays: vec![],
+            month_day: None,
+        }
+    }
+
+    // Accepts the RRULE subset with FREQ, INTERVAL, BYDAY and BYMONTHDAY, or
+    // the short forms `daily`, `weekly`, `weekly:mo,fr`, `monthly`,
+    // `monthly:15` and `every:3` for every 3 days.
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim();
+
+        if text.contains('=') {
+            return parse_rrule(text.strip_prefix("RRULE:").unwrap_or(text));
+        }
+
+        let (name, value) = match text.split_once(':') {
+            Some((name, value)) => (name.trim().to_lowercase(), Some(value.trim())),
+            None => (text.to_lowercase(), None),
+        };
+
+        let mut rule = match name.as_str() {
+            "daily" => Recurrence::new(Frequency::Daily),
+            "weekly" => Recurrence::new(Frequency::Weekly),
+            "monthly" => Recurrence::new(Frequency::Monthly),
+            "every" => {
+                let mut rule = Recurrence::new(Frequency::Daily);
+                rule.interval = parse_interval(value.unwrap_or_default())?;
+                return Ok(rule);
+            }
+            _ => bail!("unknown repeat rule `{text}`"),
+        };
+
+        match (rule.frequency, value) {
+            (_, None) => (),
+            (Frequency::Weekly, Some(value)) => rule.weekdays = parse_weekdays(value)?,
+            (Frequency::Monthly, Some(value)) => rule.month_day = Some(parse_month_day(value)?),
+            _ => bail!("unknown repeat rule `{text}`"),
+        }
+
+        Ok(rule)
+    }
+
+    pub fn to_rrule(&self) -> String {
+        let mut parts = vec![format!("FREQ={}", self.frequency.as_str())];
+
+        if self.interval > 1 {
+            parts.push(format!("INTERVAL={}", self.interval));
+        }
+
+        if !self.weekdays.is_empty() {
+            let days = WEEKDAYS
+                .iter()
+                .filter(|(_, day)| self.weekdays.contains(day))
+                .map(|(name, _)| *name)
+                .collect::<Vec<_>>();
+            parts.push(format!("BYDAY={}", days.join(",")));
+        }
+
+        if let Some(day) = self.month_day {
+            parts.push(format!("BYMONTHDAY={day}"));
+        }
+
+        parts.join(";")
+    }
+
+    // The first date after `date` matching the rule. The weeks and months of
+    // the interval are counted from the week and month of `date`.
+    pub fn next_date(&self, date: NaiveDate) -> NaiveDate {
+        let interval = self.interval.max(1);
+
+        match self.frequency {
+            Frequency::Daily => date + Duration::days(interval as i64),
+            Frequency::Weekly => {
+                let weekdays = if self.weekdays.is_empty() {
+                    vec![date.weekday()]
+                } else {
+                    self.weekdays.clone()
+                };
+
+                let week_start = |date: NaiveDate| {
+                    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+                };
+
+                let mut next = date + Duration::days(1);
+                loop {
+                    let weeks = (week_start(next) - week_start(date)).num_days() / 7;
+                    if weeks % interval as i64 == 0 && weekdays.contains(&next.weekday()) {
+                        return next;
+                    }
+                    next += Duration::days(1);
+                }
+            }
+            Frequency::Monthly => {
+                let day = self.month_day.unwrap_or(date.day() as i32);
+                let months = date.year() * 12 + date.month0() as i32;
+
+                (0..)
+                    .map(|index| month_day_date(months + index * interval as i32, day))
+                    .find(|next| *next > date)
+                    .unwrap()
+            }
+        }
+    }
+}
+
+// Normalizes the repeat rule of the entry to a RRULE
+pub fn normalize(entry: &mut RecordEntry) -> Result<()> {
+    if !entry.recurrence.trim().is_empty() {
+        entry.recurrence = Recurrence::parse(&entry.recurrence)?.to_rrule();
+    } else {
+        entry.recurrence.clear();
+    }
+
+    Ok(())
+}
+
+// Creates the next occurrence of a repeating record with the dates shifted by
+// the rule and the plan reset. The occurrences which would already be over at
+// `now` are skipped. The rule moves to the new record, so an occurrence is
+// never generated twice from the same record.
+pub fn next_occurrence(entry: &mut RecordEntry, now: i64) -> Result<Option<RecordEntry>> {
+    if entry.recurrence.is_empty() {
+        return Ok(None);
+    }
+
+    let rule = Recurrence::parse(&entry.recurrence)?;
+    let today = parse_date(&cutil::time::timestamp_to_local(now, DATE_FORMAT))?;
+    let mut start_date = parse_date(&entry.start_date)?;
+    let days = (parse_date(&entry.end_date)? - start_date)
+        .num_days()
+        .max(0);
+
+    let old_start_date = start_date;
+    loop {
+        start_date = rule.next_date(start_date);
+        if start_date + Duration::days(days) >= today {
+            break;
+        }
+    }
+
+    // The due dates of the plan steps keep their distance to the start date
+    let shift = start_date - old_start_date;
+
+    let mut next = RecordEntry {
+        uuid: Uuid::new_v4().to_string(),
+        start_date: start_date.format(DATE_FORMAT).to_string(),
+        end_date: (start_date + Duration::days(days))
+            .format(DATE_FORMAT)
+            .to_string(),
+        title: entry.title.clone(),
+        plan: entry
+            .plan
+            .iter()
+            .map(|item| RecordPlanEntry {
+                is_finished: false,
+                due_date: parse_date(&item.due_date)
+                    .map(|date| (date + shift).format(DATE_FORMAT).to_string())
+                    .unwrap_or_default(),
+                ..item.clone()
+            })
+            .collect(),
+        tags: entry.tags.clone(),
+        state: RecordState::NotStarted,
+        recurrence: std::mem::take(&mut entry.recurrence),
+        ..Default::default()
+    };
+    record::refresh_state(&mut next, now);
+
+    Ok(Some(next))
+}
+
+fn parse_rrule(text: &str) -> Result<Recurrence> {
+    let mut frequency = None;
+    let mut interval = 1;
+    let mut weekdays = vec![];
+    let mut month_day = None;
+
+    for part in text.split(';').filter(|part| !part.trim().is_empty()) {
+        let (key, value) = part
+            .split_once('=')
+            .with_context(|| format!("invalid rule part `{part}`"))?;
+
+        match key.trim().to_uppercase().as_str() {
+            "FREQ" => {
+                frequency = match value.trim().to_uppercase().as_str() {
+                    "DAILY" => Some(Frequency::Daily),
+                    "WEEKLY" => Some(Frequency::Weekly),
+                    "MONTHLY" => Some(Frequency::Monthly),
+                    _ => bail!("unsupported frequency `{value}`"),
+                }
+            }
+            "INTERVAL" => interval = parse_interval(value)?,
+            "BYDAY" => weekdays = parse_weekdays(value)?,
+            "BYMONTHDAY" => month_day = Some(parse_month_day(value)?),
+            _ => bail!("unsupported rule part `{key}`"),
+        }
+    }
+
+    let Some(frequency) = frequency else {
+        bail!("missing FREQ in `{text}`");
+    };
+
+    if !weekdays.is_empty() && frequency != Frequency::Weekly {
+        bail!("BYDAY only works with FREQ=WEEKLY");
+    }
+
+    if month_day.is_some() && frequency != Frequency::Monthly {
+        bail!("BYMONTHDAY only works with FREQ=MONTHLY");
+    }
+
+    Ok(Recurrence {
+        frequency,
+        interval,
+        weekdays,
+        month_day,
+    })
+}
+
+fn parse_interval(value: &str) -> Result<u32> {
+    match value.trim().parse::<u32>() {
+        Ok(interval) if interval > 0 => Ok(interval),
+        _ => bail!("interval should be a positive number, got `{value}`"),
+    }
+}
+
+fn parse_weekdays(value: &str) -> Result<Vec<Weekday>> {
+    value
+        .split(',')
+        .map(|day| {
+            let day = day.trim().to_uppercase();
+            WEEKDAYS
+                .iter()
+                .find(|(name, _)| day.starts_with(name))
+                .map(|(_, weekday)| *weekday)
+                .with_context(|| format!("unknown weekday `{day}`"))
+        })
+        .collect()
+}
+
+fn parse_month_day(value: &str) -> Result<i32> {
+    match value.trim().parse::<i32>() {
+        Ok(day) if (1..=31).contains(&day) || day == -1 => Ok(day),
+        _ => bail!("day of month should be in 1..=31 or -1, got `{value}`"),
+    }
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(date, DATE_FORMAT)
+        .with_context(|| format!("invalid date `{date}`, expect `YYYY-MM-DD`"))
+}
+
+// The day is clamped to the last day of the month
+fn month_day_date(months: i32, day: i32) -> NaiveDate {
+    let (year, month) = (months.div_euclid(12), months.rem_euclid(12) as u32 + 1);
+    let first_day = NaiveDate::from_ymd_opt(year, month, 1).unwrap();
+    let last_day = (first_day + Duration::days(32)).with_day(1).unwrap() - Duration::days(1);
+
+    if day < 0 || day as u32 >= last_day.day() {
+        last_day
+    } else {
+        first_day.with_day(day as u32).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> NaiveDate {
+        parse_date(text).unwrap()
+    }
+
+    #[test]
+    fn test_parse() -> Result<()> {
+        assert_eq!(Recurrence::parse("daily")?.to_rrule(), "FREQ=DAILY");
+        assert_eq!(
+            Recurrence::parse("every:3")?.to_rrule(),
+            "FREQ=DAILY;INTERVAL=3"
+        );
+        assert_eq!(
+            Recurrence::parse("Weekly: fr, mon")?.to_rrule(),
+            "FREQ=WEEKLY;BYDAY=MO,FR"
+        );
+        assert_eq!(
+            Recurrence::parse("monthly:-1")?.to_rrule(),
+            "FREQ=MONTHLY;BYMONTHDAY=-1"
+        );
+        assert_eq!(
+            Recurrence::parse("RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=TU")?.to_rrule(),
+            "FREQ=WEEKLY;INTERVAL=2;BYDAY=TU"
+        );
+
+        for text in [
+            "yearly",
+            "every:0",
+            "monthly:32",
+            "FREQ=DAILY;BYDAY=MO",
+            "FREQ=DAILY;COUNT=3",
+            "INTERVAL=2",
+        ] {
+            assert!(Recurrence::parse(text).is_err(), "{text}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_next_date() -> Result<()> {
+        let rule = Recurrence::parse("every:3")?;
+        assert_eq!(rule.next_date(date("2025-03-30")), date("2025-04-02"));
+
+        // 2025-03-05 is a Wednesday
+        let rule = Recurrence::parse("weekly")?;
+        assert_eq!(rule.next_date(date("2025-03-05")), date("2025-03-12"));
+
+        let rule = Recurrence::parse("weekly:mo,fr")?;
+        assert_eq!(rule.next_date(date("2025-03-05")), date("2025-03-07"));
+        assert_eq!(rule.next_date(date("2025-03-07")), date("2025-03-10"));
+
+        let rule = Recurrence::parse("FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,FR")?;
+        assert_eq!(rule.next_date(date("2025-03-05")), date("2025-03-07"));
+        assert_eq!(rule.next_date(date("2025-03-07")), date("2025-03-17"));
+
+        let rule = Recurrence::parse("monthly")?;
+        assert_eq!(rule.next_date(date("2025-01-31")), date("2025-02-28"));
+        assert_eq!(rule.next_date(date("2025-12-15")), date("2026-01-15"));
+
+        let rule = Recurrence::parse("monthly:15")?;
+        assert_eq!(rule.next_date(date("2025-03-10")), date("2025-03-15"));
+        assert_eq!(rule.next_date(date("2025-03-15")), date("2025-04-15"));
+
+        let rule = Recurrence::parse("monthly:-1")?;
+        assert_eq!(rule.next_date(date("2024-01-31")), date("2024-02-29"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_next_occurrence() -> Result<()> {
+        let mut entry = RecordEntry {
+            uuid: "uuid-1".to_string(),
+            start_date: "2025-03-03".to_string(),
+            end_date: "2025-03-05".to_string(),
+            title: "chore".to_string(),
+            plan: vec![RecordPlanEntry {
+                detail: "a".to_string(),
+                is_finished: true,
+                due_date: "2025-03-04".to_string(),
+                ..Default::default()
+            }],
+            state: RecordState::Finished,
+            recurrence: "FREQ=WEEKLY".to_string(),
+            ..Default::default()
+        };
+
+        let now = cutil::time::date_str_to_timestamp("2025-03-04")?;
+        let next = next_occurrence(&mut entry, now)?.unwrap();
+
+        assert_ne!(next.uuid, entry.uuid);
+        assert_eq!(next.start_date, "2025-03-10");
+        assert_eq!(next.end_date, "2025-03-12");
+        assert_eq!(next.state, RecordState::NotStarted);
+        assert_eq!(next.recurrence, "FREQ=WEEKLY");
+        assert!(!next.plan[0].is_finished);
+        assert_eq!(next.plan[0].due_date, "2025-03-11");
+        assert!(entry.recurrence.is_empty());
+        assert!(next_occurrence(&mut entry, now)?.is_none());
+
+        // The occurrences already over are skipped
+        let mut entry = next;
+        let now = cutil::time::date_str_to_timestamp("2025-04-01")?;
+        let next = next_occurrence(&mut entry, now)?.unwrap();
+        assert_eq!(next.start_date, "2025-03-31");
+        assert_eq!(next.state, RecordState::Running);
+
+        Ok(())
+    }
+}
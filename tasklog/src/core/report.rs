@@ -0,0 +1,847 @@
+// A status report of a period: the records started, finished, given up and
+// timed out in it, the plan steps completed, the time tracked per tag and the
+// items still open after their deadline. It is rendered to Markdown or to a
+// self-contained HTML page colored like the charts of the statistic.
+
+use super::{
+    plan,
+    record::DATE_FORMAT,
+    session,
+    stats::{self, Range, parse_date},
+};
+use crate::db::def::{RecordEntry, RecordState, SessionEntry};
+use anyhow::{Result, bail};
+use chrono::{Duration, NaiveDate};
+use std::{collections::HashMap, path::Path};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    #[default]
+    Markdown,
+    Html,
+}
+
+impl Format {
+    pub fn parse(text: &str) -> Result<Self> {
+        match text.trim().to_lowercase().as_str() {
+            "md" | "markdown" => Ok(Format::Markdown),
+            "html" | "htm" => Ok(Format::Html),
+            _ => bail!("unknown report format `{text}`, expect md or html"),
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        Format::parse(path.extension()?.to_str()?).ok()
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Markdown => "md",
+            Format::Html => "html",
+        }
+    }
+}
+
+// The state colors of the light theme, for a report written without the app
+pub fn default_color(state: RecordState) -> String {
+    match state {
+        RecordState::Running => "#5E79FF",
+        RecordState::Finished => "#67C23A",
+        RecordState::Giveup => "#F56C6C",
+        RecordState::Timeout => "#E6A23C",
+        RecordState::NotStarted => "#909090",
+    }
+    .to_string()
+}
+
+// The first and the last day of the report, both included. An open range
+// starts at the first record and ends today.
+pub fn period(range: &Range, entries: &[RecordEntry], today: NaiveDate) -> (NaiveDate, NaiveDate) {
+    let (start, end) = range.bounds(&today.format(DATE_FORMAT).to_string());
+    let end = end.unwrap_or(today);
+    let start = start.unwrap_or_else(|| {
+        entries
+            .iter()
+            .filter_map(|entry| parse_date(&entry.start_date))
+            .min()
+            .unwrap_or(end)
+    });
+
+    (start.min(end), end)
+}
+
+// A plan step with the title of its record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub title: String,
+    pub detail: String,
+    pub date: NaiveDate,
+}
+
+// A record or a plan step which is not done after its deadline. `detail` is
+// empty for a record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Overdue {
+    pub title: String,
+    pub detail: String,
+    pub state: RecordState,
+    pub due_date: NaiveDate,
+    pub days: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub today: NaiveDate,
+
+    // Each list is sorted by the date it is in the report for
+    pub started: Vec<RecordEntry>,
+    pub finished: Vec<(RecordEntry, NaiveDate)>,
+    pub given_up: Vec<RecordEntry>,
+    pub timed_out: Vec<RecordEntry>,
+    pub steps: Vec<Step>,
+
+    // The seconds tracked in the period for each tag, most first. A record is
+    // counted for each of its tags, and the records without a tag are under an
+    // empty tag at the end.
+    pub tags: Vec<(String, i64)>,
+    pub tracked_seconds: i64,
+
+    // Open today, the most overdue first
+    pub overdue: Vec<Overdue>,
+}
+
+impl Report {
+    // A record is given up or timed out on its end date. The plan steps have
+    // a finished date only if the change table has it. The sessions of the
+    // records which are not in `entries` are left out, and a running session
+    // counts up to `now`.
+    pub fn new(
+        entries: &[RecordEntry],
+        sessions: &[SessionEntry],
+        (start, end): (NaiveDate, NaiveDate),
+        today: NaiveDate,
+        now: i64,
+    ) -> Self {
+        let in_period =
+            |date: Option<NaiveDate>| date.is_some_and(|date| date >= start && date <= end);
+
+        let mut started = entries
+            .iter()
+            .filter(|entry| {
+                entry.state != RecordState::NotStarted && in_period(parse_date(&entry.start_date))
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        started.sort_by(|a, b| (&a.start_date, &a.title).cmp(&(&b.start_date, &b.title)));
+
+        let mut finished = entries
+            .iter()
+            .filter_map(|entry| {
+                let date = stats::finish_date(entry, today);
+                in_period(date).then(|| (entry.clone(), date.unwrap_or(today)))
+            })
+            .collect::<Vec<_>>();
+        finished.sort_by(|a, b| (a.1, &a.0.title).cmp(&(b.1, &b.0.title)));
+
+        let ended = |state: RecordState| {
+            let mut items = entries
+                .iter()
+                .filter(|entry| entry.state == state && in_period(parse_date(&entry.end_date)))
+                .cloned()
+                .collect::<Vec<_>>();
+            items.sort_by(|a, b| (&a.end_date, &a.title).cmp(&(&b.end_date, &b.title)));
+            items
+        };
+
+        let mut steps = vec![];
+        for entry in entries.iter() {
+            for (index, item) in entry.plan.iter().enumerate() {
+                if plan::has_children(&entry.plan, index) || !item.is_finished {
+                    continue;
+                }
+
+                if let Some(date) =
+                    parse_date(&item.finished_date).filter(|date| in_period(Some(*date)))
+                {
+                    steps.push(Step {
+                        title: entry.title.clone(),
+                        detail: item.detail.clone(),
+                        date,
+                    });
+                }
+            }
+        }
+        steps.sort_by(|a, b| (a.date, &a.title).cmp(&(b.date, &b.title)));
+
+        let tracked = tracked_by_record(sessions, (start, end), now);
+        let tracked_seconds = entries
+            .iter()
+            .filter_map(|entry| tracked.get(entry.uuid.as_str()))
+            .sum();
+
+        Report {
+            start,
+            end,
+            today,
+            started,
+            finished,
+            given_up: ended(RecordState::Giveup),
+            timed_out: ended(RecordState::Timeout),
+            steps,
+            tags: tracked_by_tag(entries, &tracked),
+            tracked_seconds,
+            overdue: overdue(entries, today),
+        }
+    }
+
+    pub fn render(&self, format: Format, color: &dyn Fn(RecordState) -> String) -> String {
+        match format {
+            Format::Markdown => self.to_markdown(),
+            Format::Html => self.to_html(color),
+        }
+    }
+
+    // The rows of the summary and the state whose color they are shown in
+    fn summary(&self) -> [(&'static str, usize, RecordState); 6] {
+        [
+            ("Started", self.started.len(), RecordState::Running),
+            ("Finished", self.finished.len(), RecordState::Finished),
+            ("Given up", self.given_up.len(), RecordState::Giveup),
+            ("Timed out", self.timed_out.len(), RecordState::Timeout),
+            (
+                "Plan steps completed",
+                self.steps.len(),
+                RecordState::Finished,
+            ),
+            ("Open overdue", self.overdue.len(), RecordState::Timeout),
+        ]
+    }
+
+    fn title(&self) -> String {
+        format!(
+            "Status report {} - {}",
+            self.start.format(DATE_FORMAT),
+            self.end.format(DATE_FORMAT)
+        )
+    }
+
+    // The sections of records with the text of each line
+    fn record_sections(&self) -> Vec<(&'static str, RecordState, Vec<String>)> {
+        vec![
+            (
+                "Started",
+                RecordState::Running,
+                self.started
+                    .iter()
+                    .map(|entry| {
+                        format!(
+                            "{}{} - {} -> {}",
+                            entry.title,
+                            tags(entry),
+                            entry.start_date,
+                            entry.end_date
+                        )
+                    })
+                    .collect(),
+            ),
+            (
+                "Finished",
+                RecordState::Finished,
+                self.finished
+                    .iter()
+                    .map(|(entry, date)| {
+                        format!(
+                            "{}{} - {}{}",
+                            entry.title,
+                            tags(entry),
+                            date,
+                            slip(entry, *date)
+                        )
+                    })
+                    .collect(),
+            ),
+            (
+                "Given up",
+                RecordState::Giveup,
+                self.given_up
+                    .iter()
+                    .map(|entry| format!("{}{} - {}", entry.title, tags(entry), entry.end_date))
+                    .collect(),
+            ),
+            (
+                "Timed out",
+                RecordState::Timeout,
+                self.timed_out
+                    .iter()
+                    .map(|entry| format!("{}{} - due {}", entry.title, tags(entry), entry.end_date))
+                    .collect(),
+            ),
+            (
+                "Plan steps completed",
+                RecordState::Finished,
+                self.steps
+                    .iter()
+                    .map(|item| format!("{}: {} - {}", item.title, item.detail, item.date))
+                    .collect(),
+            ),
+        ]
+    }
+
+    fn overdue_lines(&self) -> Vec<String> {
+        self.overdue
+            .iter()
+            .map(|item| {
+                let title = if item.detail.is_empty() {
+                    item.title.clone()
+                } else {
+                    format!("{}: {}", item.title, item.detail)
+                };
+                format!(
+                    "{title} - due {}, {} overdue ({})",
+                    item.due_date,
+                    days(item.days),
+                    item.state.as_str()
+                )
+            })
+            .collect()
+    }
+
+    fn to_markdown(&self) -> String {
+        let mut text = format!(
+            "# {}\n\nWritten on {}.\n\n",
+            self.title(),
+            self.today.format(DATE_FORMAT)
+        );
+
+        text.push_str("| Item | Count |\n| --- | ---: |\n");
+        for (label, value, _) in self.summary() {
+            text.push_str(&format!("| {label} | {value} |\n"));
+        }
+        text.push_str(&format!(
+            "| Time tracked | {} |\n",
+            session::format_duration(self.tracked_seconds)
+        ));
+
+        for (title, _, lines) in self.record_sections() {
+            text.push_str(&format!("\n## {title}\n\n"));
+            push_markdown_list(&mut text, &lines);
+        }
+
+        text.push_str("\n## Time per tag\n\n");
+        if self.tags.is_empty() {
+            text.push_str("None\n");
+        } else {
+            text.push_str("| Tag | Time |\n| --- | ---: |\n");
+            for (tag, seconds) in self.tags.iter() {
+                text.push_str(&format!(
+                    "| {} | {} |\n",
+                    single_line(&tag_label(tag)).replace('|', "\\|"),
+                    session::format_duration(*seconds)
+                ));
+            }
+        }
+
+        text.push_str("\n## Open overdue\n\n");
+        push_markdown_list(&mut text, &self.overdue_lines());
+
+        text
+    }
+
+    fn to_html(&self, color: &dyn Fn(RecordState) -> String) -> String {
+        let title = escape_html(&self.title());
+        let mut body = format!(
+            "<h1>{title}</h1>\n<p class=\"none\">Written on {}.</p>\n",
+            self.today.format(DATE_FORMAT)
+        );
+
+        let summary = self.summary();
+        let max = summary
+            .iter()
+            .map(|(_, count, _)| *count)
+            .max()
+            .unwrap_or_default();
+        body.push_str("<table class=\"bars\">\n");
+        for (label, count, state) in summary.iter() {
+            push_html_bar(
+                &mut body,
+                label,
+                &count.to_string(),
+                bar_width(*count as i64, max as i64),
+                &color(*state),
+            );
+        }
+        body.push_str(&format!(
+            "<tr><td>Time tracked</td><td></td><td class=\"value\">{}</td></tr>\n</table>\n",
+            session::format_duration(self.tracked_seconds)
+        ));
+
+        for (section, state, lines) in self.record_sections() {
+            push_html_list(&mut body, section, &color(state), &lines);
+        }
+
+        body.push_str("<h2>Time per tag</h2>\n");
+        if self.tags.is_empty() {
+            body.push_str("<p class=\"none\">None</p>\n");
+        } else {
+            let max = self
+                .tags
+                .iter()
+                .map(|(_, seconds)| *seconds)
+                .max()
+                .unwrap_or_default();
+            body.push_str("<table class=\"bars\">\n");
+            for (tag, seconds) in self.tags.iter() {
+                push_html_bar(
+                    &mut body,
+                    &tag_label(tag),
+                    &session::format_duration(*seconds),
+                    bar_width(*seconds, max),
+                    &color(RecordState::Running),
+                );
+            }
+            body.push_str("</table>\n");
+        }
+
+        push_html_list(
+            &mut body,
+            "Open overdue",
+            &color(RecordState::Timeout),
+            &self.overdue_lines(),
+        );
+
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body {{ font-family: sans-serif; max-width: 860px; margin: 32px auto; padding: 0 16px; color: #303133; }}
+h2 {{ margin-top: 32px; font-size: 20px; }}
+ul {{ list-style: none; padding: 0; }}
+li {{ margin: 6px 0; padding: 4px 10px; border-left: 4px solid; }}
+.bars {{ border-collapse: collapse; width: 100%; }}
+.bars td {{ padding: 4px 8px; white-space: nowrap; }}
+.bars td.bar {{ width: 100%; }}
+.bars td.bar div {{ height: 14px; border-radius: 3px; }}
+.value {{ text-align: right; }}
+.none {{ color: #909090; }}
+</style>
+</head>
+<body>
+{body}</body>
+</html>
+"#
+        )
+    }
+}
+
+// Seconds of the sessions of each record in the period
+fn tracked_by_record(
+    sessions: &[SessionEntry],
+    (start, end): (NaiveDate, NaiveDate),
+    now: i64,
+) -> HashMap<&str, i64> {
+    let timestamp = |date: NaiveDate| {
+        cutil::time::date_str_to_timestamp(&date.format(DATE_FORMAT).to_string()).ok()
+    };
+    let (Some(first), Some(last)) = (timestamp(start), timestamp(end + Duration::days(1))) else {
+        return HashMap::new();
+    };
+
+    let mut seconds = HashMap::<&str, i64>::new();
+    for item in sessions.iter() {
+        let end = if session::is_running(item) {
+            now
+        } else {
+            item.end
+        };
+        let value = end.min(last) - item.start.max(first);
+        if value > 0 {
+            *seconds.entry(item.record_uuid.as_str()).or_default() += value;
+        }
+    }
+
+    seconds
+}
+
+// See `Report::tags`
+fn tracked_by_tag(entries: &[RecordEntry], tracked: &HashMap<&str, i64>) -> Vec<(String, i64)> {
+    let mut items: Vec<(String, i64)> = vec![];
+    let mut untagged = 0;
+
+    for entry in entries.iter() {
+        let Some(value) = tracked.get(entry.uuid.as_str()).copied() else {
+            continue;
+        };
+
+        if entry.tags.is_empty() {
+            untagged += value;
+        }
+
+        for tag in entry.tags.iter() {
+            match items
+                .iter_mut()
+                .find(|(name, _)| name.eq_ignore_ascii_case(tag))
+            {
+                Some((_, item)) => *item += value,
+                None => items.push((tag.clone(), value)),
+            }
+        }
+    }
+
+    items.sort_by(|a, b| {
+        b.1.cmp(&a.1)
+            .then_with(|| a.0.to_lowercase().cmp(&b.0.to_lowercase()))
+    });
+    if untagged > 0 {
+        items.push((String::default(), untagged));
+    }
+
+    items
+}
+
+// A record is overdue when its end date passed or it timed out already, a plan
+// step of an open record when its due date passed
+fn overdue(entries: &[RecordEntry], today: NaiveDate) -> Vec<Overdue> {
+    let mut items = vec![];
+
+    for entry in entries.iter() {
+        if matches!(entry.state, RecordState::Finished | RecordState::Giveup) {
+            continue;
+        }
+
+        if let Some(end) = parse_date(&entry.end_date)
+            && (end < today || entry.state == RecordState::Timeout)
+        {
+            items.push(Overdue {
+                title: entry.title.clone(),
+                detail: String::default(),
+                state: entry.state,
+                due_date: end,
+                days: (today - end).num_days().max(0),
+            });
+        }
+
+        for (index, item) in entry.plan.iter().enumerate() {
+            if item.is_finished || plan::has_children(&entry.plan, index) {
+                continue;
+            }
+
+            if let Some(due) = parse_date(&item.due_date).filter(|due| *due < today) {
+                items.push(Overdue {
+                    title: entry.title.clone(),
+                    detail: item.detail.clone(),
+                    state: entry.state,
+                    due_date: due,
+                    days: (today - due).num_days(),
+                });
+            }
+        }
+    }
+
+    items.sort_by(|a, b| (b.days, &a.title).cmp(&(a.days, &b.title)));
+    items
+}
+
+fn tags(entry: &RecordEntry) -> String {
+    if entry.tags.is_empty() {
+        String::default()
+    } else {
+        format!(" [{}]", entry.tags.join(", "))
+    }
+}
+
+fn slip(entry: &RecordEntry, finished: NaiveDate) -> String {
+    match parse_date(&entry.end_date) {
+        Some(end) if finished > end => format!(", {} late", days((finished - end).num_days())),
+        _ => String::default(),
+    }
+}
+
+fn days(count: i64) -> String {
+    if count == 1 {
+        "1 day".to_string()
+    } else {
+        format!("{count} days")
+    }
+}
+
+fn tag_label(tag: &str) -> String {
+    if tag.is_empty() {
+        "No tag".to_string()
+    } else {
+        tag.to_string()
+    }
+}
+
+fn single_line(text: &str) -> String {
+    text.lines().collect::<Vec<_>>().join(" ")
+}
+
+fn push_markdown_list(text: &mut String, lines: &[String]) {
+    if lines.is_empty() {
+        text.push_str("None\n");
+    }
+
+    for line in lines.iter() {
+        text.push_str(&format!("- {}\n", single_line(line)));
+    }
+}
+
+// The percent of the widest bar
+fn bar_width(value: i64, max: i64) -> i64 {
+    if max <= 0 { 0 } else { value * 100 / max }
+}
+
+fn push_html_bar(body: &mut String, label: &str, value: &str, width: i64, color: &str) {
+    body.push_str(&format!(
+        "<tr><td>{}</td><td class=\"bar\"><div style=\"width: {width}%; background: {};\"></div></td><td class=\"value\">{}</td></tr>\n",
+        escape_html(label),
+        escape_html(color),
+        escape_html(value)
+    ));
+}
+
+fn push_html_list(body: &mut String, title: &str, color: &str, lines: &[String]) {
+    body.push_str(&format!("<h2>{}</h2>\n", escape_html(title)));
+
+    if lines.is_empty() {
+        body.push_str("<p class=\"none\">None</p>\n");
+        return;
+    }
+
+    body.push_str("<ul>\n");
+    for line in lines.iter() {
+        body.push_str(&format!(
+            "<li style=\"border-color: {};\">{}</li>\n",
+            escape_html(color),
+            escape_html(line)
+        ));
+    }
+    body.push_str("</ul>\n");
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::def::RecordPlanEntry;
+
+    fn date(text: &str) -> NaiveDate {
+        parse_date(text).unwrap()
+    }
+
+    fn entries() -> Vec<RecordEntry> {
+        vec![
+            RecordEntry {
+                uuid: "a".to_string(),
+                title: "a".to_string(),
+                state: RecordState::Finished,
+                start_date: "2026-10-10".to_string(),
+                end_date: "2026-10-14".to_string(),
+                finished_date: "2026-10-15".to_string(),
+                tags: vec!["work".to_string()],
+                plan: vec![
+                    RecordPlanEntry {
+                        detail: "x".to_string(),
+                        is_finished: true,
+                        finished_date: "2026-10-13".to_string(),
+                        ..Default::default()
+                    },
+                    RecordPlanEntry {
+                        detail: "y".to_string(),
+                        is_finished: true,
+                        finished_date: "2026-10-01".to_string(),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            },
+            RecordEntry {
+                uuid: "b".to_string(),
+                title: "b".to_string(),
+                state: RecordState::Running,
+                start_date: "2026-10-13".to_string(),
+                end_date: "2026-10-16".to_string(),
+                tags: vec!["Work".to_string(), "home".to_string()],
+                plan: vec![RecordPlanEntry {
+                    detail: "z".to_string(),
+                    due_date: "2026-10-17".to_string(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            RecordEntry {
+                uuid: "c & <d>".to_string(),
+                title: "c & <d>".to_string(),
+                state: RecordState::Giveup,
+                start_date: "2026-10-01".to_string(),
+                end_date: "2026-10-12".to_string(),
+                ..Default::default()
+            },
+            RecordEntry {
+                uuid: "d".to_string(),
+                title: "d".to_string(),
+                state: RecordState::Timeout,
+                start_date: "2026-09-01".to_string(),
+                end_date: "2026-10-11".to_string(),
+                ..Default::default()
+            },
+            RecordEntry {
+                uuid: "e".to_string(),
+                title: "e".to_string(),
+                start_date: "2026-10-20".to_string(),
+                end_date: "2026-10-25".to_string(),
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn test_period() {
+        let today = date("2026-10-18");
+        assert_eq!(
+            period(&Range::All, &entries(), today),
+            (date("2026-09-01"), today)
+        );
+        assert_eq!(period(&Range::All, &[], today), (today, today));
+        assert_eq!(
+            period(&Range::ThisWeek, &entries(), today),
+            (date("2026-10-12"), today)
+        );
+        assert_eq!(
+            period(
+                &Range::Custom("2026-10-01".to_string(), String::default()),
+                &[],
+                today
+            ),
+            (date("2026-10-01"), today)
+        );
+    }
+
+    #[test]
+    fn test_report() -> Result<()> {
+        let hour = 3600;
+        let day = cutil::time::date_str_to_timestamp("2026-10-12")?;
+        let today = cutil::time::date_str_to_timestamp("2026-10-18")?;
+        let sessions = [
+            // Half of it is before the period
+            SessionEntry {
+                record_uuid: "a".to_string(),
+                start: day - hour,
+                end: day + hour,
+                ..Default::default()
+            },
+            SessionEntry {
+                record_uuid: "b".to_string(),
+                start: day + 2 * 24 * hour,
+                end: day + 2 * 24 * hour + 2 * hour,
+                ..Default::default()
+            },
+            SessionEntry {
+                record_uuid: "b".to_string(),
+                start: today + 10 * hour,
+                end: 0,
+                ..Default::default()
+            },
+            SessionEntry {
+                record_uuid: "c & <d>".to_string(),
+                start: day,
+                end: day + hour,
+                ..Default::default()
+            },
+            SessionEntry {
+                record_uuid: "removed".to_string(),
+                start: day,
+                end: day + hour,
+                ..Default::default()
+            },
+        ];
+
+        let report = Report::new(
+            &entries(),
+            &sessions,
+            (date("2026-10-12"), date("2026-10-18")),
+            date("2026-10-18"),
+            today + 10 * hour + hour / 2,
+        );
+
+        let titles = |items: &[RecordEntry]| {
+            items
+                .iter()
+                .map(|entry| entry.title.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(titles(&report.started), ["b"]);
+        assert_eq!(report.finished.len(), 1);
+        assert_eq!(report.finished[0].1, date("2026-10-15"));
+        assert_eq!(titles(&report.given_up), ["c & <d>"]);
+        assert!(report.timed_out.is_empty());
+        assert_eq!(
+            report
+                .steps
+                .iter()
+                .map(|item| item.detail.clone())
+                .collect::<Vec<_>>(),
+            ["x"]
+        );
+
+        assert_eq!(
+            report.tags,
+            [
+                ("work".to_string(), 3 * hour + hour / 2),
+                ("home".to_string(), 2 * hour + hour / 2),
+                (String::default(), hour),
+            ]
+        );
+        assert_eq!(report.tracked_seconds, 4 * hour + hour / 2);
+
+        assert_eq!(
+            report
+                .overdue
+                .iter()
+                .map(|item| (item.title.as_str(), item.detail.as_str(), item.days))
+                .collect::<Vec<_>>(),
+            [("d", "", 7), ("b", "", 2), ("b", "z", 1)]
+        );
+
+        let text = report.render(Format::Markdown, &default_color);
+        assert!(
+            text.starts_with("# Status report 2026-10-12 - 2026-10-18\n\nWritten on 2026-10-18.\n")
+        );
+        assert!(text.contains("| Time tracked | 4h 30m |"));
+        assert!(text.contains("- a [work] - 2026-10-15, 1 day late\n"));
+        assert!(text.contains("| No tag | 1h 00m |"));
+        assert!(text.contains("## Timed out\n\nNone\n"));
+        assert!(text.contains("- b: z - due 2026-10-17, 1 day overdue (Running)\n"));
+
+        let html = report.render(Format::Html, &|state| format!("#{}", state.as_str()));
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("c &amp; &lt;d&gt; - 2026-10-12"));
+        assert!(html.contains("background: #Finished;"));
+        assert!(html.contains("border-color: #Timeout;"));
+        assert!(!html.contains("<d>"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(Format::parse("HTML").unwrap(), Format::Html);
+        assert_eq!(
+            Format::from_path(Path::new("week.md")),
+            Some(Format::Markdown)
+        );
+        assert!(Format::parse("pdf").is_err());
+    }
+}
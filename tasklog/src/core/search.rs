@@ -0,0 +1,424 @@
+use super::{field, session};
+use crate::db::def::{RecordEntry, RecordPriority, RecordState};
+use anyhow::{Result, bail};
+use std::cmp::Ordering;
+
+const STATES: [RecordState; 5] = [
+    RecordState::NotStarted,
+    RecordState::Running,
+    RecordState::Finished,
+    RecordState::Giveup,
+    RecordState::Timeout,
+];
+
+const PRIORITIES: [RecordPriority; 5] = [
+    RecordPriority::None,
+    RecordPriority::Low,
+    RecordPriority::Medium,
+    RecordPriority::High,
+    RecordPriority::Urgent,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateField {
+    Start,
+    Due,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Lt,
+    Le,
+    Eq,
+    Ge,
+    Gt,
+}
+
+impl CompareOp {
+    fn test(&self, ordering: Ordering) -> bool {
+        match self {
+            CompareOp::Lt => ordering.is_lt(),
+            CompareOp::Le => ordering.is_le(),
+            CompareOp::Eq => ordering.is_eq(),
+            CompareOp::Ge => ordering.is_ge(),
+            CompareOp::Gt => ordering.is_gt(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DateFilter {
+    pub field: DateField,
+    pub op: CompareOp,
+    pub date: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EstimateFilter {
+    pub op: CompareOp,
+    pub seconds: i64,
+}
+
+// A parsed search like `tag:work state:running due<2026-11-01 "exact phrase"`.
+// All the conditions should be met:
+// - words and quoted phrases are searched in the title, plan and tags
+// - `tag:name` matches a whole tag
+// - `state:name` accepts a prefix of the state name and can be repeated
+// - `priority:name` works the same as `state:name`
+// - `due` and `start` compare the end and start dates with <, <=, =, >=, >
+// - `estimate` compares the estimate with a duration, e.g. `estimate<=2h`
+// - `field:name=value` matches the whole value of a custom field
+// Texts, tags and custom fields are case-insensitive.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Query {
+    pub texts: Vec<String>,
+    pub tags: Vec<String>,
+    pub states: Vec<RecordState>,
+    pub priorities: Vec<RecordPriority>,
+    pub dates: Vec<DateFilter>,
+    pub estimates: Vec<EstimateFilter>,
+    pub fields: Vec<(String, String)>,
+}
+
+impl Query {
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut query = Query::default();
+
+        for (token, is_quoted) in tokenize(text) {
+            if is_quoted {
+                query.texts.push(token.to_lowercase());
+                continue;
+            }
+
+            if let Some(filter) = parse_date_filter(&token)? {
+                query.dates.push(filter);
+                continue;
+            }
+
+            if let Some(filter) = parse_estimate_filter(&token)? {
+                query.estimates.push(filter);
+                continue;
+            }
+
+            match token.split_once(':') {
+                Some((key, value)) if key.eq_ignore_ascii_case("tag") => {
+                    if !value.is_empty() {
+                        query.tags.push(value.to_lowercase());
+                    }
+                }
+                Some((key, value)) if key.eq_ignore_ascii_case("state") => {
+                    if !value.is_empty() {
+                        query.states.push(parse_state(value)?);
+                    }
+                }
+                Some((key, value)) if key.eq_ignore_ascii_case("priority") => {
+                    if !value.is_empty() {
+                        query.priorities.push(parse_priority(value)?);
+                    }
+                }
+                Some((key, value)) if key.eq_ignore_ascii_case("field") => {
+                    if !value.is_empty() {
+                        let (name, value) = field::parse_assignment(value)?;
+                        query.fields.push((name, value.to_lowercase()));
+                    }
+                }
+                _ => query.texts.push(token.to_lowercase()),
+            }
+        }
+
+        Ok(query)
+    }
+
+    pub fn matches(&self, entry: &RecordEntry) -> bool {
+        let title = entry.title.to_lowercase();
+        let plan = entry
+            .plan
+            .iter()
+            .map(|item| item.detail.to_lowercase())
+            .collect::<Vec<_>>();
+        let tags = entry
+            .tags
+            .iter()
+            .map(|tag| tag.to_lowercase())
+            .collect::<Vec<_>>();
+
+        let is_texts_matched = self.texts.iter().all(|text| {
+            title.contains(text)
+                || plan.iter().any(|detail| detail.contains(text))
+                || tags.iter().any(|tag| tag.contains(text))
+        });
+
+        let is_dates_matched = self.dates.iter().all(|filter| {
+            let date = match filter.field {
+                DateField::Start => &entry.start_date,
+                DateField::Due => &entry.end_date,
+            };
+
+            match cutil::time::date_str_add_days(date, 0) {
+                Ok(date) => filter.op.test(date.cmp(&filter.date)),
+                _ => false,
+            }
+        });
+
+        let is_estimates_matched = self.estimates.iter().all(|filter| {
+            entry.estimate_seconds > 0
+                && filter.op.test(entry.estimate_seconds.cmp(&filter.seconds))
+        });
+
+        let is_fields_matched = self.fields.iter().all(|(name, value)| {
+            field::value(entry, name).is_some_and(|item| item.to_lowercase() == *value)
+        });
+
+        is_texts_matched
+            && is_dates_matched
+            && is_estimates_matched
+            && is_fields_matched
+            && self.tags.iter().all(|tag| tags.contains(tag))
+            && (self.states.is_empty() || self.states.contains(&entry.state))
+            && (self.priorities.is_empty() || self.priorities.contains(&entry.priority))
+    }
+
+    // Sql condition narrowing down the rows of `table` before `matches` is
+    // applied. The states are left out, since the stored ones can be outdated,
+    // and so are the dates, since the stored ones can be without the leading
+    // zeros, e.g. `2025-3-5`. The priorities, estimates and custom fields have
+    // no columns and are only filtered by `matches`.
+    #[cfg(feature = "database")]
+    pub fn to_sql(&self, table: &str) -> (String, Vec<String>) {
+        let columns = crate::db::migration::SEARCH_COLUMNS
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>();
+
+        let mut conditions = vec![];
+        let mut binds = vec![];
+
+        for text in self.texts.iter() {
+            let (condition, values) = sqldb::fts::contains_condition(table, &columns, text);
+            conditions.push(condition);
+            binds.extend(values);
+        }
+
+        // `lower` of sqlite only handles ascii characters
+        for tag in self.tags.iter().filter(|tag| tag.is_ascii()) {
+            conditions.push(format!(
+                "EXISTS (SELECT 1 FROM json_each({table}.tags) WHERE lower(value) = ?)"
+            ));
+            binds.push(tag.clone());
+        }
+
+        if conditions.is_empty() {
+            ("1".to_string(), binds)
+        } else {
+            (conditions.join(" AND "), binds)
+        }
+    }
+}
+
+// Splits the text by whitespaces. The whitespaces between double quotes are
+// kept. The flag is true if the token starts with a double quote.
+fn tokenize(text: &str) -> Vec<(String, bool)> {
+    let mut tokens = vec![];
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let is_quoted = c == '"';
+        let mut token = String::default();
+        let mut in_quote = false;
+
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() && !in_quote {
+                break;
+            }
+
+            chars.next();
+            if c == '"' {
+                in_quote = !in_quote;
+            } else {
+                token.push(c);
+            }
+        }
+
+        if !token.is_empty() {
+            tokens.push((token, is_quoted));
+        }
+    }
+
+    tokens
+}
+
+pub(super) fn parse_state(value: &str) -> Result<RecordState> {
+    let value = value.to_lowercase().replace(['-', '_'], "");
+    let states = STATES
+        .into_iter()
+        .filter(|state| state.as_str().to_lowercase().starts_with(&value))
+        .collect::<Vec<_>>();
+
+    match states[..] {
+        [state] => Ok(state),
+        _ => bail!("unknown state `{value}`"),
+    }
+}
+
+pub fn parse_priority(value: &str) -> Result<RecordPriority> {
+    let value = value.to_lowercase();
+    let priorities = PRIORITIES
+        .into_iter()
+        .filter(|priority| priority.as_str().to_lowercase().starts_with(&value))
+        .collect::<Vec<_>>();
+
+    match priorities[..] {
+        [priority] => Ok(priority),
+        _ => bail!("unknown priority `{value}`, expect none, low, medium, high or urgent"),
+    }
+}
+
+fn parse_compare_op(text: &str) -> Option<(CompareOp, &str)> {
+    if let Some(value) = text.strip_prefix("<=") {
+        Some((CompareOp::Le, value))
+    } else if let Some(value) = text.strip_prefix(">=") {
+        Some((CompareOp::Ge, value))
+    } else if let Some(value) = text.strip_prefix('<') {
+        Some((CompareOp::Lt, value))
+    } else if let Some(value) = text.strip_prefix('>') {
+        Some((CompareOp::Gt, value))
+    } else {
+        text.strip_prefix('=')
+            .or(text.strip_prefix(':'))
+            .map(|value| (CompareOp::Eq, value))
+    }
+}
+
+fn parse_estimate_filter(token: &str) -> Result<Option<EstimateFilter>> {
+    let lower_token = token.to_lowercase();
+    let Some(rest) = lower_token.strip_prefix("estimate") else {
+        return Ok(None);
+    };
+
+    let Some((op, value)) = parse_compare_op(rest) else {
+        return Ok(None);
+    };
+
+    Ok(Some(EstimateFilter {
+        op,
+        seconds: session::parse_duration(value)?,
+    }))
+}
+
+fn parse_date_filter(token: &str) -> Result<Option<DateFilter>> {
+    let lower_token = token.to_lowercase();
+    let (field, rest) = if let Some(rest) = lower_token.strip_prefix("due") {
+        (DateField::Due, rest)
+    } else if let Some(rest) = lower_token.strip_prefix("start") {
+        (DateField::Start, rest)
+    } else {
+        return Ok(None);
+    };
+
+    let Some((op, date)) = parse_compare_op(rest) else {
+        return Ok(None);
+    };
+
+    match cutil::time::date_str_add_days(date, 0) {
+        Ok(date) => Ok(Some(DateFilter { field, op, date })),
+        _ => bail!("invalid date `{date}`, expect `YYYY-MM-DD`"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::def::{RecordField, RecordPlanEntry};
+
+    #[test]
+    fn test_parse() -> Result<()> {
+        let query = Query::parse(r#"tag:Work state:run due<2026-11-1 "Exact Phrase" 学习"#)?;
+
+        assert_eq!(query.texts, vec!["exact phrase", "学习"]);
+        assert_eq!(query.tags, vec!["work"]);
+        assert_eq!(query.states, vec![RecordState::Running]);
+        assert_eq!(
+            query.dates,
+            vec![DateFilter {
+                field: DateField::Due,
+                op: CompareOp::Lt,
+                date: "2026-11-01".to_string(),
+            }]
+        );
+
+        assert_eq!(Query::parse("tag: state:")?, Query::default());
+        assert!(Query::parse("state:paused").is_err());
+        assert!(Query::parse("due>=tomorrow").is_err());
+        assert_eq!(Query::parse("duet")?.texts, vec!["duet"]);
+
+        let query = Query::parse("priority:hi estimate>=1h30m field:Area=UI estimated")?;
+        assert_eq!(query.priorities, vec![RecordPriority::High]);
+        assert_eq!(
+            query.estimates,
+            vec![EstimateFilter {
+                op: CompareOp::Ge,
+                seconds: 5400,
+            }]
+        );
+        assert_eq!(query.fields, vec![("Area".to_string(), "ui".to_string())]);
+        assert_eq!(query.texts, vec!["estimated"]);
+        assert!(Query::parse("priority:x").is_err());
+        assert!(Query::parse("field:=x").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_matches() -> Result<()> {
+        let entry = RecordEntry {
+            title: "学习 Rust".to_string(),
+            start_date: "2026-10-01".to_string(),
+            end_date: "2026-10-20".to_string(),
+            plan: vec![RecordPlanEntry {
+                detail: "Read the Book".to_string(),
+                ..Default::default()
+            }],
+            tags: vec!["Work".to_string()],
+            state: RecordState::Running,
+            priority: RecordPriority::High,
+            estimate_seconds: 7200,
+            fields: vec![RecordField {
+                name: "Area".to_string(),
+                value: "UI".to_string(),
+            }],
+            ..Default::default()
+        };
+
+        for text in [
+            "rust",
+            "学习",
+            "\"the book\"",
+            "tag:work state:r",
+            "due<=2026-10-20 start>2026-09-30",
+            "priority:high priority:low estimate<=2h field:area=ui",
+            "",
+        ] {
+            assert!(Query::parse(text)?.matches(&entry), "{text}");
+        }
+
+        for text in [
+            "python",
+            "\"book the\"",
+            "tag:wor",
+            "state:finished",
+            "due<2026-10-20",
+            "priority:urgent",
+            "estimate>2h",
+            "field:area=backend",
+        ] {
+            assert!(!Query::parse(text)?.matches(&entry), "{text}");
+        }
+
+        Ok(())
+    }
+}
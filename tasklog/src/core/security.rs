@@ -0,0 +1,119 @@
+// Applies the security setting of the config to the database. The sessions
+// are never encrypted, their queries rely on the generated columns.
+
+use crate::{
+    config,
+    db::{
+        self,
+        def::{ARCHIVE_TABLE, CHANGE_TABLE, CORRUPT_TABLE, RECORD_TABLE, TRASH_TABLE},
+    },
+};
+use anyhow::{Result, bail};
+use std::path::PathBuf;
+
+pub const ENCRYPTED_TABLES: [&str; 5] = [
+    RECORD_TABLE,
+    ARCHIVE_TABLE,
+    CORRUPT_TABLE,
+    TRASH_TABLE,
+    CHANGE_TABLE,
+];
+
+#[derive(Debug, Clone)]
+pub struct Status {
+    pub is_locked: bool,
+    pub is_master_password: bool,
+    pub is_database_encrypted: bool,
+
+    // `None` if a master password is used
+    pub key_file: Option<PathBuf>,
+}
+
+pub fn init() {
+    db::sqlite().set_cipher(config::secret_key());
+    set_tables(config::security().encrypt_database);
+}
+
+pub fn status() -> Status {
+    let security = config::security();
+
+    Status {
+        is_locked: config::secret_key().is_none(),
+        is_master_password: !security.password_salt.is_empty(),
+        is_database_encrypted: security.encrypt_database,
+        key_file: config::key_file(),
+    }
+}
+
+pub fn unlock(password: &str) -> Result<()> {
+    config::unlock(password)?;
+    init();
+    Ok(())
+}
+
+// Replaces the key by one from `password`, or from `key_file` if there is no
+// password. The encrypted tables are sealed again with the new key.
+pub async fn set_master_password(password: Option<&str>, key_file: Option<&str>) -> Result<()> {
+    let old_key = config::secret_key();
+    let (security, cipher) = config::new_key(password, key_file)?;
+
+    if security.encrypt_database {
+        db::sqlite().set_cipher(Some(cipher.clone()));
+
+        if let Err(e) = db::sqlite()
+            .rewrite(&ENCRYPTED_TABLES, old_key.as_ref())
+            .await
+        {
+            db::sqlite().set_cipher(old_key);
+            return Err(e);
+        }
+    }
+
+    if let Err(e) = config::save_with_key(security.clone(), cipher.clone()) {
+        if security.encrypt_database {
+            db::sqlite().set_cipher(old_key);
+            if let Err(e) = db::sqlite().rewrite(&ENCRYPTED_TABLES, Some(&cipher)).await {
+                log::warn!("restore the old key of the database failed: {e:?}");
+            }
+        }
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+// Returns the number of the rewritten rows
+pub async fn set_database_encryption(enabled: bool) -> Result<usize> {
+    if config::secret_key().is_none() {
+        bail!("the secrets are locked, enter the master password first");
+    }
+
+    let mut all = config::all();
+    if all.security.encrypt_database == enabled {
+        return Ok(0);
+    }
+
+    set_tables(enabled);
+    let counts = match db::sqlite().rewrite(&ENCRYPTED_TABLES, None).await {
+        Ok(counts) => counts,
+        Err(e) => {
+            set_tables(!enabled);
+            return Err(e);
+        }
+    };
+
+    all.security.encrypt_database = enabled;
+    if let Err(e) = config::save(all) {
+        set_tables(!enabled);
+        if let Err(e) = db::sqlite().rewrite(&ENCRYPTED_TABLES, None).await {
+            log::warn!("restore the database encryption failed: {e:?}");
+        }
+        return Err(e);
+    }
+
+    Ok(counts)
+}
+
+fn set_tables(enabled: bool) {
+    db::sqlite().set_encrypted_tables(if enabled { &ENCRYPTED_TABLES } else { &[] });
+}
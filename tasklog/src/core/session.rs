@@ -0,0 +1,140 @@
+use crate::db::def::SessionEntry;
+use anyhow::{Result, bail};
+use std::collections::HashMap;
+
+pub fn is_running(session: &SessionEntry) -> bool {
+    session.end == 0
+}
+
+// Seconds of the session up to `now` if it is still running
+pub fn duration(session: &SessionEntry, now: i64) -> i64 {
+    let end = if is_running(session) {
+        now
+    } else {
+        session.end
+    };
+    (end - session.start).max(0)
+}
+
+pub fn tracked_seconds(sessions: &[SessionEntry], now: i64) -> i64 {
+    sessions.iter().map(|session| duration(session, now)).sum()
+}
+
+// Tracked seconds of each record
+pub fn tracked_seconds_by_record(sessions: &[SessionEntry], now: i64) -> HashMap<String, i64> {
+    let mut items = HashMap::new();
+
+    for session in sessions.iter() {
+        *items.entry(session.record_uuid.clone()).or_default() += duration(session, now);
+    }
+
+    items
+}
+
+// e.g. `2h 05m`, `45m`
+pub fn format_duration(seconds: i64) -> String {
+    let minutes = seconds.max(0) / 60;
+
+    if minutes < 60 {
+        format!("{minutes}m")
+    } else {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    }
+}
+
+// Seconds of a text like `2h 30m`, `1.5h`, `45m` or `3`, a number without a
+// unit is in hours. An empty text is 0.
+pub fn parse_duration(text: &str) -> Result<i64> {
+    let text = text.trim().to_lowercase();
+    if text.is_empty() {
+        return Ok(0);
+    }
+
+    if let Ok(hours) = text.parse::<f64>() {
+        return to_seconds(&text, hours, 3600.0);
+    }
+
+    let mut seconds = 0;
+    let mut number = String::default();
+
+    for c in text.chars() {
+        match c {
+            '0'..='9' | '.' => number.push(c),
+            'h' | 'm' if !number.is_empty() => {
+                let value = number.parse::<f64>().unwrap_or(f64::NAN);
+                seconds += to_seconds(&text, value, if c == 'h' { 3600.0 } else { 60.0 })?;
+                number.clear();
+            }
+            _ if c.is_whitespace() => (),
+            _ => bail!("invalid duration `{text}`, expect e.g. `2h 30m`"),
+        }
+    }
+
+    if !number.is_empty() {
+        bail!("invalid duration `{text}`, expect e.g. `2h 30m`");
+    }
+
+    Ok(seconds)
+}
+
+fn to_seconds(text: &str, value: f64, unit: f64) -> Result<i64> {
+    if !value.is_finite() || value < 0.0 {
+        bail!("invalid duration `{text}`, expect e.g. `2h 30m`");
+    }
+
+    Ok((value * unit).round() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracked_seconds() {
+        let sessions = vec![
+            SessionEntry {
+                record_uuid: "a".to_string(),
+                start: 100,
+                end: 400,
+                ..Default::default()
+            },
+            SessionEntry {
+                record_uuid: "b".to_string(),
+                start: 1000,
+                end: 0,
+                ..Default::default()
+            },
+            SessionEntry {
+                record_uuid: "a".to_string(),
+                start: 500,
+                end: 450,
+                ..Default::default()
+            },
+        ];
+
+        assert!(is_running(&sessions[1]));
+        assert_eq!(duration(&sessions[2], 2000), 0);
+        assert_eq!(tracked_seconds(&sessions, 2000), 1300);
+
+        let items = tracked_seconds_by_record(&sessions, 2000);
+        assert_eq!(items["a"], 300);
+        assert_eq!(items["b"], 1000);
+
+        assert_eq!(format_duration(59), "0m");
+        assert_eq!(format_duration(45 * 60), "45m");
+        assert_eq!(format_duration(2 * 3600 + 5 * 60 + 30), "2h 05m");
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("").unwrap(), 0);
+        assert_eq!(parse_duration("3").unwrap(), 3 * 3600);
+        assert_eq!(parse_duration("1.5h").unwrap(), 5400);
+        assert_eq!(parse_duration("2h 05m").unwrap(), 2 * 3600 + 5 * 60);
+        assert_eq!(parse_duration("45M").unwrap(), 45 * 60);
+
+        for text in ["h", "2x", "1h 30", "-1", "1..5h"] {
+            assert!(parse_duration(text).is_err(), "{text}");
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use super::field;
+use crate::db::def::{FieldEntry, FieldKind, RecordEntry};
+use std::cmp::{Ordering, Reverse};
+
+// The order of the record list. `Default` keeps the order the entries are
+// loaded in, newest first.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum SortKey {
+    #[default]
+    Default,
+    Priority,
+    EndDate,
+    StartDate,
+    Title,
+    Estimate,
+    Field(String),
+}
+
+impl SortKey {
+    // The built-in keys, a custom field otherwise
+    pub fn parse(text: &str) -> Self {
+        match text.trim().to_lowercase().as_str() {
+            "" | "default" | "newest" => SortKey::Default,
+            "priority" => SortKey::Priority,
+            "due" | "end" | "end_date" => SortKey::EndDate,
+            "start" | "start_date" => SortKey::StartDate,
+            "title" => SortKey::Title,
+            "estimate" => SortKey::Estimate,
+            _ => SortKey::Field(text.trim().to_string()),
+        }
+    }
+}
+
+// The highest priority and the largest estimate come first, the other keys
+// are ascending. Entries without a value go last and the ties keep their
+// order. A custom field is compared by its kind, a select field by the order
+// of its options.
+pub fn sort(entries: &mut [RecordEntry], key: &SortKey, fields: &[FieldEntry]) {
+    match key {
+        SortKey::Default => (),
+        SortKey::Priority => entries.sort_by_key(|entry| Reverse(entry.priority)),
+        SortKey::EndDate => entries.sort_by(|a, b| a.end_date.cmp(&b.end_date)),
+        SortKey::StartDate => entries.sort_by(|a, b| a.start_date.cmp(&b.start_date)),
+        SortKey::Title => entries.sort_by_key(|entry| entry.title.to_lowercase()),
+        SortKey::Estimate => entries.sort_by(|a, b| {
+            missing_last(a.estimate_seconds > 0, b.estimate_seconds > 0)
+                .then(b.estimate_seconds.cmp(&a.estimate_seconds))
+        }),
+        SortKey::Field(name) => {
+            let definition = field::find(fields, name);
+
+            entries.sort_by(
+                |a, b| match (field::value(a, name), field::value(b, name)) {
+                    (Some(a), Some(b)) => compare_values(definition, a, b),
+                    (a, b) => missing_last(a.is_some(), b.is_some()),
+                },
+            );
+        }
+    }
+}
+
+fn missing_last(a: bool, b: bool) -> Ordering {
+    b.cmp(&a)
+}
+
+fn compare_values(definition: Option<&FieldEntry>, a: &str, b: &str) -> Ordering {
+    match definition.map(|item| item.kind) {
+        Some(FieldKind::Number) => {
+            let a = a.parse::<f64>().unwrap_or(f64::MAX);
+            let b = b.parse::<f64>().unwrap_or(f64::MAX);
+            a.total_cmp(&b)
+        }
+        Some(FieldKind::Select) => {
+            let options = &definition.unwrap().options;
+            let index = |value: &str| {
+                options
+                    .iter()
+                    .position(|option| option == value)
+                    .unwrap_or(options.len())
+            };
+            index(a).cmp(&index(b))
+        }
+        _ => a.to_lowercase().cmp(&b.to_lowercase()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::def::{RecordField, RecordPriority};
+
+    fn titles(entries: &[RecordEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.title.as_str()).collect()
+    }
+
+    #[test]
+    fn test_sort() {
+        let mut entries = vec![
+            RecordEntry {
+                title: "b".to_string(),
+                priority: RecordPriority::Low,
+                ..Default::default()
+            },
+            RecordEntry {
+                title: "a".to_string(),
+                priority: RecordPriority::High,
+                estimate_seconds: 600,
+                ..Default::default()
+            },
+            RecordEntry {
+                title: "c".to_string(),
+                priority: RecordPriority::Low,
+                estimate_seconds: 3600,
+                ..Default::default()
+            },
+        ];
+
+        sort(&mut entries, &SortKey::parse("priority"), &[]);
+        assert_eq!(titles(&entries), vec!["a", "b", "c"]);
+
+        sort(&mut entries, &SortKey::Estimate, &[]);
+        assert_eq!(titles(&entries), vec!["c", "a", "b"]);
+
+        sort(&mut entries, &SortKey::Title, &[]);
+        assert_eq!(titles(&entries), vec!["a", "b", "c"]);
+
+        let fields = vec![FieldEntry {
+            name: "Size".to_string(),
+            kind: FieldKind::Select,
+            options: vec!["S".to_string(), "M".to_string(), "L".to_string()],
+            ..Default::default()
+        }];
+
+        for (entry, value) in entries.iter_mut().zip(["L", "S"]) {
+            entry.fields.push(RecordField {
+                name: "Size".to_string(),
+                value: value.to_string(),
+            });
+        }
+
+        sort(&mut entries, &SortKey::parse("size"), &fields);
+        assert_eq!(titles(&entries), vec!["b", "a", "c"]);
+    }
+}
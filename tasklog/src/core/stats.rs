@@ -0,0 +1,995 @@
+// The scope of the statistics: the records overlapping a date range, with
+// one of the chosen tags and in one of the chosen states. An empty tag or
+// state list does not filter. The series of the activity charts are counted
+// over the days of the range. The aggregates group the records by state, tag
+// or period, each with the distributions of the days spent and the tracked
+// time for the medians and percentiles.
+
+use super::{
+    plan,
+    record::{self, DATE_FORMAT},
+};
+use crate::db::def::{RecordEntry, RecordState};
+use chrono::{Datelike, Duration, NaiveDate};
+
+pub const STATES: [RecordState; 5] = [
+    RecordState::NotStarted,
+    RecordState::Running,
+    RecordState::Finished,
+    RecordState::Giveup,
+    RecordState::Timeout,
+];
+
+// The state of a name saved by `RecordState::as_str`
+pub fn parse_state(name: &str) -> Option<RecordState> {
+    STATES.into_iter().find(|state| state.as_str() == name)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Range {
+    #[default]
+    All,
+    ThisWeek,
+    ThisMonth,
+    Last90Days,
+
+    // `YYYY-MM-DD`, an empty date leaves that side open
+    Custom(String, String),
+}
+
+impl Range {
+    pub const NAMES: [&str; 5] = ["all", "week", "month", "90days", "custom"];
+
+    // An unknown name is `All`
+    pub fn parse(name: &str, start_date: &str, end_date: &str) -> Self {
+        match name {
+            "week" => Range::ThisWeek,
+            "month" => Range::ThisMonth,
+            "90days" => Range::Last90Days,
+            "custom" => Range::Custom(start_date.to_string(), end_date.to_string()),
+            _ => Range::All,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Range::All => "all",
+            Range::ThisWeek => "week",
+            Range::ThisMonth => "month",
+            Range::Last90Days => "90days",
+            Range::Custom(..) => "custom",
+        }
+    }
+
+    // The first and the last day of the range, both included. A week starts
+    // on Monday. `None` if the range is open on that side.
+    pub fn bounds(&self, today: &str) -> (Option<NaiveDate>, Option<NaiveDate>) {
+        let Ok(today) = NaiveDate::parse_from_str(today, DATE_FORMAT) else {
+            return (None, None);
+        };
+
+        match self {
+            Range::All => (None, None),
+            Range::ThisWeek => {
+                let start = week_start(today);
+                (Some(start), Some(start + Duration::days(6)))
+            }
+            Range::ThisMonth => {
+                let start = today.with_day(1).unwrap_or(today);
+                let end = start
+                    .checked_add_months(chrono::Months::new(1))
+                    .map_or(today, |next| next - Duration::days(1));
+                (Some(start), Some(end))
+            }
+            Range::Last90Days => (Some(today - Duration::days(89)), Some(today)),
+            Range::Custom(start, end) => (parse_date(start), parse_date(end)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Scope {
+    pub range: Range,
+    pub tags: Vec<String>,
+    pub states: Vec<RecordState>,
+}
+
+impl Scope {
+    // A record is in the range if any of its days is, so a long record is
+    // counted as a whole. A record with dates which can not be parsed is only
+    // in the `All` range.
+    pub fn matches(&self, entry: &RecordEntry, today: &str) -> bool {
+        if !self.states.is_empty() && !self.states.contains(&entry.state) {
+            return false;
+        }
+
+        if !self.tags.is_empty()
+            && !entry
+                .tags
+                .iter()
+                .any(|tag| self.tags.iter().any(|item| item.eq_ignore_ascii_case(tag)))
+        {
+            return false;
+        }
+
+        let (start, end) = self.range.bounds(today);
+        if start.is_none() && end.is_none() {
+            return true;
+        }
+
+        let (Some(entry_start), Some(entry_end)) =
+            (parse_date(&entry.start_date), parse_date(&entry.end_date))
+        else {
+            return false;
+        };
+
+        start.is_none_or(|start| entry_end >= start) && end.is_none_or(|end| entry_start <= end)
+    }
+}
+
+// The days a chart of the range shows, both included. An open range ends
+// today and starts at the first record, at most `max_days` before its end.
+pub fn window(
+    range: &Range,
+    entries: &[RecordEntry],
+    today: NaiveDate,
+    max_days: i64,
+) -> (NaiveDate, NaiveDate) {
+    let (start, end) = range.bounds(&today.format(DATE_FORMAT).to_string());
+    let end = end.unwrap_or(today);
+    let first = end - Duration::days(max_days - 1);
+
+    let start = start.unwrap_or_else(|| {
+        entries
+            .iter()
+            .filter_map(|entry| parse_date(&entry.start_date))
+            .min()
+            .unwrap_or(end)
+    });
+
+    (start.max(first).min(end), end)
+}
+
+// The day the record was finished, from the record or the change table, else
+// its end date or today if that is earlier. `None` unless it is finished.
+pub fn finish_date(entry: &RecordEntry, today: NaiveDate) -> Option<NaiveDate> {
+    if entry.state != RecordState::Finished {
+        return None;
+    }
+
+    parse_date(&entry.finished_date)
+        .or_else(|| parse_date(&entry.end_date).map(|date| date.min(today)))
+}
+
+// The buckets of the actual days of a record divided by its planned days
+pub const DURATION_RATIO_LABELS: [&str; 5] = ["<= 50%", "<= 100%", "<= 150%", "<= 200%", "> 200%"];
+const DURATION_RATIO_LIMITS: [f64; 4] = [0.5, 1.0, 1.5, 2.0];
+
+// How the finished records kept to their plan. The planned days are from the
+// start to the end date, the actual ones to the finished date, both included.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Punctuality {
+    pub on_time: usize,
+    pub late: usize,
+
+    // The days finished after the end date, 0 for a record on time
+    pub slip_days: i64,
+
+    // The records in each bucket of `DURATION_RATIO_LABELS`
+    pub duration_ratios: [usize; 5],
+}
+
+impl Punctuality {
+    pub fn finished(&self) -> usize {
+        self.on_time + self.late
+    }
+
+    pub fn on_time_rate(&self) -> Option<f64> {
+        (self.finished() > 0).then(|| self.on_time as f64 / self.finished() as f64)
+    }
+
+    pub fn mean_slip_days(&self) -> Option<f64> {
+        (self.finished() > 0).then(|| self.slip_days as f64 / self.finished() as f64)
+    }
+
+    fn add(&mut self, entry: &RecordEntry, today: NaiveDate) {
+        let (Some(start), Some(end), Some(finished)) = (
+            parse_date(&entry.start_date),
+            parse_date(&entry.end_date),
+            finish_date(entry, today),
+        ) else {
+            return;
+        };
+
+        if finished > end {
+            self.late += 1;
+            self.slip_days += (finished - end).num_days();
+        } else {
+            self.on_time += 1;
+        }
+
+        let planned = ((end - start).num_days() + 1).max(1);
+        let actual = ((finished - start).num_days() + 1).max(1);
+        let ratio = actual as f64 / planned as f64;
+        let index = DURATION_RATIO_LIMITS
+            .iter()
+            .position(|limit| ratio <= *limit)
+            .unwrap_or(DURATION_RATIO_LIMITS.len());
+        self.duration_ratios[index] += 1;
+    }
+}
+
+pub fn punctuality(entries: &[RecordEntry], today: NaiveDate) -> Punctuality {
+    let mut item = Punctuality::default();
+    for entry in entries.iter() {
+        item.add(entry, today);
+    }
+    item
+}
+
+// `punctuality` of the records of each tag from `group_by_tag`, the tags
+// without a finished record are left out
+pub fn punctuality_by_tag(entries: &[RecordEntry], today: NaiveDate) -> Vec<(String, Punctuality)> {
+    let mut items = group_by_tag(entries, |item: &mut Punctuality, entry| {
+        item.add(entry, today)
+    });
+    items.retain(|(_, item)| item.finished() > 0);
+    items
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DayActivity {
+    pub date: NaiveDate,
+    pub started: usize,
+    pub finished: usize,
+}
+
+// The records started and finished on each day of the whole weeks from
+// `start` to `end`, for a calendar with a column per week
+pub fn activity(
+    entries: &[RecordEntry],
+    start: NaiveDate,
+    end: NaiveDate,
+    today: NaiveDate,
+) -> Vec<DayActivity> {
+    let first = week_start(start);
+    let days = (week_start(end) - first).num_days() + 7;
+
+    let mut items = (0..days)
+        .map(|offset| DayActivity {
+            date: first + Duration::days(offset),
+            started: 0,
+            finished: 0,
+        })
+        .collect::<Vec<_>>();
+
+    let index_of = |date: NaiveDate| {
+        let offset = (date - first).num_days();
+        (0..days).contains(&offset).then_some(offset as usize)
+    };
+
+    for entry in entries.iter() {
+        if let Some(index) = parse_date(&entry.start_date).and_then(index_of) {
+            items[index].started += 1;
+        }
+
+        if let Some(index) = finish_date(entry, today).and_then(index_of) {
+            items[index].finished += 1;
+        }
+    }
+
+    items
+}
+
+// The records finished in each week from `start` to `end`, by the Monday of
+// the week. The days out of the range are not counted.
+pub fn throughput(
+    entries: &[RecordEntry],
+    start: NaiveDate,
+    end: NaiveDate,
+    today: NaiveDate,
+) -> Vec<(NaiveDate, usize)> {
+    let first = week_start(start);
+    let weeks = (week_start(end) - first).num_days() / 7 + 1;
+    let mut items = (0..weeks)
+        .map(|week| (first + Duration::days(week * 7), 0))
+        .collect::<Vec<_>>();
+
+    for date in entries
+        .iter()
+        .filter_map(|entry| finish_date(entry, today))
+        .filter(|date| (start..=end).contains(date))
+    {
+        items[((date - first).num_days() / 7) as usize].1 += 1;
+    }
+
+    items
+}
+
+// The open plan steps at the end of each day from `start` to `end`, or of
+// each week if the range is longer than a month. Only the leaf steps are
+// counted. A step is open from the day it was added, or the start of its
+// record, until the day it was finished. A finished step without a known day
+// is finished with its record, or is not counted at all.
+pub fn burndown(
+    entries: &[RecordEntry],
+    start: NaiveDate,
+    end: NaiveDate,
+    today: NaiveDate,
+) -> Vec<(NaiveDate, usize)> {
+    let mut spans = vec![];
+    for entry in entries.iter() {
+        for (index, item) in entry.plan.iter().enumerate() {
+            if plan::has_children(&entry.plan, index) {
+                continue;
+            }
+
+            let Some(opened) =
+                parse_date(&item.added_date).or_else(|| parse_date(&entry.start_date))
+            else {
+                continue;
+            };
+
+            let closed = if item.is_finished {
+                Some(
+                    parse_date(&item.finished_date)
+                        .or_else(|| finish_date(entry, today))
+                        .unwrap_or(opened),
+                )
+            } else {
+                None
+            };
+
+            spans.push((opened, closed));
+        }
+    }
+
+    let step = if (end - start).num_days() > 31 { 7 } else { 1 };
+    let mut days = vec![];
+    let mut day = start;
+    while day < end {
+        days.push(day);
+        day += Duration::days(step);
+    }
+    days.push(end);
+
+    days.into_iter()
+        .map(|day| {
+            let count = spans
+                .iter()
+                .filter(|(opened, closed)| *opened <= day && closed.is_none_or(|date| date > day))
+                .count();
+            (day, count)
+        })
+        .collect()
+}
+
+// The state of the record at `now` as `record::calc_state` sets it, so a
+// record which has not been refreshed since its dates passed is counted in
+// its new state
+pub fn state_at(entry: &RecordEntry, now: i64) -> RecordState {
+    record::calc_state(&entry.start_date, &entry.end_date, entry.state, now).unwrap_or(entry.state)
+}
+
+// Whole days from the start date to the day the work ended, which is the
+// finished date of a finished record and the end date of the others, at most
+// to the day of `now` as in `finish_date`. A record started and ended on the
+// same day is 0 days. `None` if the record has not started at `now` or its
+// dates can not be parsed.
+pub fn days_spent(entry: &RecordEntry, now: i64) -> Option<i64> {
+    if state_at(entry, now) == RecordState::NotStarted {
+        return None;
+    }
+
+    let start = parse_date(&entry.start_date)?;
+    let end = parse_date(record::spent_end_date(entry))?;
+    let today = parse_date(&cutil::time::timestamp_to_local(now, DATE_FORMAT))?;
+
+    Some((end.min(today) - start).num_days().max(0))
+}
+
+// The values of a group, sorted
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Distribution {
+    values: Vec<i64>,
+}
+
+impl Distribution {
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn total(&self) -> i64 {
+        self.values.iter().sum()
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (!self.is_empty()).then(|| self.total() as f64 / self.len() as f64)
+    }
+
+    pub fn median(&self) -> Option<f64> {
+        self.percentile(50.0)
+    }
+
+    // Interpolated between the two closest values, `percent` is clamped to
+    // 0..=100
+    pub fn percentile(&self, percent: f64) -> Option<f64> {
+        let last = self.values.len().checked_sub(1)?;
+        let rank = percent.clamp(0.0, 100.0) / 100.0 * last as f64;
+        let (low, high) = (rank.floor() as usize, rank.ceil() as usize);
+        let (low_value, high_value) = (self.values[low] as f64, self.values[high] as f64);
+
+        Some(low_value + (high_value - low_value) * (rank - low as f64))
+    }
+
+    fn push(&mut self, value: i64) {
+        let index = self.values.partition_point(|item| *item <= value);
+        self.values.insert(index, value);
+    }
+}
+
+// The records of a group, the days spent on the ones which started and the
+// tracked seconds of the ones with tracked time
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Aggregate {
+    pub count: usize,
+    pub days: Distribution,
+    pub tracked_seconds: Distribution,
+}
+
+impl Aggregate {
+    fn add(&mut self, entry: &RecordEntry, now: i64) {
+        self.count += 1;
+
+        if let Some(days) = days_spent(entry, now) {
+            self.days.push(days);
+        }
+
+        if entry.tracked_seconds > 0 {
+            self.tracked_seconds.push(entry.tracked_seconds);
+        }
+    }
+}
+
+pub fn aggregate(entries: &[RecordEntry], now: i64) -> Aggregate {
+    let mut item = Aggregate::default();
+    for entry in entries.iter() {
+        item.add(entry, now);
+    }
+    item
+}
+
+// `aggregate` of the records in each state of `STATES` at `now`
+pub fn by_state(entries: &[RecordEntry], now: i64) -> [(RecordState, Aggregate); 5] {
+    let mut items = STATES.map(|state| (state, Aggregate::default()));
+    for entry in entries.iter() {
+        let state = state_at(entry, now);
+        if let Some((_, item)) = items.iter_mut().find(|(item, _)| *item == state) {
+            item.add(entry, now);
+        }
+    }
+    items
+}
+
+// `aggregate` of the records of each tag from `group_by_tag`
+pub fn by_tag(entries: &[RecordEntry], now: i64) -> Vec<(String, Aggregate)> {
+    group_by_tag(entries, |item: &mut Aggregate, entry| item.add(entry, now))
+}
+
+// The records of each tag folded by `add`, sorted by the tag. A tag matches
+// regardless of its case. A record is added for each of its tags, and the
+// records without a tag are under an empty tag at the end.
+fn group_by_tag<T: Default>(
+    entries: &[RecordEntry],
+    mut add: impl FnMut(&mut T, &RecordEntry),
+) -> Vec<(String, T)> {
+    let mut items: Vec<(String, T)> = vec![];
+    let mut untagged = None;
+
+    for entry in entries.iter() {
+        if entry.tags.is_empty() {
+            add(untagged.get_or_insert_with(T::default), entry);
+        }
+
+        for tag in entry.tags.iter() {
+            match items
+                .iter_mut()
+                .find(|(name, _)| name.eq_ignore_ascii_case(tag))
+            {
+                Some((_, item)) => add(item, entry),
+                None => {
+                    let mut item = T::default();
+                    add(&mut item, entry);
+                    items.push((tag.clone(), item));
+                }
+            }
+        }
+    }
+
+    items.sort_by_key(|(name, _)| name.to_lowercase());
+    items.extend(untagged.map(|item| (String::default(), item)));
+    items
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Period {
+    #[default]
+    Week,
+    Month,
+}
+
+// `aggregate` of the records started in each week or month, keyed by its
+// first day and sorted. The records without a start date are left out.
+pub fn by_period(entries: &[RecordEntry], period: Period, now: i64) -> Vec<(NaiveDate, Aggregate)> {
+    let mut items: Vec<(NaiveDate, Aggregate)> = vec![];
+
+    for entry in entries.iter() {
+        let Some(start) = parse_date(&entry.start_date) else {
+            continue;
+        };
+
+        let key = match period {
+            Period::Week => week_start(start),
+            Period::Month => start.with_day(1).unwrap_or(start),
+        };
+
+        match items.iter_mut().find(|(item, _)| *item == key) {
+            Some((_, item)) => item.add(entry, now),
+            None => {
+                let mut item = Aggregate::default();
+                item.add(entry, now);
+                items.push((key, item));
+            }
+        }
+    }
+
+    items.sort_by_key(|(key, _)| *key);
+    items
+}
+
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+pub fn parse_date(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date.trim(), DATE_FORMAT).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::def::RecordPlanEntry;
+
+    fn day(text: &str) -> NaiveDate {
+        parse_date(text).unwrap()
+    }
+
+    #[test]
+    fn test_bounds() {
+        // 2026-10-14 is a Wednesday
+        let today = "2026-10-14";
+        assert_eq!(
+            Range::ThisWeek.bounds(today),
+            (parse_date("2026-10-12"), parse_date("2026-10-18"))
+        );
+        assert_eq!(
+            Range::ThisMonth.bounds(today),
+            (parse_date("2026-10-01"), parse_date("2026-10-31"))
+        );
+        assert_eq!(
+            Range::ThisMonth.bounds("2024-02-10"),
+            (parse_date("2024-02-01"), parse_date("2024-02-29"))
+        );
+        assert_eq!(
+            Range::Last90Days.bounds(today),
+            (parse_date("2026-07-17"), parse_date(today))
+        );
+        assert_eq!(
+            Range::Custom("".to_string(), "2026-10-01".to_string()).bounds(today),
+            (None, parse_date("2026-10-01"))
+        );
+        assert_eq!(Range::All.bounds(today), (None, None));
+
+        for name in Range::NAMES {
+            assert_eq!(Range::parse(name, "", "").as_str(), name);
+        }
+        assert_eq!(Range::parse("year", "", ""), Range::All);
+
+        assert_eq!(parse_state("Giveup"), Some(RecordState::Giveup));
+        assert_eq!(parse_state("Done"), None);
+    }
+
+    #[test]
+    fn test_matches() {
+        let today = "2026-10-14";
+        let scope = Scope {
+            range: Range::ThisWeek,
+            ..Default::default()
+        };
+
+        for (start_date, end_date, matched) in [
+            ("2026-10-01", "2026-10-12", true),
+            ("2026-10-18", "2026-10-20", true),
+            ("2026-10-01", "2026-10-11", false),
+            ("2026-10-19", "2026-10-20", false),
+            ("", "", false),
+        ] {
+            let item = RecordEntry {
+                start_date: start_date.to_string(),
+                end_date: end_date.to_string(),
+                ..Default::default()
+            };
+            assert_eq!(scope.matches(&item, today), matched, "{start_date}");
+        }
+        assert!(Scope::default().matches(&RecordEntry::default(), today));
+
+        let scope = Scope {
+            tags: vec!["work".to_string()],
+            states: vec![RecordState::NotStarted, RecordState::Running],
+            ..Default::default()
+        };
+        let mut item = RecordEntry {
+            tags: vec!["Work".to_string(), "home".to_string()],
+            ..Default::default()
+        };
+        assert!(scope.matches(&item, today));
+
+        item.state = RecordState::Finished;
+        assert!(!scope.matches(&item, today));
+
+        item.state = RecordState::NotStarted;
+        item.tags = vec!["home".to_string()];
+        assert!(!scope.matches(&item, today));
+    }
+
+    // The end date of the second record is after today, so it is finished
+    // today
+    fn entries() -> Vec<RecordEntry> {
+        vec![
+            RecordEntry {
+                start_date: "2026-10-05".to_string(),
+                end_date: "2026-10-07".to_string(),
+                state: RecordState::Finished,
+                finished_date: "2026-10-06".to_string(),
+                ..Default::default()
+            },
+            RecordEntry {
+                start_date: "2026-10-07".to_string(),
+                end_date: "2026-10-20".to_string(),
+                state: RecordState::Finished,
+                ..Default::default()
+            },
+            RecordEntry {
+                start_date: "2026-10-12".to_string(),
+                end_date: "2026-10-30".to_string(),
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn test_window() {
+        let (entries, today) = (entries(), day("2026-10-14"));
+        assert_eq!(
+            window(&Range::All, &entries, today, 365),
+            (day("2026-10-05"), today)
+        );
+        assert_eq!(
+            window(&Range::All, &entries, today, 5),
+            (day("2026-10-10"), today)
+        );
+        assert_eq!(
+            window(&Range::ThisWeek, &entries, today, 365),
+            (day("2026-10-12"), day("2026-10-18"))
+        );
+        assert_eq!(window(&Range::All, &[], today, 365), (today, today));
+    }
+
+    #[test]
+    fn test_activity_and_throughput() {
+        let (entries, today) = (entries(), day("2026-10-14"));
+        assert_eq!(finish_date(&entries[1], today), Some(today));
+        assert_eq!(finish_date(&entries[2], today), None);
+
+        let items = activity(&entries, day("2026-10-07"), today, today);
+        assert_eq!(items.len(), 14);
+        assert_eq!(items[0].date, day("2026-10-05"));
+        assert_eq!(items[13].date, day("2026-10-18"));
+
+        let counts = items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| item.started > 0 || item.finished > 0)
+            .map(|(index, item)| (index, item.started, item.finished))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            counts,
+            [(0, 1, 0), (1, 0, 1), (2, 1, 0), (7, 1, 0), (9, 0, 1)]
+        );
+
+        // The record finished on 2026-10-06 is before the range
+        assert_eq!(
+            throughput(&entries, day("2026-10-07"), today, today),
+            [(day("2026-10-05"), 0), (day("2026-10-12"), 1)]
+        );
+    }
+
+    #[test]
+    fn test_punctuality() {
+        let today = day("2026-10-14");
+        let entries = vec![
+            // On time, 2 of 10 planned days
+            RecordEntry {
+                start_date: "2026-10-01".to_string(),
+                end_date: "2026-10-10".to_string(),
+                state: RecordState::Finished,
+                finished_date: "2026-10-02".to_string(),
+                tags: vec!["work".to_string()],
+                ..Default::default()
+            },
+            // 3 days late, 13 of 10 days
+            RecordEntry {
+                start_date: "2026-10-01".to_string(),
+                end_date: "2026-10-10".to_string(),
+                state: RecordState::Finished,
+                finished_date: "2026-10-13".to_string(),
+                tags: vec!["Work".to_string(), "home".to_string()],
+                ..Default::default()
+            },
+            // Finished on its only day
+            RecordEntry {
+                start_date: "2026-10-05".to_string(),
+                end_date: "2026-10-05".to_string(),
+                state: RecordState::Finished,
+                finished_date: "2026-10-05".to_string(),
+                ..Default::default()
+            },
+            // Finished before the finished date was kept
+            RecordEntry {
+                start_date: "2026-10-05".to_string(),
+                end_date: "2026-10-06".to_string(),
+                state: RecordState::Finished,
+                ..Default::default()
+            },
+            RecordEntry {
+                end_date: "2026-10-06".to_string(),
+                state: RecordState::Finished,
+                finished_date: "2026-10-06".to_string(),
+                ..Default::default()
+            },
+            RecordEntry {
+                start_date: "2026-10-01".to_string(),
+                end_date: "2026-10-05".to_string(),
+                tags: vec!["work".to_string()],
+                ..Default::default()
+            },
+        ];
+
+        let item = punctuality(&entries, today);
+        assert_eq!((item.on_time, item.late, item.slip_days), (3, 1, 3));
+        assert_eq!(item.duration_ratios, [1, 2, 1, 0, 0]);
+        assert_eq!(item.on_time_rate(), Some(0.75));
+        assert_eq!(item.mean_slip_days(), Some(0.75));
+        assert_eq!(punctuality(&[], today).on_time_rate(), None);
+
+        let items = punctuality_by_tag(&entries, today)
+            .into_iter()
+            .map(|(tag, item)| (tag, item.on_time, item.late))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            items,
+            [
+                ("home".to_string(), 0, 1),
+                ("work".to_string(), 1, 1),
+                (String::default(), 2, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_burndown() {
+        let today = day("2026-10-14");
+        let mut entries = entries();
+        entries[0].plan = vec![
+            RecordPlanEntry {
+                is_finished: true,
+                ..Default::default()
+            },
+            RecordPlanEntry::default(),
+        ];
+        entries[2].plan = vec![
+            RecordPlanEntry::default(),
+            RecordPlanEntry {
+                level: 1,
+                is_finished: true,
+                finished_date: "2026-10-13".to_string(),
+                ..Default::default()
+            },
+            RecordPlanEntry {
+                level: 1,
+                added_date: "2026-10-13".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        let counts = burndown(&entries, day("2026-10-11"), today, today)
+            .into_iter()
+            .map(|(_, count)| count)
+            .collect::<Vec<_>>();
+        assert_eq!(counts, [1, 2, 2, 2]);
+
+        // Weekly for a long range, the last day is always counted
+        let items = burndown(&entries, day("2026-09-01"), today, today);
+        assert_eq!(items.len(), 8);
+        assert_eq!(items[0], (day("2026-09-01"), 0));
+        assert_eq!(items[6], (day("2026-10-13"), 2));
+        assert_eq!(items[7], (today, 2));
+    }
+
+    // Noon of the day, away from the day boundaries
+    fn noon(date: &str) -> i64 {
+        cutil::time::date_str_to_timestamp(date).unwrap() + 12 * 3600
+    }
+
+    fn distribution(values: &[i64]) -> Distribution {
+        let mut item = Distribution::default();
+        values.iter().for_each(|value| item.push(*value));
+        item
+    }
+
+    #[test]
+    fn test_days_spent() {
+        let mut item = RecordEntry {
+            start_date: "2025-03-10".to_string(),
+            end_date: "2025-03-20".to_string(),
+            state: RecordState::Running,
+            ..Default::default()
+        };
+        assert_eq!(days_spent(&item, noon("2025-03-15")), Some(5));
+        assert_eq!(days_spent(&item, noon("2025-04-01")), Some(10));
+        assert_eq!(days_spent(&item, noon("2025-03-01")), None);
+
+        // Counted as running once its start date passed, like `calc_state`
+        item.state = RecordState::NotStarted;
+        assert_eq!(state_at(&item, noon("2025-03-15")), RecordState::Running);
+        assert_eq!(days_spent(&item, noon("2025-03-15")), Some(5));
+        assert_eq!(days_spent(&item, noon("2025-03-09")), None);
+
+        // Finished before its end date, the finished date is only known from
+        // the change table for the records finished by an old version
+        let mut item = RecordEntry {
+            start_date: "2025-03-05".to_string(),
+            end_date: "2025-03-20".to_string(),
+            state: RecordState::Finished,
+            ..Default::default()
+        };
+        assert_eq!(days_spent(&item, noon("2025-03-10")), Some(5));
+        assert_eq!(days_spent(&item, noon("2025-03-25")), Some(15));
+        item.finished_date = "2025-03-05".to_string();
+        assert_eq!(days_spent(&item, noon("2025-03-10")), Some(0));
+        assert_eq!(days_spent(&item, noon("2025-03-25")), Some(0));
+
+        let item = RecordEntry {
+            start_date: "2025-03-10".to_string(),
+            end_date: "2025-03-08".to_string(),
+            state: RecordState::Giveup,
+            ..Default::default()
+        };
+        assert_eq!(days_spent(&item, noon("2025-03-25")), Some(0));
+
+        // Missing dates
+        for (start_date, end_date) in [("", "2025-03-20"), ("2025-03-10", ""), ("", "")] {
+            for state in STATES {
+                let item = RecordEntry {
+                    start_date: start_date.to_string(),
+                    end_date: end_date.to_string(),
+                    state,
+                    ..Default::default()
+                };
+                assert_eq!(days_spent(&item, noon("2025-03-15")), None);
+            }
+        }
+    }
+
+    #[test]
+    fn test_distribution() {
+        let item = Distribution::default();
+        assert_eq!(item.total(), 0);
+        assert_eq!(item.mean(), None);
+        assert_eq!(item.median(), None);
+        assert_eq!(item.percentile(90.0), None);
+
+        let item = distribution(&[7]);
+        assert_eq!(item.median(), Some(7.0));
+        assert_eq!(item.percentile(90.0), Some(7.0));
+
+        let mut item = distribution(&[4, 1, 3]);
+        item.push(2);
+        assert_eq!(item.len(), 4);
+        assert_eq!(item.total(), 10);
+        assert_eq!(item.mean(), Some(2.5));
+        assert_eq!(item.median(), Some(2.5));
+        assert_eq!(item.percentile(0.0), Some(1.0));
+        assert_eq!(item.percentile(100.0), Some(4.0));
+        assert_eq!(item.percentile(150.0), Some(4.0));
+        assert!((item.percentile(90.0).unwrap() - 3.7).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_aggregates() {
+        let now = noon("2025-03-15");
+        let entries = vec![
+            RecordEntry {
+                start_date: "2025-03-10".to_string(),
+                end_date: "2025-03-20".to_string(),
+                state: RecordState::Running,
+                tags: vec!["work".to_string()],
+                tracked_seconds: 3600,
+                ..Default::default()
+            },
+            RecordEntry {
+                start_date: "2025-03-12".to_string(),
+                end_date: "2025-03-14".to_string(),
+                state: RecordState::Finished,
+                finished_date: "2025-03-13".to_string(),
+                tags: vec!["Work".to_string(), "home".to_string()],
+                ..Default::default()
+            },
+            RecordEntry {
+                start_date: "2025-04-01".to_string(),
+                end_date: "2025-04-05".to_string(),
+                ..Default::default()
+            },
+            RecordEntry::default(),
+        ];
+
+        let item = aggregate(&entries, now);
+        assert_eq!(item.count, 4);
+        assert_eq!(item.days, distribution(&[5, 1]));
+        assert_eq!(item.tracked_seconds, distribution(&[3600]));
+
+        let items = by_state(&entries, now)
+            .into_iter()
+            .map(|(state, item)| (state, item.count, item.days.total()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            items,
+            [
+                (RecordState::NotStarted, 2, 0),
+                (RecordState::Running, 1, 5),
+                (RecordState::Finished, 1, 1),
+                (RecordState::Giveup, 0, 0),
+                (RecordState::Timeout, 0, 0),
+            ]
+        );
+
+        let items = by_tag(&entries, now)
+            .into_iter()
+            .map(|(tag, item)| (tag, item.count, item.days.median()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            items,
+            [
+                ("home".to_string(), 1, Some(1.0)),
+                ("work".to_string(), 2, Some(3.0)),
+                (String::default(), 2, None),
+            ]
+        );
+
+        let items = by_period(&entries, Period::Week, now)
+            .into_iter()
+            .map(|(start, item)| (start, item.count))
+            .collect::<Vec<_>>();
+        assert_eq!(items, [(day("2025-03-10"), 2), (day("2025-03-31"), 1)]);
+
+        let items = by_period(&entries, Period::Month, now)
+            .into_iter()
+            .map(|(start, item)| (start, item.count))
+            .collect::<Vec<_>>();
+        assert_eq!(items, [(day("2025-03-01"), 2), (day("2025-04-01"), 1)]);
+    }
+}
@@ -0,0 +1,1789 @@
+use super::{
+    change, dependency, field,
+    history::{Change, Placement, Snapshot},
+    plan, project,
+    record::{self, today},
+    recurrence,
+    search::Query,
+    session,
+    transfer::{self, Bundle, Conflict, ImportSummary},
+};
+use crate::db::{
+    self, Storage, WriteOp,
+    def::{
+        ARCHIVE_TABLE, CHANGE_TABLE, CORRUPT_TABLE, ChangeEntry, CorruptEntry, FIELD_TABLE,
+        FieldEntry, PROJECT_TABLE, ProjectEntry, RECORD_TABLE, RecordEntry, RecordState,
+        SESSION_TABLE, SessionEntry, TRASH_TABLE, TrashEntry,
+    },
+};
+use anyhow::{Context, Result, bail};
+use std::{collections::HashMap, sync::Arc};
+use uuid::Uuid;
+
+// Record and archive operations shared by the desktop window and the command
+// line. The callers keep their own view of the entries, the store only applies
+// the record rules and persists the result.
+#[derive(Clone)]
+pub struct TaskStore {
+    storage: Arc<dyn Storage>,
+}
+
+impl Default for TaskStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TaskStore {
+    // Uses the storage opened by `db::init`
+    pub fn new() -> Self {
+        Self::with_storage(db::storage())
+    }
+
+    pub fn with_storage(storage: Arc<dyn Storage>) -> Self {
+        Self { storage }
+    }
+
+    fn db(&self) -> &dyn Storage {
+        self.storage.as_ref()
+    }
+
+    // Newest record first, with the state refreshed to the current time
+    pub async fn records(&self) -> Result<Vec<RecordEntry>> {
+        let now = cutil::time::timestamp();
+        let mut entries = load(self.db(), RECORD_TABLE).await?;
+
+        entries
+            .iter_mut()
+            .for_each(|entry| record::refresh_state(entry, now));
+        entries.reverse();
+        dependency::fill(&mut entries);
+
+        fill_tracked_time(self.db(), &mut entries).await?;
+        Ok(entries)
+    }
+
+    pub async fn archives(&self) -> Result<Vec<RecordEntry>> {
+        let mut entries = load(self.db(), ARCHIVE_TABLE).await?;
+        fill_tracked_time(self.db(), &mut entries).await?;
+        Ok(entries)
+    }
+
+    // Same order as `records`
+    pub async fn search_records(&self, query: &Query) -> Result<Vec<RecordEntry>> {
+        let mut entries = search(self.db(), RECORD_TABLE, query).await?;
+        entries.reverse();
+
+        fill_tracked_time(self.db(), &mut entries).await?;
+        Ok(entries)
+    }
+
+    pub async fn search_archives(&self, query: &Query) -> Result<Vec<RecordEntry>> {
+        let mut entries = search(self.db(), ARCHIVE_TABLE, query).await?;
+        fill_tracked_time(self.db(), &mut entries).await?;
+        Ok(entries)
+    }
+
+    // Archived entries followed by the current records
+    pub async fn statistic_entries(&self) -> Result<Vec<RecordEntry>> {
+        let mut entries = load(self.db(), ARCHIVE_TABLE).await?;
+        entries.extend(load(self.db(), RECORD_TABLE).await?);
+
+        fill_tracked_time(self.db(), &mut entries).await?;
+
+        let changes = load_changes(self.db(), "1", &[]).await?;
+        let extensions = change::deadline_extensions_by_record(&changes);
+        for entry in entries.iter_mut() {
+            entry.deadline_extensions = extensions.get(&entry.uuid).copied().unwrap_or_default();
+        }
+        change::fill_history(&mut entries, &changes);
+
+        Ok(entries)
+    }
+
+    pub async fn find_record(&self, uuid_prefix: &str) -> Result<RecordEntry> {
+        find(self.db(), RECORD_TABLE, uuid_prefix).await
+    }
+
+    pub async fn find_archive(&self, uuid_prefix: &str) -> Result<RecordEntry> {
+        find(self.db(), ARCHIVE_TABLE, uuid_prefix).await
+    }
+
+    // Assigns an uuid to the entry if it does not have one yet
+    pub async fn add(&self, mut entry: RecordEntry) -> Result<RecordEntry> {
+        if entry.uuid.is_empty() {
+            entry.uuid = Uuid::new_v4().to_string();
+        }
+
+        plan::normalize(&mut entry)?;
+        recurrence::normalize(&mut entry)?;
+        field::normalize(&mut entry, &self.fields().await?)?;
+        self.check_links(&mut entry).await?;
+        self.check_project(&entry).await?;
+        record::refresh_state(&mut entry, cutil::time::timestamp());
+        insert(self.db(), RECORD_TABLE, &entry).await?;
+
+        Ok(entry)
+    }
+
+    // The changed fields are logged to the change table
+    pub async fn update(&self, entry: RecordEntry) -> Result<RecordEntry> {
+        self.update_with(entry, vec![]).await
+    }
+
+    // Same as `update`, `ops` are written in the same transaction. They are the
+    // next occurrence from `recur` when a repeating record is finished.
+    pub async fn update_with(
+        &self,
+        mut entry: RecordEntry,
+        mut ops: Vec<WriteOp>,
+    ) -> Result<RecordEntry> {
+        plan::normalize(&mut entry)?;
+        recurrence::normalize(&mut entry)?;
+        field::normalize(&mut entry, &self.fields().await?)?;
+        self.check_links(&mut entry).await?;
+        self.check_project(&entry).await?;
+        record::refresh_state(&mut entry, cutil::time::timestamp());
+
+        ops.extend(update_record_ops(self.db(), &entry).await?);
+        self.db().transaction(ops).await?;
+
+        Ok(entry)
+    }
+
+    // The next occurrence of a repeating record and the op adding it. The
+    // repeat rule is moved from `entry` to the new record, the caller writes
+    // the op in the same transaction as `entry`.
+    pub fn recur(entry: &mut RecordEntry) -> Result<(Option<RecordEntry>, Vec<WriteOp>)> {
+        let Some(next) = recurrence::next_occurrence(entry, cutil::time::timestamp())? else {
+            return Ok((None, vec![]));
+        };
+
+        let op = WriteOp::insert(RECORD_TABLE, &next.uuid, &serde_json::to_string(&next)?);
+        Ok((Some(next), vec![op]))
+    }
+
+    // Finishing a repeating record also adds its next occurrence in the same
+    // transaction, which is returned as the second item.
+    pub async fn transition(
+        &self,
+        mut entry: RecordEntry,
+        state: RecordState,
+    ) -> Result<(RecordEntry, Option<RecordEntry>)> {
+        record::refresh_state(&mut entry, cutil::time::timestamp());
+
+        if entry.state == state {
+            bail!("record is already {}", state.as_str());
+        }
+
+        if state == RecordState::Running {
+            let records = load(self.db(), RECORD_TABLE).await?;
+            if let Some(item) = dependency::blockers(&entry, &records).first() {
+                bail!("record is blocked by `{}`", item.title);
+            }
+        }
+
+        // The dates are shifted from the planned ones, before they are changed
+        let (next, mut ops) = if state == RecordState::Finished {
+            Self::recur(&mut entry)?
+        } else {
+            (None, vec![])
+        };
+
+        if !record::update_state(&mut entry, state, &today()) {
+            bail!("can not move a record to {}", state.as_str());
+        }
+
+        ops.extend(update_record_ops(self.db(), &entry).await?);
+        self.db().transaction(ops).await?;
+        Ok((entry, next))
+    }
+
+    // The records which are blocked by `uuid`
+    pub async fn dependents(&self, uuid: &str) -> Result<Vec<RecordEntry>> {
+        let records = load(self.db(), RECORD_TABLE).await?;
+        Ok(dependency::dependents(uuid, &records)
+            .into_iter()
+            .cloned()
+            .collect())
+    }
+
+    // Removes the links to `uuid` from the other records, the changes are
+    // logged. Returns the updated records and the ops, which the caller writes
+    // in the same transaction as the move of `uuid`.
+    pub async fn unlink_ops(&self, uuid: &str) -> Result<(Vec<RecordEntry>, Vec<WriteOp>)> {
+        let now = cutil::time::timestamp();
+        let mut ops = vec![];
+        let mut entries = vec![];
+
+        for old in self.dependents(uuid).await? {
+            let mut entry = old.clone();
+            entry.blocked_by.retain(|item| item != uuid);
+
+            ops.push(WriteOp::update(
+                RECORD_TABLE,
+                &entry.uuid,
+                &serde_json::to_string(&entry)?,
+            ));
+            ops.extend(change_ops(&old, &entry, now)?);
+            entries.push(entry);
+        }
+
+        Ok((entries, ops))
+    }
+
+    // Moves the record to the trash, `ops` are written in the same transaction
+    pub async fn remove(&self, uuid: &str, ops: Vec<WriteOp>) -> Result<()> {
+        trash(self.db(), RECORD_TABLE, uuid, ops).await
+    }
+
+    // A running timer of the record is stopped. The timer, `ops` and the move
+    // to the archive table are written in one transaction, so a failure leaves
+    // all of them as they were.
+    pub async fn archive(
+        &self,
+        mut entry: RecordEntry,
+        mut ops: Vec<WriteOp>,
+    ) -> Result<RecordEntry> {
+        record::refresh_state(&mut entry, cutil::time::timestamp());
+
+        ops.extend(stop_timer_ops(self.db(), &entry.uuid).await?);
+        entry.is_timing = false;
+        ops.push(WriteOp::move_row(
+            RECORD_TABLE,
+            ARCHIVE_TABLE,
+            &entry.uuid,
+            &serde_json::to_string(&entry)?,
+        ));
+        self.db().transaction(ops).await?;
+
+        Ok(entry)
+    }
+
+    // The recovered record keeps its uuid
+    pub async fn recover(&self, mut entry: RecordEntry) -> Result<RecordEntry> {
+        record::refresh_state(&mut entry, cutil::time::timestamp());
+
+        self.db()
+            .move_row(
+                ARCHIVE_TABLE,
+                RECORD_TABLE,
+                &entry.uuid,
+                &serde_json::to_string(&entry)?,
+            )
+            .await?;
+
+        Ok(entry)
+    }
+
+    // Moves the archive to the trash
+    pub async fn remove_archive(&self, uuid: &str, ops: Vec<WriteOp>) -> Result<()> {
+        trash(self.db(), ARCHIVE_TABLE, uuid, ops).await
+    }
+
+    // Newest deleted first
+    pub async fn trash_entries(&self) -> Result<Vec<TrashEntry>> {
+        let mut entries = vec![];
+
+        for item in self.db().select_all(TRASH_TABLE).await? {
+            match serde_json::from_str::<TrashEntry>(&item.data) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => log::warn!("skip {TRASH_TABLE} entry {}: {e:?}", item.uuid),
+            }
+        }
+
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.deleted_at));
+        Ok(entries)
+    }
+
+    pub async fn find_trash(&self, uuid_prefix: &str) -> Result<TrashEntry> {
+        let mut entries = self
+            .trash_entries()
+            .await?
+            .into_iter()
+            .filter(|entry| entry.entry.uuid.starts_with(uuid_prefix))
+            .collect::<Vec<_>>();
+
+        match entries.len() {
+            0 => bail!("no trash entry matches `{uuid_prefix}`"),
+            1 => Ok(entries.remove(0)),
+            n => bail!("{n} trash entries match `{uuid_prefix}`, use a longer uuid prefix"),
+        }
+    }
+
+    // Moves the entry back to the table it was removed from. It fails if an
+    // entry with the same uuid was added there since.
+    pub async fn restore_trash(&self, uuid: &str) -> Result<TrashEntry> {
+        let mut trash: TrashEntry =
+            serde_json::from_str(&self.db().select(TRASH_TABLE, uuid).await?.data)?;
+        if trash.table != RECORD_TABLE && trash.table != ARCHIVE_TABLE {
+            bail!("unknown table `{}`", trash.table);
+        }
+
+        if trash.table == RECORD_TABLE {
+            record::refresh_state(&mut trash.entry, cutil::time::timestamp());
+        }
+
+        self.db()
+            .move_row(
+                TRASH_TABLE,
+                &trash.table,
+                uuid,
+                &serde_json::to_string(&trash.entry)?,
+            )
+            .await?;
+
+        Ok(trash)
+    }
+
+    // Deletes the entry, its sessions and changes for good
+    pub async fn remove_trash(&self, uuid: &str) -> Result<()> {
+        let ops = remove_trash_ops(self.db(), uuid).await?;
+        self.db().transaction(ops).await
+    }
+
+    pub async fn remove_all_trash(&self) -> Result<()> {
+        let mut ops = vec![];
+        for item in self.db().select_all(TRASH_TABLE).await? {
+            ops.extend(remove_trash_ops(self.db(), &item.uuid).await?);
+        }
+
+        self.db().transaction(ops).await
+    }
+
+    // Deletes the entries removed more than `retention_days` ago, nothing is
+    // deleted if it is 0. Returns the number of the deleted entries.
+    pub async fn purge_trash(&self, retention_days: u32) -> Result<usize> {
+        if retention_days == 0 {
+            return Ok(0);
+        }
+
+        let deadline = cutil::time::timestamp() - retention_days as i64 * 24 * 60 * 60;
+        let (mut ops, mut counts) = (vec![], 0);
+
+        for entry in self.trash_entries().await? {
+            if entry.deleted_at < deadline {
+                ops.extend(remove_trash_ops(self.db(), &entry.entry.uuid).await?);
+                counts += 1;
+            }
+        }
+
+        self.db().transaction(ops).await?;
+        Ok(counts)
+    }
+
+    pub async fn export(&self) -> Result<Bundle> {
+        Ok(Bundle {
+            records: self.records().await?,
+            archives: self.archives().await?,
+            projects: self.projects().await?,
+        })
+    }
+
+    // Entries whose uuid is already in the record or archive table are handled
+    // by `conflict`. An overwritten entry moves to the table it is imported to.
+    // A project is matched by its uuid or name and added if it is missing.
+    // Everything is written in one transaction, a failed import changes nothing.
+    pub async fn import(&self, bundle: Bundle, conflict: Conflict) -> Result<ImportSummary> {
+        let now = cutil::time::timestamp();
+        let fields = self.fields().await?;
+        let mut summary = ImportSummary::default();
+
+        // Every entry is checked before the first one is written
+        let mut projects = self.projects().await?;
+        let mut new_projects = vec![];
+        let mut project_uuids = HashMap::new();
+        for item in bundle.projects {
+            let item = project::check_project(&item)?;
+            if item.uuid.is_empty() {
+                continue;
+            }
+
+            match projects.iter().find(|project| {
+                project.uuid == item.uuid || project.name.eq_ignore_ascii_case(&item.name)
+            }) {
+                Some(project) => {
+                    project_uuids.insert(item.uuid, project.uuid.clone());
+                }
+                None => {
+                    projects.push(item.clone());
+                    new_projects.push(item);
+                }
+            }
+        }
+
+        let mut items = vec![];
+        for (table, entries) in [
+            (RECORD_TABLE, bundle.records),
+            (ARCHIVE_TABLE, bundle.archives),
+        ] {
+            for mut entry in entries {
+                transfer::prepare(&mut entry)?;
+                field::normalize(&mut entry, &fields)
+                    .with_context(|| format!("invalid field of `{}`", entry.title))?;
+
+                if let Some(uuid) = project_uuids.get(&entry.project) {
+                    entry.project = uuid.clone();
+                }
+
+                if table == RECORD_TABLE {
+                    record::refresh_state(&mut entry, now);
+                }
+
+                items.push((table, entry));
+            }
+        }
+
+        let mut ops = vec![];
+        for item in new_projects {
+            ops.push(WriteOp::insert(
+                PROJECT_TABLE,
+                &item.uuid,
+                &serde_json::to_string(&item)?,
+            ));
+        }
+
+        // The tables of the uuids written so far, a uuid can repeat in the file
+        let mut written = HashMap::new();
+        for (table, mut entry) in items {
+            let old_table = match written.get(&entry.uuid) {
+                Some(table) => Some(*table),
+                None => table_of(self.db(), &entry.uuid).await,
+            };
+
+            match (old_table, conflict) {
+                (None, _) => summary.added += 1,
+                (Some(_), Conflict::Skip) => {
+                    summary.skipped += 1;
+                    continue;
+                }
+                (Some(old_table), Conflict::Overwrite) => {
+                    summary.overwritten += 1;
+                    if old_table == table {
+                        ops.push(WriteOp::update(
+                            table,
+                            &entry.uuid,
+                            &serde_json::to_string(&entry)?,
+                        ));
+                        continue;
+                    }
+
+                    ops.push(WriteOp::delete(old_table, &entry.uuid));
+                }
+                (Some(_), Conflict::Duplicate) => {
+                    entry.uuid = Uuid::new_v4().to_string();
+                    summary.added += 1;
+                }
+            }
+
+            ops.push(WriteOp::insert(
+                table,
+                &entry.uuid,
+                &serde_json::to_string(&entry)?,
+            ));
+            written.insert(entry.uuid, table);
+        }
+
+        self.db().transaction(ops).await?;
+        Ok(summary)
+    }
+
+    // The custom fields in the order they are added
+    pub async fn fields(&self) -> Result<Vec<FieldEntry>> {
+        let mut entries = vec![];
+
+        for item in self.db().select_all(FIELD_TABLE).await? {
+            match serde_json::from_str::<FieldEntry>(&item.data) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => log::warn!("skip {FIELD_TABLE} entry {}: {e:?}", item.uuid),
+            }
+        }
+
+        Ok(entries)
+    }
+
+    // The name should not be used by another field
+    pub async fn add_field(&self, entry: FieldEntry) -> Result<FieldEntry> {
+        let mut entry = field::check_field(&entry)?;
+        if field::find(&self.fields().await?, &entry.name).is_some() {
+            bail!("field `{}` already exists", entry.name);
+        }
+
+        entry.uuid = Uuid::new_v4().to_string();
+        self.db()
+            .insert(FIELD_TABLE, &entry.uuid, &serde_json::to_string(&entry)?)
+            .await?;
+
+        Ok(entry)
+    }
+
+    // The values of the records are kept and shown as text, adding the field
+    // again brings them back
+    pub async fn remove_field(&self, name: &str) -> Result<FieldEntry> {
+        let fields = self.fields().await?;
+        let Some(entry) = field::find(&fields, name) else {
+            bail!("no field `{name}`");
+        };
+
+        self.db().delete(FIELD_TABLE, &entry.uuid).await?;
+        Ok(entry.clone())
+    }
+
+    // The projects in the order they are added
+    pub async fn projects(&self) -> Result<Vec<ProjectEntry>> {
+        let mut entries = vec![];
+
+        for item in self.db().select_all(PROJECT_TABLE).await? {
+            match serde_json::from_str::<ProjectEntry>(&item.data) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => log::warn!("skip {PROJECT_TABLE} entry {}: {e:?}", item.uuid),
+            }
+        }
+
+        Ok(entries)
+    }
+
+    pub async fn find_project(&self, name_or_uuid: &str) -> Result<ProjectEntry> {
+        Ok(project::find(&self.projects().await?, name_or_uuid)?.clone())
+    }
+
+    // The name should not be used by another project
+    pub async fn add_project(&self, entry: ProjectEntry) -> Result<ProjectEntry> {
+        let mut entry = project::check_project(&entry)?;
+        self.check_project_name(&entry).await?;
+
+        entry.uuid = Uuid::new_v4().to_string();
+        entry.is_archived = false;
+        self.db()
+            .insert(PROJECT_TABLE, &entry.uuid, &serde_json::to_string(&entry)?)
+            .await?;
+
+        Ok(entry)
+    }
+
+    // Changes the name, color and description, use `archive_project` and
+    // `recover_project` for the archived flag
+    pub async fn update_project(&self, entry: ProjectEntry) -> Result<ProjectEntry> {
+        let mut entry = project::check_project(&entry)?;
+        self.check_project_name(&entry).await?;
+
+        entry.is_archived = self.find_project(&entry.uuid).await?.is_archived;
+        self.db()
+            .update(PROJECT_TABLE, &entry.uuid, &serde_json::to_string(&entry)?)
+            .await?;
+
+        Ok(entry)
+    }
+
+    // The records and archives of the project are left without a project in
+    // the same transaction. Returns the number of them.
+    pub async fn remove_project(&self, uuid: &str) -> Result<usize> {
+        let entry = self.find_project(uuid).await?;
+        let now = cutil::time::timestamp();
+        let mut ops = vec![WriteOp::delete(PROJECT_TABLE, &entry.uuid)];
+        let mut count = 0;
+
+        for table in [RECORD_TABLE, ARCHIVE_TABLE] {
+            for old in load(self.db(), table).await? {
+                if old.project != entry.uuid {
+                    continue;
+                }
+
+                let mut item = old.clone();
+                item.project.clear();
+                ops.push(WriteOp::update(
+                    table,
+                    &item.uuid,
+                    &serde_json::to_string(&item)?,
+                ));
+                ops.extend(change_ops(&old, &item, now)?);
+                count += 1;
+            }
+        }
+
+        self.db().transaction(ops).await?;
+        Ok(count)
+    }
+
+    // Moves all the records of the project to the archive and marks the
+    // project archived in one transaction. The running timers are stopped.
+    // Returns the archived records.
+    pub async fn archive_project(&self, uuid: &str) -> Result<Vec<RecordEntry>> {
+        let mut entry = self.find_project(uuid).await?;
+        if entry.is_archived {
+            bail!("project `{}` is already archived", entry.name);
+        }
+
+        let now = cutil::time::timestamp();
+        let mut ops = vec![];
+        let mut records = vec![];
+
+        for mut item in load(self.db(), RECORD_TABLE).await? {
+            if item.project != entry.uuid {
+                continue;
+            }
+
+            record::refresh_state(&mut item, now);
+            item.is_timing = false;
+            ops.extend(stop_timer_ops(self.db(), &item.uuid).await?);
+            ops.push(WriteOp::move_row(
+                RECORD_TABLE,
+                ARCHIVE_TABLE,
+                &item.uuid,
+                &serde_json::to_string(&item)?,
+            ));
+            records.push(item);
+        }
+
+        entry.is_archived = true;
+        ops.push(WriteOp::update(
+            PROJECT_TABLE,
+            &entry.uuid,
+            &serde_json::to_string(&entry)?,
+        ));
+        self.db().transaction(ops).await?;
+
+        Ok(records)
+    }
+
+    // The reverse of `archive_project`, all the archives of the project are
+    // recovered. Returns the recovered records.
+    pub async fn recover_project(&self, uuid: &str) -> Result<Vec<RecordEntry>> {
+        let mut entry = self.find_project(uuid).await?;
+        if !entry.is_archived {
+            bail!("project `{}` is not archived", entry.name);
+        }
+
+        let now = cutil::time::timestamp();
+        let mut ops = vec![];
+        let mut records = vec![];
+
+        for mut item in load(self.db(), ARCHIVE_TABLE).await? {
+            if item.project != entry.uuid {
+                continue;
+            }
+
+            record::refresh_state(&mut item, now);
+            ops.push(WriteOp::move_row(
+                ARCHIVE_TABLE,
+                RECORD_TABLE,
+                &item.uuid,
+                &serde_json::to_string(&item)?,
+            ));
+            records.push(item);
+        }
+
+        entry.is_archived = false;
+        ops.push(WriteOp::update(
+            PROJECT_TABLE,
+            &entry.uuid,
+            &serde_json::to_string(&entry)?,
+        ));
+        self.db().transaction(ops).await?;
+
+        Ok(records)
+    }
+
+    async fn check_project_name(&self, entry: &ProjectEntry) -> Result<()> {
+        if self
+            .projects()
+            .await?
+            .iter()
+            .any(|item| item.uuid != entry.uuid && item.name.eq_ignore_ascii_case(&entry.name))
+        {
+            bail!("project `{}` already exists", entry.name);
+        }
+
+        Ok(())
+    }
+
+    // A record can only be moved to a project which exists and is not
+    // archived. A record which is already in a removed project keeps it.
+    async fn check_project(&self, entry: &RecordEntry) -> Result<()> {
+        if entry.project.is_empty() {
+            return Ok(());
+        }
+
+        let saved = match self.db().select(RECORD_TABLE, &entry.uuid).await {
+            Ok(item) => serde_json::from_str::<RecordEntry>(&item.data)
+                .map(|item| item.project)
+                .unwrap_or_default(),
+            Err(_) => String::default(),
+        };
+
+        if saved == entry.project {
+            return Ok(());
+        }
+
+        match project::find_by_uuid(&self.projects().await?, &entry.project) {
+            None => bail!("no project `{}` for `{}`", entry.project, entry.title),
+            Some(item) if item.is_archived => {
+                bail!("project `{}` is archived", item.name)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // A new link should point to a record or an archive. The links which
+    // are already saved are kept, even if their record is removed.
+    async fn check_links(&self, entry: &mut RecordEntry) -> Result<()> {
+        if entry.blocked_by.is_empty() {
+            entry.is_blocked = false;
+            return Ok(());
+        }
+
+        let records = load(self.db(), RECORD_TABLE).await?;
+        dependency::check(entry, &records)?;
+        entry.is_blocked = !dependency::blockers(entry, &records).is_empty();
+
+        let saved = records
+            .iter()
+            .find(|item| item.uuid == entry.uuid)
+            .map(|item| item.blocked_by.clone())
+            .unwrap_or_default();
+
+        for uuid in entry.blocked_by.iter().filter(|uuid| !saved.contains(uuid)) {
+            if table_of(self.db(), uuid).await.is_none() {
+                bail!("no record `{uuid}` to block `{}`", entry.title);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn sessions(&self, record_uuid: &str) -> Result<Vec<SessionEntry>> {
+        load_sessions(self.db(), "record_uuid = ?", &[record_uuid.to_string()]).await
+    }
+
+    // The sessions of all the records and archives
+    pub async fn all_sessions(&self) -> Result<Vec<SessionEntry>> {
+        load_sessions(self.db(), "1", &[]).await
+    }
+
+    // Oldest first
+    pub async fn changes(&self, record_uuid: &str) -> Result<Vec<ChangeEntry>> {
+        let mut changes = load_changes(self.db(), "record_uuid = ?", &[record_uuid.to_string()])
+            .await?
+            .into_iter()
+            .filter(|item| item.record_uuid == record_uuid)
+            .collect::<Vec<_>>();
+
+        changes.sort_by_key(|item| item.timestamp);
+        Ok(changes)
+    }
+
+    pub async fn running_session(&self) -> Result<Option<SessionEntry>> {
+        Ok(running_sessions(self.db()).await?.pop())
+    }
+
+    // Starts a new work session of the record. Only one timer runs at a time,
+    // so the running one is stopped first.
+    pub async fn start_timer(&self, record_uuid: &str, note: &str) -> Result<SessionEntry> {
+        self.db()
+            .is_exist(RECORD_TABLE, record_uuid)
+            .await
+            .with_context(|| format!("no record `{record_uuid}`"))?;
+
+        if let Some(session) = self.running_session().await?
+            && session.record_uuid == record_uuid
+        {
+            bail!("the timer of the record is already running");
+        }
+
+        let session = SessionEntry {
+            uuid: Uuid::new_v4().to_string(),
+            record_uuid: record_uuid.to_string(),
+            start: cutil::time::timestamp(),
+            end: 0,
+            note: note.to_string(),
+        };
+
+        // The other timer is stopped in the same transaction, so there is
+        // never more than one open session
+        let (_, mut ops) = stop_sessions(running_sessions(self.db()).await?)?;
+        ops.push(WriteOp::insert(
+            SESSION_TABLE,
+            &session.uuid,
+            &serde_json::to_string(&session)?,
+        ));
+        self.db().transaction(ops).await?;
+
+        Ok(session)
+    }
+
+    // Returns the stopped session, `None` if no timer is running
+    pub async fn stop_timer(&self) -> Result<Option<SessionEntry>> {
+        let (stopped, ops) = stop_sessions(running_sessions(self.db()).await?)?;
+        self.db().transaction(ops).await?;
+        Ok(stopped)
+    }
+
+    // Where the record is and its sessions, for the undo history
+    pub async fn snapshot(&self, uuid: &str) -> Result<Snapshot> {
+        let mut placement = Placement::Absent;
+
+        for table in [RECORD_TABLE, ARCHIVE_TABLE] {
+            if self.db().is_exist(table, uuid).await.is_err() {
+                continue;
+            }
+
+            let entry = serde_json::from_str(&self.db().select(table, uuid).await?.data)?;
+            placement = if table == RECORD_TABLE {
+                Placement::Record(entry)
+            } else {
+                Placement::Archive(entry)
+            };
+            break;
+        }
+
+        if matches!(placement, Placement::Absent)
+            && self.db().is_exist(TRASH_TABLE, uuid).await.is_ok()
+        {
+            let entry = serde_json::from_str(&self.db().select(TRASH_TABLE, uuid).await?.data)?;
+            placement = Placement::Trash(entry);
+        }
+
+        Ok(Snapshot {
+            placement,
+            sessions: self.sessions(uuid).await?,
+        })
+    }
+
+    // Writes the before snapshots of the `changes` back, or the after ones if
+    // it is not `undo`. All of them are written in one transaction.
+    pub async fn restore(&self, changes: &[Change], undo: bool) -> Result<()> {
+        let mut ops = vec![];
+
+        let now = cutil::time::timestamp();
+
+        for change in changes.iter() {
+            let (snapshot, current) = if undo {
+                (&change.before, &change.after)
+            } else {
+                (&change.after, &change.before)
+            };
+            let uuid = &change.uuid;
+
+            if let (Placement::Record(old), Placement::Record(new)) =
+                (&current.placement, &snapshot.placement)
+            {
+                ops.extend(change_ops(old, new, now)?);
+            }
+
+            ops.push(WriteOp::delete(RECORD_TABLE, uuid));
+            ops.push(WriteOp::delete(ARCHIVE_TABLE, uuid));
+            ops.push(WriteOp::delete(TRASH_TABLE, uuid));
+
+            match &snapshot.placement {
+                Placement::Absent => (),
+                Placement::Record(entry) => ops.push(WriteOp::insert(
+                    RECORD_TABLE,
+                    uuid,
+                    &serde_json::to_string(entry)?,
+                )),
+                Placement::Archive(entry) => ops.push(WriteOp::insert(
+                    ARCHIVE_TABLE,
+                    uuid,
+                    &serde_json::to_string(entry)?,
+                )),
+                Placement::Trash(entry) => ops.push(WriteOp::insert(
+                    TRASH_TABLE,
+                    uuid,
+                    &serde_json::to_string(entry)?,
+                )),
+            }
+
+            if !change.is_sessions_changed() {
+                continue;
+            }
+
+            for session in self.sessions(uuid).await? {
+                ops.push(WriteOp::delete(SESSION_TABLE, &session.uuid));
+            }
+
+            for session in snapshot.sessions.iter() {
+                ops.push(WriteOp::insert(
+                    SESSION_TABLE,
+                    &session.uuid,
+                    &serde_json::to_string(session)?,
+                ));
+            }
+        }
+
+        self.db().transaction(ops).await
+    }
+
+    // Returns the number of the newly quarantined rows.
+    pub async fn check(&self) -> Result<i64> {
+        let counts = self.db().row_counts(CORRUPT_TABLE).await?;
+
+        load(self.db(), RECORD_TABLE).await?;
+        load(self.db(), ARCHIVE_TABLE).await?;
+
+        Ok(self.db().row_counts(CORRUPT_TABLE).await? - counts)
+    }
+
+    // Oldest first
+    pub async fn corrupt_entries(&self) -> Result<Vec<CorruptEntry>> {
+        let mut entries = vec![];
+
+        for item in self.db().select_all(CORRUPT_TABLE).await? {
+            match serde_json::from_str::<CorruptEntry>(&item.data) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => log::warn!("skip {CORRUPT_TABLE} entry {}: {e:?}", item.uuid),
+            }
+        }
+
+        Ok(entries)
+    }
+
+    pub async fn find_corrupt(&self, id_prefix: &str) -> Result<CorruptEntry> {
+        let mut entries = self
+            .corrupt_entries()
+            .await?
+            .into_iter()
+            .filter(|entry| entry.id.starts_with(id_prefix))
+            .collect::<Vec<_>>();
+
+        match entries.len() {
+            0 => bail!("no corrupt entry matches `{id_prefix}`"),
+            1 => Ok(entries.remove(0)),
+            n => bail!("{n} corrupt entries match `{id_prefix}`, use a longer id prefix"),
+        }
+    }
+
+    // Puts the repaired `data` back to the table the corrupt entry came from
+    pub async fn repair_corrupt(&self, entry: &CorruptEntry, data: &str) -> Result<RecordEntry> {
+        if entry.table != RECORD_TABLE && entry.table != ARCHIVE_TABLE {
+            bail!("unknown table `{}`", entry.table);
+        }
+
+        let mut record = serde_json::from_str::<RecordEntry>(data)?;
+        if record.uuid.is_empty() {
+            record.uuid = if entry.uuid.is_empty() {
+                Uuid::new_v4().to_string()
+            } else {
+                entry.uuid.clone()
+            };
+        }
+
+        insert(self.db(), &entry.table, &record).await?;
+        self.db().delete(CORRUPT_TABLE, &entry.id).await?;
+
+        Ok(record)
+    }
+
+    // Restores the corrupt entries which can be deserialized now, e.g. after
+    // an upgrade. Returns the number of the restored entries.
+    pub async fn retry_corrupt(&self) -> Result<usize> {
+        let mut counts = 0;
+
+        for entry in self.corrupt_entries().await? {
+            if serde_json::from_str::<RecordEntry>(&entry.data).is_err() {
+                continue;
+            }
+
+            match self.repair_corrupt(&entry, &entry.data).await {
+                Ok(_) => counts += 1,
+                Err(e) => log::warn!("restore corrupt entry {} failed: {e:?}", entry.id),
+            }
+        }
+
+        Ok(counts)
+    }
+
+    pub async fn remove_corrupt(&self, id: &str) -> Result<()> {
+        self.db().delete(CORRUPT_TABLE, id).await
+    }
+
+    pub async fn remove_all_corrupt(&self) -> Result<()> {
+        self.db().delete_all(CORRUPT_TABLE).await
+    }
+}
+
+async fn load(db: &dyn Storage, table: &str) -> Result<Vec<RecordEntry>> {
+    let mut entries = vec![];
+
+    for item in db.select_all(table).await? {
+        match serde_json::from_str::<RecordEntry>(&item.data) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => {
+                log::warn!("quarantine {table} entry {}: {e:?}", item.uuid);
+
+                if let Err(e) = quarantine(db, table, item.uuid, item.data, e.to_string()).await {
+                    log::warn!("quarantine failed: {e:?}");
+                }
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+// The sql condition only narrows down the rows, the final filter runs on the
+// loaded entries with their states refreshed. Rows which can not be parsed are
+// skipped and left to `load` to quarantine.
+async fn search(db: &dyn Storage, table: &str, query: &Query) -> Result<Vec<RecordEntry>> {
+    let now = cutil::time::timestamp();
+    #[cfg(feature = "database")]
+    let (condition, binds) = query.to_sql(table);
+
+    // Without sql all the rows are filtered here
+    #[cfg(not(feature = "database"))]
+    let (condition, binds) = ("1".to_string(), Vec::<String>::new());
+    let mut entries = vec![];
+
+    for item in db.select_where(table, &condition, &binds).await? {
+        match serde_json::from_str::<RecordEntry>(&item.data) {
+            Ok(mut entry) => {
+                if table == RECORD_TABLE {
+                    record::refresh_state(&mut entry, now);
+                }
+
+                if query.matches(&entry) {
+                    entries.push(entry);
+                }
+            }
+            Err(e) => log::warn!("skip {table} entry {}: {e:?}", item.uuid),
+        }
+    }
+
+    Ok(entries)
+}
+
+async fn load_sessions(
+    db: &dyn Storage,
+    condition: &str,
+    binds: &[String],
+) -> Result<Vec<SessionEntry>> {
+    let mut sessions = vec![];
+
+    for item in db.select_where(SESSION_TABLE, condition, binds).await? {
+        match serde_json::from_str::<SessionEntry>(&item.data) {
+            Ok(session) => sessions.push(session),
+            Err(e) => log::warn!("skip {SESSION_TABLE} entry {}: {e:?}", item.uuid),
+        }
+    }
+
+    Ok(sessions)
+}
+
+// The conditions only narrow down the rows, see `db::Storage`
+async fn running_sessions(db: &dyn Storage) -> Result<Vec<SessionEntry>> {
+    let sessions = load_sessions(db, "json_extract(data, '$.end') = 0", &[]).await?;
+    Ok(sessions.into_iter().filter(session::is_running).collect())
+}
+
+async fn load_changes(
+    db: &dyn Storage,
+    condition: &str,
+    binds: &[String],
+) -> Result<Vec<ChangeEntry>> {
+    let mut changes = vec![];
+
+    for item in db.select_where(CHANGE_TABLE, condition, binds).await? {
+        match serde_json::from_str::<ChangeEntry>(&item.data) {
+            Ok(change) => changes.push(change),
+            Err(e) => log::warn!("skip {CHANGE_TABLE} entry {}: {e:?}", item.uuid),
+        }
+    }
+
+    Ok(changes)
+}
+
+async fn remove_changes_ops(db: &dyn Storage, record_uuid: &str) -> Result<Vec<WriteOp>> {
+    Ok(
+        load_changes(db, "record_uuid = ?", &[record_uuid.to_string()])
+            .await?
+            .into_iter()
+            .filter(|change| change.record_uuid == record_uuid)
+            .map(|change| WriteOp::delete(CHANGE_TABLE, &change.uuid))
+            .collect(),
+    )
+}
+
+fn change_ops(old: &RecordEntry, new: &RecordEntry, now: i64) -> Result<Vec<WriteOp>> {
+    change::diff(old, new, now)
+        .into_iter()
+        .map(|change| {
+            Ok(WriteOp::insert(
+                CHANGE_TABLE,
+                &change.uuid,
+                &serde_json::to_string(&change)?,
+            ))
+        })
+        .collect()
+}
+
+// Updates the record and logs its changed fields
+async fn update_record_ops(db: &dyn Storage, entry: &RecordEntry) -> Result<Vec<WriteOp>> {
+    let mut ops = vec![WriteOp::update(
+        RECORD_TABLE,
+        &entry.uuid,
+        &serde_json::to_string(entry)?,
+    )];
+
+    if let Ok(item) = db.select(RECORD_TABLE, &entry.uuid).await
+        && let Ok(old) = serde_json::from_str::<RecordEntry>(&item.data)
+    {
+        ops.extend(change_ops(&old, entry, cutil::time::timestamp())?);
+    }
+
+    Ok(ops)
+}
+
+// Deletes the trash entry with its sessions and changes
+async fn remove_trash_ops(db: &dyn Storage, uuid: &str) -> Result<Vec<WriteOp>> {
+    let mut ops = vec![WriteOp::delete(TRASH_TABLE, uuid)];
+    ops.extend(remove_sessions_ops(db, uuid).await?);
+    ops.extend(remove_changes_ops(db, uuid).await?);
+    Ok(ops)
+}
+
+async fn remove_sessions_ops(db: &dyn Storage, record_uuid: &str) -> Result<Vec<WriteOp>> {
+    Ok(
+        load_sessions(db, "record_uuid = ?", &[record_uuid.to_string()])
+            .await?
+            .into_iter()
+            .filter(|session| session.record_uuid == record_uuid)
+            .map(|session| WriteOp::delete(SESSION_TABLE, &session.uuid))
+            .collect(),
+    )
+}
+
+// Stops the running timer of the record
+async fn stop_timer_ops(db: &dyn Storage, record_uuid: &str) -> Result<Vec<WriteOp>> {
+    let sessions = running_sessions(db)
+        .await?
+        .into_iter()
+        .filter(|session| session.record_uuid == record_uuid)
+        .collect();
+
+    Ok(stop_sessions(sessions)?.1)
+}
+
+// Ends the running `sessions` now. Returns the last stopped session and the
+// updates of the sessions.
+fn stop_sessions(sessions: Vec<SessionEntry>) -> Result<(Option<SessionEntry>, Vec<WriteOp>)> {
+    let now = cutil::time::timestamp();
+    let (mut stopped, mut ops) = (None, vec![]);
+
+    for mut session in sessions {
+        session.end = now.max(session.start);
+        ops.push(WriteOp::update(
+            SESSION_TABLE,
+            &session.uuid,
+            &serde_json::to_string(&session)?,
+        ));
+        stopped = Some(session);
+    }
+
+    Ok((stopped, ops))
+}
+
+// Moves the row of `table` to the trash table and stops its timer in one
+// transaction with `ops`. The sessions are kept until the entry is deleted from
+// the trash.
+async fn trash(db: &dyn Storage, table: &str, uuid: &str, mut ops: Vec<WriteOp>) -> Result<()> {
+    let mut entry: RecordEntry = serde_json::from_str(&db.select(table, uuid).await?.data)?;
+    entry.is_timing = false;
+
+    let trash = TrashEntry {
+        table: table.to_string(),
+        deleted_at: cutil::time::timestamp(),
+        entry,
+    };
+
+    ops.extend(stop_timer_ops(db, uuid).await?);
+    ops.push(WriteOp::move_row(
+        table,
+        TRASH_TABLE,
+        uuid,
+        &serde_json::to_string(&trash)?,
+    ));
+    db.transaction(ops).await
+}
+
+async fn fill_tracked_time(db: &dyn Storage, entries: &mut [RecordEntry]) -> Result<()> {
+    let sessions = load_sessions(db, "1", &[]).await?;
+    let tracked = session::tracked_seconds_by_record(&sessions, cutil::time::timestamp());
+
+    for entry in entries.iter_mut() {
+        entry.tracked_seconds = tracked.get(&entry.uuid).copied().unwrap_or_default();
+        entry.is_timing = sessions
+            .iter()
+            .any(|item| item.record_uuid == entry.uuid && session::is_running(item));
+    }
+
+    Ok(())
+}
+
+// The row is copied to the corrupt table and deleted in one transaction, so
+// it is never lost and is not quarantined again on the next load.
+async fn quarantine(
+    db: &dyn Storage,
+    table: &str,
+    uuid: String,
+    data: String,
+    error: String,
+) -> Result<()> {
+    let entry = CorruptEntry {
+        id: Uuid::new_v4().to_string(),
+        table: table.to_string(),
+        uuid,
+        data,
+        error,
+        created_at: cutil::time::local_now("%Y-%m-%d %H:%M:%S"),
+    };
+
+    db.transaction(vec![
+        WriteOp::delete(table, &entry.uuid),
+        WriteOp::insert(CORRUPT_TABLE, &entry.id, &serde_json::to_string(&entry)?),
+    ])
+    .await
+}
+
+async fn find(db: &dyn Storage, table: &str, uuid_prefix: &str) -> Result<RecordEntry> {
+    let mut entries = load(db, table)
+        .await?
+        .into_iter()
+        .filter(|entry| entry.uuid.starts_with(uuid_prefix))
+        .collect::<Vec<_>>();
+
+    match entries.len() {
+        0 => bail!("no record matches `{uuid_prefix}`"),
+        1 => Ok(entries.remove(0)),
+        n => bail!("{n} records match `{uuid_prefix}`, use a longer uuid prefix"),
+    }
+}
+
+async fn table_of(db: &dyn Storage, uuid: &str) -> Option<&'static str> {
+    for table in [RECORD_TABLE, ARCHIVE_TABLE] {
+        if db.is_exist(table, uuid).await.is_ok() {
+            return Some(table);
+        }
+    }
+
+    None
+}
+
+async fn insert(db: &dyn Storage, table: &str, entry: &RecordEntry) -> Result<()> {
+    let data = serde_json::to_string(entry)?;
+    db.insert(table, &entry.uuid, &data).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::def::FieldKind;
+    use sqldb::MemoryStorage;
+
+    fn store() -> TaskStore {
+        TaskStore::with_storage(Arc::new(MemoryStorage::new()))
+    }
+
+    #[tokio::test]
+    async fn test_archive_recover() -> Result<()> {
+        let store = store();
+        let first = store
+            .add(RecordEntry {
+                title: "first".to_string(),
+                ..Default::default()
+            })
+            .await?;
+        store
+            .add(RecordEntry {
+                title: "second".to_string(),
+                ..Default::default()
+            })
+            .await?;
+
+        let records = store.records().await?;
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].title, "second");
+
+        store.archive(first.clone(), vec![]).await?;
+        assert_eq!(store.records().await?.len(), 1);
+        assert_eq!(store.find_archive(&first.uuid).await?.title, "first");
+
+        store.recover(first.clone()).await?;
+        assert!(store.archives().await?.is_empty());
+        assert_eq!(store.find_record(&first.uuid).await?.title, "first");
+
+        // Nothing is moved if the row is not in the source table
+        assert!(store.recover(first.clone()).await.is_err());
+        assert_eq!(store.records().await?.len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_recur() -> Result<()> {
+        let store = store();
+        let entry = store
+            .add(RecordEntry {
+                title: "daily".to_string(),
+                start_date: "2026-10-12".to_string(),
+                end_date: "2026-10-13".to_string(),
+                recurrence: "daily".to_string(),
+                ..Default::default()
+            })
+            .await?;
+
+        let (entry, next) = store.transition(entry, RecordState::Finished).await?;
+        let next = next.unwrap();
+        assert!(entry.recurrence.is_empty());
+        assert_eq!(
+            store.find_record(&next.uuid).await?.recurrence,
+            "FREQ=DAILY"
+        );
+
+        // A failed archive does not add the next occurrence
+        let mut next = store.find_record(&next.uuid).await?;
+        let (after, ops) = TaskStore::recur(&mut next)?;
+        store.db().insert(ARCHIVE_TABLE, &next.uuid, "{}").await?;
+        assert!(store.archive(next.clone(), ops.clone()).await.is_err());
+        assert!(store.find_record(&after.unwrap().uuid).await.is_err());
+
+        store.db().delete(ARCHIVE_TABLE, &next.uuid).await?;
+        store.archive(next, ops).await?;
+        assert_eq!(store.records().await?.len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_timer() -> Result<()> {
+        let store = store();
+        let entry = store
+            .add(RecordEntry {
+                title: "timed".to_string(),
+                ..Default::default()
+            })
+            .await?;
+        assert!(store.start_timer("uuid-0", "").await.is_err());
+
+        store.start_timer(&entry.uuid, "note").await?;
+        assert!(store.start_timer(&entry.uuid, "").await.is_err());
+        assert!(store.records().await?[0].is_timing);
+
+        // A failed archive keeps the timer running
+        store.db().insert(ARCHIVE_TABLE, &entry.uuid, "{}").await?;
+        assert!(store.archive(entry.clone(), vec![]).await.is_err());
+        assert!(store.running_session().await?.is_some());
+        assert_eq!(store.records().await?.len(), 1);
+        store.db().delete(ARCHIVE_TABLE, &entry.uuid).await?;
+
+        // Archiving stops the timer, deleting it from the trash drops its
+        // sessions
+        let entry = store.archive(entry, vec![]).await?;
+        assert!(store.running_session().await?.is_none());
+        assert_eq!(store.sessions(&entry.uuid).await?.len(), 1);
+
+        store.remove_archive(&entry.uuid, vec![]).await?;
+        assert_eq!(store.sessions(&entry.uuid).await?.len(), 1);
+
+        store.remove_trash(&entry.uuid).await?;
+        assert!(store.sessions(&entry.uuid).await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_trash() -> Result<()> {
+        let store = store();
+        let record = store
+            .add(RecordEntry {
+                title: "record".to_string(),
+                ..Default::default()
+            })
+            .await?;
+        let archive = store
+            .add(RecordEntry {
+                title: "archive".to_string(),
+                ..Default::default()
+            })
+            .await?;
+        store.archive(archive.clone(), vec![]).await?;
+
+        store.start_timer(&record.uuid, "").await?;
+        store.remove(&record.uuid, vec![]).await?;
+        store.remove_archive(&archive.uuid, vec![]).await?;
+        assert!(store.running_session().await?.is_none());
+        assert!(store.records().await?.is_empty());
+        assert!(store.archives().await?.is_empty());
+
+        let trash = store.trash_entries().await?;
+        assert_eq!(trash.len(), 2);
+        assert_eq!(store.find_trash(&archive.uuid).await?.table, ARCHIVE_TABLE);
+
+        // Restored to the table it was removed from
+        store.restore_trash(&record.uuid).await?;
+        store.restore_trash(&archive.uuid).await?;
+        assert!(store.trash_entries().await?.is_empty());
+        assert_eq!(store.find_record(&record.uuid).await?.title, "record");
+        assert_eq!(store.find_archive(&archive.uuid).await?.title, "archive");
+        assert!(store.restore_trash(&record.uuid).await.is_err());
+
+        store.remove(&record.uuid, vec![]).await?;
+        store.remove_all_trash().await?;
+        assert!(store.trash_entries().await?.is_empty());
+        assert!(store.sessions(&record.uuid).await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_purge_trash() -> Result<()> {
+        let store = store();
+        let old = store
+            .add(RecordEntry {
+                title: "old".to_string(),
+                ..Default::default()
+            })
+            .await?;
+        let new = store
+            .add(RecordEntry {
+                title: "new".to_string(),
+                ..Default::default()
+            })
+            .await?;
+        store.remove(&new.uuid, vec![]).await?;
+
+        let trash = TrashEntry {
+            table: RECORD_TABLE.to_string(),
+            deleted_at: cutil::time::timestamp() - 31 * 24 * 60 * 60,
+            entry: old.clone(),
+        };
+        store.db().delete(RECORD_TABLE, &old.uuid).await?;
+        store
+            .db()
+            .insert(TRASH_TABLE, &old.uuid, &serde_json::to_string(&trash)?)
+            .await?;
+
+        assert_eq!(store.purge_trash(0).await?, 0);
+        assert_eq!(store.purge_trash(30).await?, 1);
+
+        let trash = store.trash_entries().await?;
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].entry.uuid, new.uuid);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_changes() -> Result<()> {
+        let store = store();
+        let mut entry = store
+            .add(RecordEntry {
+                title: "report".to_string(),
+                start_date: "2999-01-01".to_string(),
+                end_date: "2999-01-02".to_string(),
+                ..Default::default()
+            })
+            .await?;
+        assert!(store.changes(&entry.uuid).await?.is_empty());
+
+        entry.end_date = "2999-01-05".to_string();
+        entry.tags = vec!["work".to_string()];
+        let entry = store.update(entry).await?;
+
+        let changes = store.changes(&entry.uuid).await?;
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].field, change::FIELD_END_DATE);
+        assert_eq!(changes[0].old_value, "2999-01-02");
+        assert_eq!(changes[1].field, change::FIELD_TAGS);
+
+        // Giving up moves the end date to today, which is no extension
+        store.transition(entry.clone(), RecordState::Giveup).await?;
+        let changes = store.changes(&entry.uuid).await?;
+        assert!(
+            changes
+                .iter()
+                .any(|item| item.field == change::FIELD_STATE && item.new_value == "Giveup")
+        );
+        assert_eq!(store.statistic_entries().await?[0].deadline_extensions, 1);
+
+        // The changes are kept in the trash and deleted with the record
+        store.remove(&entry.uuid, vec![]).await?;
+        assert_eq!(store.changes(&entry.uuid).await?.len(), changes.len());
+        store.remove_trash(&entry.uuid).await?;
+        assert!(store.changes(&entry.uuid).await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fields() -> Result<()> {
+        let store = store();
+        store
+            .add_field(FieldEntry {
+                name: "Area".to_string(),
+                kind: FieldKind::Select,
+                options: vec!["Backend".to_string(), "UI".to_string()],
+                ..Default::default()
+            })
+            .await?;
+        assert!(
+            store
+                .add_field(FieldEntry {
+                    name: "area".to_string(),
+                    ..Default::default()
+                })
+                .await
+                .is_err()
+        );
+
+        let mut item = RecordEntry {
+            title: "styled".to_string(),
+            ..Default::default()
+        };
+        field::set_value(&mut item, "area", "ui");
+        let mut item = store.add(item).await?;
+        assert_eq!(item.fields[0].name, "Area");
+        assert_eq!(item.fields[0].value, "UI");
+
+        field::set_value(&mut item, "Area", "Docs");
+        assert!(store.update(item.clone()).await.is_err());
+
+        // The values outlive the removed field
+        store.remove_field("AREA").await?;
+        assert!(store.fields().await?.is_empty());
+        store.update(item.clone()).await?;
+        assert_eq!(store.find_record(&item.uuid).await?.fields[0].value, "Docs");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_projects() -> Result<()> {
+        let store = store();
+        let website = store
+            .add_project(ProjectEntry {
+                name: "Website".to_string(),
+                color: "green".to_string(),
+                ..Default::default()
+            })
+            .await?;
+        assert!(
+            store
+                .add_project(ProjectEntry {
+                    name: "website".to_string(),
+                    ..Default::default()
+                })
+                .await
+                .is_err()
+        );
+
+        let mut record = RecordEntry {
+            title: "landing page".to_string(),
+            ..Default::default()
+        };
+        record.project = website.uuid.clone();
+        let record = store.add(record).await?;
+        store
+            .add(RecordEntry {
+                title: "other".to_string(),
+                ..Default::default()
+            })
+            .await?;
+        store.start_timer(&record.uuid, "").await?;
+
+        let mut unknown = RecordEntry {
+            title: "unknown".to_string(),
+            ..Default::default()
+        };
+        unknown.project = "project-0".to_string();
+        assert!(store.add(unknown).await.is_err());
+
+        let archived = store.archive_project(&website.uuid).await?;
+        assert_eq!(archived.len(), 1);
+        assert!(store.find_project("website").await?.is_archived);
+        assert!(store.running_session().await?.is_none());
+        assert_eq!(store.records().await?.len(), 1);
+        assert_eq!(store.archives().await?[0].project, website.uuid);
+
+        // An archived project takes no new records
+        let mut late = RecordEntry {
+            title: "late".to_string(),
+            ..Default::default()
+        };
+        late.project = website.uuid.clone();
+        assert!(store.add(late).await.is_err());
+
+        assert_eq!(store.recover_project("Website").await?.len(), 1);
+        assert!(!store.find_project(&website.uuid).await?.is_archived);
+        assert_eq!(store.records().await?.len(), 2);
+
+        // The exported project is matched by its name in another database
+        let bundle = store.export().await?;
+        let other = self::store();
+        other
+            .add_project(ProjectEntry {
+                name: "WEBSITE".to_string(),
+                ..Default::default()
+            })
+            .await?;
+        other.import(bundle, Conflict::Skip).await?;
+        let project = other.find_project("website").await?;
+        assert_eq!(other.projects().await?.len(), 1);
+        assert_eq!(other.find_record(&record.uuid).await?.project, project.uuid);
+
+        assert_eq!(store.remove_project(&website.uuid).await?, 1);
+        assert!(store.projects().await?.is_empty());
+        assert!(store.find_record(&record.uuid).await?.project.is_empty());
+
+        let changes = store.changes(&record.uuid).await?;
+        assert_eq!(changes.last().unwrap().field, change::FIELD_PROJECT);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_import() -> Result<()> {
+        let store = store();
+        let record = store
+            .add(RecordEntry {
+                title: "record".to_string(),
+                ..Default::default()
+            })
+            .await?;
+
+        // The second entry of the same uuid moves the first one
+        let archive = RecordEntry {
+            uuid: "uuid-1".to_string(),
+            title: "archive".to_string(),
+            ..Default::default()
+        };
+        let bundle = Bundle {
+            records: vec![record.clone(), archive.clone()],
+            archives: vec![archive.clone()],
+            ..Default::default()
+        };
+
+        let summary = store.import(bundle.clone(), Conflict::Skip).await?;
+        assert_eq!((summary.added, summary.skipped), (1, 2));
+
+        let summary = store.import(bundle, Conflict::Overwrite).await?;
+        assert_eq!((summary.added, summary.overwritten), (0, 3));
+        assert_eq!(store.records().await?.len(), 1);
+        assert_eq!(store.find_archive(&archive.uuid).await?.title, "archive");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_dependencies() -> Result<()> {
+        let store = store();
+        let design = store
+            .add(RecordEntry {
+                title: "design".to_string(),
+                ..Default::default()
+            })
+            .await?;
+
+        let mut item = RecordEntry {
+            title: "build".to_string(),
+            ..Default::default()
+        };
+        item.start_date = "2099-01-01".to_string();
+        item.end_date = "2099-01-02".to_string();
+        item.blocked_by = vec![design.uuid.clone()];
+        let build = store.add(item).await?;
+
+        let records = store.records().await?;
+        assert!(records[0].is_blocked);
+        assert_eq!(records[1].blocking_count, 1);
+
+        let e = store
+            .transition(build.clone(), RecordState::Running)
+            .await
+            .unwrap_err();
+        assert_eq!(e.to_string(), "record is blocked by `design`");
+
+        let mut item = design.clone();
+        item.blocked_by = vec![build.uuid.clone()];
+        assert!(store.update(item.clone()).await.is_err());
+        item.blocked_by = vec!["missing".to_string()];
+        assert!(store.update(item).await.is_err());
+
+        store
+            .transition(design.clone(), RecordState::Finished)
+            .await?;
+        let (build, _) = store.transition(build, RecordState::Running).await?;
+        assert_eq!(build.state, RecordState::Running);
+
+        // The links are removed with the trash of the record
+        let (entries, ops) = store.unlink_ops(&design.uuid).await?;
+        assert_eq!(entries.len(), 1);
+        store.remove(&design.uuid, ops).await?;
+        assert!(store.find_record(&build.uuid).await?.blocked_by.is_empty());
+        assert!(store.dependents(&design.uuid).await?.is_empty());
+
+        let changes = store.changes(&build.uuid).await?;
+        assert_eq!(changes.last().unwrap().field, change::FIELD_BLOCKED_BY);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_check() -> Result<()> {
+        let store = store();
+        store
+            .add(RecordEntry {
+                title: "valid".to_string(),
+                ..Default::default()
+            })
+            .await?;
+        store
+            .db()
+            .insert(RECORD_TABLE, "uuid-1", "not json")
+            .await?;
+
+        assert_eq!(store.check().await?, 1);
+        assert_eq!(store.check().await?, 0);
+        assert_eq!(store.records().await?.len(), 1);
+
+        let corrupt = store.corrupt_entries().await?;
+        assert_eq!(corrupt.len(), 1);
+        assert_eq!(corrupt[0].uuid, "uuid-1");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_restore() -> Result<()> {
+        let store = store();
+        let entry = store
+            .add(RecordEntry {
+                title: "removed".to_string(),
+                ..Default::default()
+            })
+            .await?;
+        store.start_timer(&entry.uuid, "").await?;
+        store.stop_timer().await?;
+
+        let before = store.snapshot(&entry.uuid).await?;
+        store.remove(&entry.uuid, vec![]).await?;
+        let change = Change {
+            uuid: entry.uuid.clone(),
+            before,
+            after: store.snapshot(&entry.uuid).await?,
+        };
+        assert!(matches!(change.after.placement, Placement::Trash(_)));
+        assert!(!change.is_sessions_changed());
+
+        store.restore(std::slice::from_ref(&change), true).await?;
+        assert_eq!(store.find_record(&entry.uuid).await?.title, "removed");
+        assert!(store.trash_entries().await?.is_empty());
+        assert_eq!(store.sessions(&entry.uuid).await?.len(), 1);
+
+        store.restore(&[change], false).await?;
+        assert!(store.records().await?.is_empty());
+        assert_eq!(store.trash_entries().await?.len(), 1);
+
+        // Deleting from the trash drops the sessions
+        let before = store.snapshot(&entry.uuid).await?;
+        store.remove_trash(&entry.uuid).await?;
+        let change = Change {
+            uuid: entry.uuid.clone(),
+            before,
+            after: store.snapshot(&entry.uuid).await?,
+        };
+        assert!(change.is_sessions_changed());
+
+        store.restore(&[change], true).await?;
+        assert_eq!(store.find_trash(&entry.uuid).await?.entry.title, "removed");
+        assert_eq!(store.sessions(&entry.uuid).await?.len(), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,717 @@
+use super::{field, ical, plan, record, recurrence, search, session};
+use crate::db::def::{
+    ARCHIVE_TABLE, ProjectEntry, RECORD_TABLE, RecordEntry, RecordField, RecordPlanEntry,
+    RecordPriority,
+};
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use uuid::Uuid;
+
+const CSV_COLUMNS: [&str; 13] = [
+    "table",
+    "uuid",
+    "start_date",
+    "end_date",
+    "finished_date",
+    "title",
+    "state",
+    "tags",
+    "recurrence",
+    "priority",
+    "estimate",
+    "fields",
+    "plan",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    #[default]
+    Json,
+    Csv,
+    Markdown,
+    Ical,
+}
+
+impl Format {
+    pub fn parse(text: &str) -> Result<Self> {
+        match text.trim().to_lowercase().as_str() {
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            "md" | "markdown" => Ok(Format::Markdown),
+            "ics" | "ical" => Ok(Format::Ical),
+            _ => bail!("unknown format `{text}`, expect json, csv, md or ics"),
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        Format::parse(path.extension()?.to_str()?).ok()
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Csv => "csv",
+            Format::Markdown => "md",
+            Format::Ical => "ics",
+        }
+    }
+
+    // The uuids of a calendar file are kept by the calendar tools, importing
+    // it again updates the records instead of skipping them
+    pub fn default_conflict(&self) -> Conflict {
+        match self {
+            Format::Ical => Conflict::Overwrite,
+            _ => Conflict::Skip,
+        }
+    }
+}
+
+// What an import does with an entry whose uuid is already in the record or
+// archive table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Conflict {
+    #[default]
+    Skip,
+    Overwrite,
+    Duplicate,
+}
+
+impl Conflict {
+    pub fn parse(text: &str) -> Result<Self> {
+        match text.trim().to_lowercase().as_str() {
+            "skip" => Ok(Conflict::Skip),
+            "overwrite" => Ok(Conflict::Overwrite),
+            "duplicate" => Ok(Conflict::Duplicate),
+            _ => bail!("unknown conflict policy `{text}`, expect skip, overwrite or duplicate"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ImportSummary {
+    pub added: usize,
+    pub overwritten: usize,
+    pub skipped: usize,
+}
+
+// The exported records and archives. JSON is the stored `RecordEntry` as is,
+// so it round-trips without loss. CSV has one row per record with the plan
+// flattened into one cell, a step per line, and the custom fields in another
+// cell, a `name=value` per line. Markdown is a checklist meant to be read and
+// edited by hand. A sub-step of the plan is indented by two spaces per
+// level. Only JSON keeps the projects and the due dates, estimates and notes
+// of the plan steps.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Bundle {
+    pub records: Vec<RecordEntry>,
+    pub archives: Vec<RecordEntry>,
+    pub projects: Vec<ProjectEntry>,
+}
+
+impl Bundle {
+    pub fn export(&self, format: Format) -> Result<String> {
+        match format {
+            Format::Json => Ok(serde_json::to_string_pretty(self)?),
+            Format::Csv => Ok(self.to_csv()),
+            Format::Markdown => Ok(self.to_markdown()),
+            Format::Ical => Ok(ical::write(self)),
+        }
+    }
+
+    // JSON also accepts a plain list of records, e.g. the output of `ls --json`
+    pub fn import(text: &str, format: Format) -> Result<Self> {
+        match format {
+            Format::Json => {
+                if text.trim_start().starts_with('[') {
+                    Ok(Bundle {
+                        records: serde_json::from_str(text)?,
+                        ..Default::default()
+                    })
+                } else {
+                    Ok(serde_json::from_str(text)?)
+                }
+            }
+            Format::Csv => Bundle::from_csv(text),
+            Format::Markdown => Bundle::from_markdown(text),
+            Format::Ical => ical::read(text),
+        }
+    }
+
+    fn tables(&self) -> [(&'static str, &Vec<RecordEntry>); 2] {
+        [
+            (RECORD_TABLE, &self.records),
+            (ARCHIVE_TABLE, &self.archives),
+        ]
+    }
+
+    fn entries_mut(&mut self, table: &str) -> &mut Vec<RecordEntry> {
+        if table == ARCHIVE_TABLE {
+            &mut self.archives
+        } else {
+            &mut self.records
+        }
+    }
+
+    fn to_csv(&self) -> String {
+        let mut text = csv_row(&CSV_COLUMNS.map(String::from));
+
+        for (table, entries) in self.tables() {
+            for entry in entries.iter() {
+                let plan = entry
+                    .plan
+                    .iter()
+                    .map(|item| {
+                        format!(
+                            "{}[{}] {}",
+                            indent(item),
+                            if item.is_finished { "x" } else { " " },
+                            single_line(&item.detail)
+                        )
+                    })
+                    .collect::<Vec<_>>();
+
+                text.push_str(&csv_row(&[
+                    table.to_string(),
+                    entry.uuid.clone(),
+                    entry.start_date.clone(),
+                    entry.end_date.clone(),
+                    entry.finished_date.clone(),
+                    entry.title.clone(),
+                    entry.state.as_str().to_string(),
+                    entry.tags.join(", "),
+                    entry.recurrence.clone(),
+                    priority_value(entry),
+                    estimate_value(entry),
+                    entry
+                        .fields
+                        .iter()
+                        .map(|item| format!("{}={}", item.name, single_line(&item.value)))
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                    plan.join("\n"),
+                ]));
+            }
+        }
+
+        text
+    }
+
+    // Columns are matched by the header, unknown ones are ignored
+    fn from_csv(text: &str) -> Result<Self> {
+        let mut rows = parse_csv(text)?.into_iter();
+        let Some(header) = rows.next() else {
+            return Ok(Bundle::default());
+        };
+
+        let header = header
+            .iter()
+            .map(|name| name.trim().to_lowercase())
+            .collect::<Vec<_>>();
+
+        if !header.iter().any(|name| name == "title") {
+            bail!("csv header has no `title` column");
+        }
+
+        let mut bundle = Bundle::default();
+
+        for (line, row) in rows.enumerate() {
+            let mut table = RECORD_TABLE;
+            let mut entry = RecordEntry::default();
+
+            for (name, value) in header.iter().zip(row) {
+                match name.as_str() {
+                    "table" => table = parse_table(&value)?,
+                    "uuid" => entry.uuid = value.trim().to_string(),
+                    "start_date" => entry.start_date = value.trim().to_string(),
+                    "end_date" => entry.end_date = value.trim().to_string(),
+                    "finished_date" => entry.finished_date = value.trim().to_string(),
+                    "title" => entry.title = value,
+                    "state" if !value.trim().is_empty() => {
+                        entry.state = search::parse_state(value.trim())
+                            .with_context(|| format!("csv row {}", line + 2))?
+                    }
+                    "tags" => entry.tags = split_tags(&value),
+                    "recurrence" => entry.recurrence = value.trim().to_string(),
+                    "priority" if !value.trim().is_empty() => {
+                        entry.priority = search::parse_priority(value.trim())
+                            .with_context(|| format!("csv row {}", line + 2))?
+                    }
+                    "estimate" => {
+                        entry.estimate_seconds = session::parse_duration(&value)
+                            .with_context(|| format!("csv row {}", line + 2))?
+                    }
+                    "fields" => {
+                        for item in value.lines().filter(|item| !item.trim().is_empty()) {
+                            let (name, value) = field::parse_assignment(item)
+                                .with_context(|| format!("csv row {}", line + 2))?;
+                            entry.fields.push(RecordField { name, value });
+                        }
+                    }
+                    "plan" => {
+                        entry.plan = value.lines().filter_map(parse_plan_item).collect();
+                    }
+                    _ => (),
+                }
+            }
+
+            bundle.entries_mut(table).push(entry);
+        }
+
+        Ok(bundle)
+    }
+
+    fn to_markdown(&self) -> String {
+        let mut text = String::default();
+
+        for (title, (_, entries)) in ["Records", "Archives"].into_iter().zip(self.tables()) {
+            text.push_str(&format!("# {title}\n"));
+
+            for entry in entries.iter() {
+                text.push_str(&format!("\n## {}\n\n", single_line(&entry.title)));
+                text.push_str(&format!("- uuid: {}\n", entry.uuid));
+                text.push_str(&format!("- state: {}\n", entry.state.as_str()));
+                text.push_str(&format!(
+                    "- date: {} -> {}\n",
+                    entry.start_date, entry.end_date
+                ));
+
+                if !entry.finished_date.is_empty() {
+                    text.push_str(&format!("- finished: {}\n", entry.finished_date));
+                }
+
+                if !entry.tags.is_empty() {
+                    text.push_str(&format!("- tags: {}\n", entry.tags.join(", ")));
+                }
+
+                if !entry.recurrence.is_empty() {
+                    text.push_str(&format!("- repeat: {}\n", entry.recurrence));
+                }
+
+                let priority = priority_value(entry);
+                if !priority.is_empty() {
+                    text.push_str(&format!("- priority: {priority}\n"));
+                }
+
+                let estimate = estimate_value(entry);
+                if !estimate.is_empty() {
+                    text.push_str(&format!("- estimate: {estimate}\n"));
+                }
+
+                for item in entry.fields.iter() {
+                    text.push_str(&format!(
+                        "- field: {}={}\n",
+                        item.name,
+                        single_line(&item.value)
+                    ));
+                }
+
+                if !entry.plan.is_empty() {
+                    text.push('\n');
+                }
+
+                for item in entry.plan.iter() {
+                    // The following lines of a multi-line step are indented
+                    let indent = indent(item);
+                    let detail = item
+                        .detail
+                        .lines()
+                        .collect::<Vec<_>>()
+                        .join(&format!("\n{indent}  "));
+                    text.push_str(&format!(
+                        "{indent}- [{}] {detail}\n",
+                        if item.is_finished { "x" } else { " " }
+                    ));
+                }
+            }
+
+            text.push('\n');
+        }
+
+        text
+    }
+
+    // `# Archives` starts the archived entries and `## title` starts an entry.
+    // The lines before the first entry and the unknown list items are ignored.
+    fn from_markdown(text: &str) -> Result<Self> {
+        let mut bundle = Bundle::default();
+        let mut table = RECORD_TABLE;
+        let mut current: Option<RecordEntry> = None;
+
+        for (line, row) in text.lines().enumerate() {
+            let trimmed = row.trim();
+
+            if let Some(title) = trimmed.strip_prefix("## ") {
+                if let Some(entry) = current.take() {
+                    bundle.entries_mut(table).push(entry);
+                }
+
+                current = Some(RecordEntry {
+                    title: title.trim().to_string(),
+                    ..Default::default()
+                });
+                continue;
+            }
+
+            if let Some(title) = trimmed.strip_prefix("# ") {
+                if let Some(entry) = current.take() {
+                    bundle.entries_mut(table).push(entry);
+                }
+
+                table = if title.trim().to_lowercase().starts_with("archive") {
+                    ARCHIVE_TABLE
+                } else {
+                    RECORD_TABLE
+                };
+                continue;
+            }
+
+            let Some(entry) = current.as_mut() else {
+                continue;
+            };
+
+            if let Some(item) = parse_plan_item(row) {
+                entry.plan.push(item);
+                continue;
+            }
+
+            if row.starts_with("  ")
+                && !trimmed.is_empty()
+                && let Some(item) = entry.plan.last_mut()
+            {
+                item.detail.push('\n');
+                item.detail.push_str(trimmed);
+                continue;
+            }
+
+            let Some((key, value)) = trimmed
+                .strip_prefix("- ")
+                .or_else(|| trimmed.strip_prefix("* "))
+                .and_then(|item| item.split_once(':'))
+            else {
+                continue;
+            };
+
+            let value = value.trim();
+            match key.trim().to_lowercase().as_str() {
+                "uuid" => entry.uuid = value.to_string(),
+                "state" => {
+                    entry.state = search::parse_state(value)
+                        .with_context(|| format!("markdown line {}", line + 1))?
+                }
+                "date" => {
+                    let (start, end) = value.split_once("->").unwrap_or((value, ""));
+                    entry.start_date = start.trim().to_string();
+                    entry.end_date = end.trim().to_string();
+                }
+                "finished" => entry.finished_date = value.to_string(),
+                "tags" => entry.tags = split_tags(value),
+                "repeat" => entry.recurrence = value.to_string(),
+                "priority" => {
+                    entry.priority = search::parse_priority(value)
+                        .with_context(|| format!("markdown line {}", line + 1))?
+                }
+                "estimate" => {
+                    entry.estimate_seconds = session::parse_duration(value)
+                        .with_context(|| format!("markdown line {}", line + 1))?
+                }
+                "field" => {
+                    let (name, value) = field::parse_assignment(value)
+                        .with_context(|| format!("markdown line {}", line + 1))?;
+                    entry.fields.push(RecordField { name, value });
+                }
+                _ => (),
+            }
+        }
+
+        if let Some(entry) = current.take() {
+            bundle.entries_mut(table).push(entry);
+        }
+
+        Ok(bundle)
+    }
+}
+
+// Fills the fields a hand written CSV or Markdown file may leave out, and
+// checks the dates, the plan and the repeat rule of an imported entry
+pub fn prepare(entry: &mut RecordEntry) -> Result<()> {
+    if entry.uuid.is_empty() {
+        entry.uuid = Uuid::new_v4().to_string();
+    }
+
+    if entry.start_date.is_empty() {
+        entry.start_date = record::today();
+    }
+
+    entry.start_date = cutil::time::date_str_add_days(&entry.start_date, 0)
+        .with_context(|| format!("invalid start date of `{}`", entry.title))?;
+
+    entry.end_date = if entry.end_date.is_empty() {
+        cutil::time::date_str_add_days(&entry.start_date, 1)?
+    } else {
+        cutil::time::date_str_add_days(&entry.end_date, 0)
+            .with_context(|| format!("invalid end date of `{}`", entry.title))?
+    };
+
+    if !entry.finished_date.is_empty() {
+        entry.finished_date = cutil::time::date_str_add_days(&entry.finished_date, 0)
+            .with_context(|| format!("invalid finished date of `{}`", entry.title))?;
+    }
+
+    plan::normalize(entry).with_context(|| format!("invalid plan of `{}`", entry.title))?;
+    recurrence::normalize(entry)
+        .with_context(|| format!("invalid repeat rule of `{}`", entry.title))
+}
+
+fn parse_table(value: &str) -> Result<&'static str> {
+    match value.trim().to_lowercase().as_str() {
+        "" | "record" => Ok(RECORD_TABLE),
+        "archive" => Ok(ARCHIVE_TABLE),
+        _ => bail!("unknown table `{value}`, expect record or archive"),
+    }
+}
+
+// `[x] detail`, `- [ ] detail` or `* [X] detail`. The level of a sub-step is
+// its indent, two spaces or a tab per level.
+pub(super) fn parse_plan_item(line: &str) -> Option<RecordPlanEntry> {
+    let spaces = line
+        .chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 2 } else { 1 })
+        .sum::<u32>();
+    let line = line.trim();
+    let line = line
+        .strip_prefix("- ")
+        .or_else(|| line.strip_prefix("* "))
+        .unwrap_or(line);
+
+    let (is_finished, detail) = if let Some(detail) = line.strip_prefix("[ ]") {
+        (false, detail)
+    } else if let Some(detail) = line
+        .strip_prefix("[x]")
+        .or_else(|| line.strip_prefix("[X]"))
+    {
+        (true, detail)
+    } else {
+        return None;
+    };
+
+    Some(RecordPlanEntry {
+        detail: detail.trim().to_string(),
+        is_finished,
+        level: spaces / 2,
+        ..Default::default()
+    })
+}
+
+// Two spaces per level of the plan step
+pub(super) fn indent(item: &RecordPlanEntry) -> String {
+    "  ".repeat(item.level as usize)
+}
+
+fn split_tags(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+// Empty for a record without priority
+fn priority_value(entry: &RecordEntry) -> String {
+    if entry.priority == RecordPriority::None {
+        String::default()
+    } else {
+        entry.priority.as_str().to_string()
+    }
+}
+
+// Empty for a record without estimate
+fn estimate_value(entry: &RecordEntry) -> String {
+    if entry.estimate_seconds > 0 {
+        session::format_duration(entry.estimate_seconds)
+    } else {
+        String::default()
+    }
+}
+
+fn single_line(text: &str) -> String {
+    text.lines().collect::<Vec<_>>().join(" ")
+}
+
+fn csv_row(cells: &[String]) -> String {
+    let cells = cells
+        .iter()
+        .map(|cell| {
+            if cell.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell.clone()
+            }
+        })
+        .collect::<Vec<_>>();
+
+    format!("{}\n", cells.join(","))
+}
+
+// RFC 4180: quoted cells may contain commas, quotes and line breaks
+fn parse_csv(text: &str) -> Result<Vec<Vec<String>>> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut cell = String::default();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    cell.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                _ => cell.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_quotes = true,
+            ',' => row.push(std::mem::take(&mut cell)),
+            '\r' => (),
+            '\n' => {
+                row.push(std::mem::take(&mut cell));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => cell.push(c),
+        }
+    }
+
+    if in_quotes {
+        bail!("csv has an unclosed quote");
+    }
+
+    if !cell.is_empty() || !row.is_empty() {
+        row.push(cell);
+        rows.push(row);
+    }
+
+    Ok(rows
+        .into_iter()
+        .filter(|row| row.iter().any(|cell| !cell.is_empty()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::def::RecordState;
+
+    fn bundle() -> Bundle {
+        Bundle {
+            records: vec![RecordEntry {
+                uuid: "uuid-1".to_string(),
+                start_date: "2026-10-12".to_string(),
+                end_date: "2026-10-13".to_string(),
+                title: "Write \"report\", v2".to_string(),
+                plan: vec![
+                    RecordPlanEntry {
+                        detail: "collect data".to_string(),
+                        is_finished: true,
+                        ..Default::default()
+                    },
+                    RecordPlanEntry {
+                        detail: "draft".to_string(),
+                        level: 1,
+                        ..Default::default()
+                    },
+                ],
+                tags: vec!["work".to_string(), "q4".to_string()],
+                state: RecordState::Running,
+                recurrence: "FREQ=WEEKLY;BYDAY=MO".to_string(),
+                priority: RecordPriority::High,
+                estimate_seconds: 2 * 3600 + 30 * 60,
+                fields: vec![
+                    RecordField {
+                        name: "Sprint".to_string(),
+                        value: "12".to_string(),
+                    },
+                    RecordField {
+                        name: "Owner".to_string(),
+                        value: "a=b".to_string(),
+                    },
+                ],
+                ..Default::default()
+            }],
+            archives: vec![RecordEntry {
+                uuid: "uuid-2".to_string(),
+                start_date: "2026-09-01".to_string(),
+                end_date: "2026-09-02".to_string(),
+                title: "Old".to_string(),
+                state: RecordState::Finished,
+                finished_date: "2026-09-04".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn assert_same(left: &Bundle, right: &Bundle) {
+        assert_eq!(
+            serde_json::to_value(left).unwrap(),
+            serde_json::to_value(right).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_round_trip() -> Result<()> {
+        let bundle = bundle();
+
+        for format in [Format::Json, Format::Csv, Format::Markdown] {
+            let text = bundle.export(format)?;
+            assert_same(&Bundle::import(&text, format)?, &bundle);
+        }
+
+        let text = serde_json::to_string(&bundle.records)?;
+        assert_eq!(Bundle::import(&text, Format::Json)?.records.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_by_hand() -> Result<()> {
+        let text = "title,tags,plan\nRead,\"a, b\",\"[x] one\n[ ] two\"\r\n";
+        let bundle = Bundle::import(text, Format::Csv)?;
+        assert_eq!(bundle.records[0].tags, vec!["a", "b"]);
+        assert!(bundle.records[0].plan[0].is_finished);
+        assert_eq!(bundle.records[0].plan[1].detail, "two");
+
+        let text = "intro\n## Read\n- [ ] one\n  more\n  - [ ] sub\n- [X] two\n- note: skipped\n# Archive\n## Old\n- state: fin\n";
+        let mut bundle = Bundle::import(text, Format::Markdown)?;
+        assert_eq!(bundle.records[0].plan[0].detail, "one\nmore");
+        assert_eq!(bundle.records[0].plan[1].level, 1);
+        assert_eq!(bundle.records[0].plan.len(), 3);
+        assert_eq!(bundle.archives[0].state, RecordState::Finished);
+
+        let entry = &mut bundle.records[0];
+        prepare(entry)?;
+        assert!(!entry.uuid.is_empty());
+        assert_eq!(
+            cutil::time::diff_dates_to_days(&entry.start_date, &entry.end_date)?,
+            1
+        );
+
+        entry.end_date = "2026-13-01".to_string();
+        assert!(prepare(entry).is_err());
+
+        assert!(Bundle::import("name\nx\n", Format::Csv).is_err());
+        assert!(Bundle::import("title\n\"x\n", Format::Csv).is_err());
+        assert_eq!(
+            Format::from_path(Path::new("a/b.MD")),
+            Some(Format::Markdown)
+        );
+
+        Ok(())
+    }
+}
@@ -1,19 +1,42 @@
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use serde_with::{serde_as, DeserializeAs, SerializeAs};
+use serde::{Deserialize, Serialize};
 
+use crate::core::project;
 use crate::slint_generatedAppWindow::{
-    RecordEntry as UIRecordEntry, RecordPlanEntry as UIRecordPlanEntry,
-    RecordState as UIRecordState,
+    CorruptEntry as UICorruptEntry, FieldEntry as UIFieldEntry, FieldKind as UIFieldKind,
+    ProjectEntry as UIProjectEntry, RecordEntry as UIRecordEntry, RecordField as UIRecordField,
+    RecordPlanEntry as UIRecordPlanEntry, RecordPriority as UIRecordPriority,
+    RecordState as UIRecordState, TrashEntry as UITrashEntry,
 };
-use slint::{Model, ModelRc, VecModel};
+use slint::{Color, Model, ModelRc, VecModel};
 
 pub const RECORD_TABLE: &str = "record";
 pub const ARCHIVE_TABLE: &str = "archive";
+pub const CORRUPT_TABLE: &str = "corrupt";
+pub const SESSION_TABLE: &str = "session";
+pub const TRASH_TABLE: &str = "trash";
+pub const CHANGE_TABLE: &str = "change";
+pub const FIELD_TABLE: &str = "field";
+pub const PROJECT_TABLE: &str = "project";
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
 pub struct RecordPlanEntry {
-    detail: String,
-    is_finished: bool,
+    pub detail: String,
+    pub is_finished: bool,
+
+    // Depth of the item in the plan tree, see `core::plan`
+    pub level: u32,
+
+    // `YYYY-MM-DD`, empty if the item has no due date
+    pub due_date: String,
+    pub estimate_seconds: i64,
+    pub note: String,
+
+    // Filled from the change table for the statistic, empty if it is unknown
+    #[serde(skip)]
+    pub added_date: String,
+    #[serde(skip)]
+    pub finished_date: String,
 }
 
 impl From<UIRecordPlanEntry> for RecordPlanEntry {
@@ -21,6 +44,12 @@ impl From<UIRecordPlanEntry> for RecordPlanEntry {
         RecordPlanEntry {
             detail: entry.detail.into(),
             is_finished: entry.is_finished,
+            level: entry.level.max(0) as u32,
+            due_date: entry.due_date.into(),
+            estimate_seconds: entry.estimate_seconds as i64,
+            note: entry.note.into(),
+            added_date: entry.added_date.into(),
+            finished_date: entry.finished_date.into(),
         }
     }
 }
@@ -30,12 +59,46 @@ impl From<RecordPlanEntry> for UIRecordPlanEntry {
         UIRecordPlanEntry {
             detail: entry.detail.into(),
             is_finished: entry.is_finished,
+            level: entry.level as i32,
+            due_date: entry.due_date.into(),
+            estimate_seconds: entry.estimate_seconds as i32,
+            note: entry.note.into(),
+            added_date: entry.added_date.into(),
+            finished_date: entry.finished_date.into(),
+        }
+    }
+}
+
+// The value of a custom field, `name` is the name of its `FieldEntry`
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct RecordField {
+    pub name: String,
+    pub value: String,
+}
+
+impl From<UIRecordField> for RecordField {
+    fn from(entry: UIRecordField) -> Self {
+        RecordField {
+            name: entry.name.into(),
+            value: entry.value.into(),
         }
     }
 }
 
-#[serde_as]
+impl From<RecordField> for UIRecordField {
+    fn from(entry: RecordField) -> Self {
+        UIRecordField {
+            name: entry.name.into(),
+            value: entry.value.into(),
+        }
+    }
+}
+
+// Fields missing in the stored json get their default values, so the rows
+// written by older versions can still be loaded.
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
 pub struct RecordEntry {
     pub uuid: String,
     pub start_date: String,
@@ -43,9 +106,44 @@ pub struct RecordEntry {
     pub title: String,
     pub plan: Vec<RecordPlanEntry>,
     pub tags: Vec<String>,
+    pub state: RecordState,
+
+    // Repeat rule in a RRULE subset, empty if the record does not repeat
+    pub recurrence: String,
+
+    pub priority: RecordPriority,
+
+    // Planned effort, 0 if it is not estimated
+    pub estimate_seconds: i64,
+
+    // Values of the custom fields, in the order of the field definitions
+    pub fields: Vec<RecordField>,
+
+    // Uuids of the records which should be finished before this one starts
+    pub blocked_by: Vec<String>,
+
+    // Uuid of the project, empty if the record has no project
+    pub project: String,
 
-    #[serde_as(as = "RecordState")]
-    pub state: UIRecordState,
+    // `YYYY-MM-DD` the record was finished, empty unless it is finished. The
+    // planned `end_date` is kept when it is finished.
+    pub finished_date: String,
+
+    // Filled from the session table when the record is loaded
+    #[serde(skip)]
+    pub tracked_seconds: i64,
+    #[serde(skip)]
+    pub is_timing: bool,
+
+    // Filled from the change table for the statistic
+    #[serde(skip)]
+    pub deadline_extensions: i64,
+
+    // Filled from the other records, see `core::dependency`
+    #[serde(skip)]
+    pub is_blocked: bool,
+    #[serde(skip)]
+    pub blocking_count: i64,
 }
 
 impl From<UIRecordEntry> for RecordEntry {
@@ -55,7 +153,17 @@ impl From<UIRecordEntry> for RecordEntry {
             start_date: entry.start_date.into(),
             end_date: entry.end_date.into(),
             title: entry.title.into(),
-            state: entry.state,
+            state: entry.state.into(),
+            recurrence: entry.recurrence.into(),
+            priority: entry.priority.into(),
+            estimate_seconds: entry.estimate_seconds as i64,
+            project: entry.project.into(),
+            tracked_seconds: entry.tracked_seconds as i64,
+            is_timing: entry.is_timing,
+            deadline_extensions: entry.deadline_extensions as i64,
+            finished_date: entry.finished_date.into(),
+            is_blocked: entry.is_blocked,
+            blocking_count: entry.blocking_count as i64,
 
             plan: entry
                 .plan
@@ -68,6 +176,18 @@ impl From<UIRecordEntry> for RecordEntry {
                 .iter()
                 .map(|item| item.into())
                 .collect::<Vec<_>>(),
+
+            fields: entry
+                .fields
+                .iter()
+                .map(|item| item.into())
+                .collect::<Vec<_>>(),
+
+            blocked_by: entry
+                .blocked_by
+                .iter()
+                .map(|item| item.into())
+                .collect::<Vec<_>>(),
         }
     }
 }
@@ -79,7 +199,17 @@ impl From<RecordEntry> for UIRecordEntry {
             start_date: entry.start_date.into(),
             end_date: entry.end_date.into(),
             title: entry.title.into(),
-            state: entry.state,
+            state: entry.state.into(),
+            recurrence: entry.recurrence.into(),
+            priority: entry.priority.into(),
+            estimate_seconds: entry.estimate_seconds as i32,
+            project: entry.project.into(),
+            tracked_seconds: entry.tracked_seconds as i32,
+            is_timing: entry.is_timing,
+            deadline_extensions: entry.deadline_extensions as i32,
+            finished_date: entry.finished_date.into(),
+            is_blocked: entry.is_blocked,
+            blocking_count: entry.blocking_count as i32,
 
             plan: ModelRc::new(VecModel::from_slice(
                 &entry
@@ -96,42 +226,343 @@ impl From<RecordEntry> for UIRecordEntry {
                     .map(|item| item.into())
                     .collect::<Vec<_>>(),
             )),
+
+            fields: ModelRc::new(VecModel::from_slice(
+                &entry
+                    .fields
+                    .into_iter()
+                    .map(|item| item.into())
+                    .collect::<Vec<UIRecordField>>(),
+            )),
+
+            blocked_by: ModelRc::new(VecModel::from_slice(
+                &entry
+                    .blocked_by
+                    .into_iter()
+                    .map(|item| item.into())
+                    .collect::<Vec<_>>(),
+            )),
+        }
+    }
+}
+
+// A period of work on a record. `end` is 0 while the timer is running.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct SessionEntry {
+    pub uuid: String,
+    pub record_uuid: String,
+    pub start: i64,
+    pub end: i64,
+    pub note: String,
+}
+
+// A changed field of a record. The values are the text of the field before
+// and after the change, see `core::change`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct ChangeEntry {
+    pub uuid: String,
+    pub record_uuid: String,
+    pub timestamp: i64,
+    pub field: String,
+    pub old_value: String,
+    pub new_value: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RecordState {
+    #[default]
+    NotStarted,
+    Running,
+    Finished,
+    Giveup,
+    Timeout,
+}
+
+impl RecordState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecordState::NotStarted => "NotStarted",
+            RecordState::Running => "Running",
+            RecordState::Finished => "Finished",
+            RecordState::Giveup => "Giveup",
+            RecordState::Timeout => "Timeout",
+        }
+    }
+}
+
+impl From<UIRecordState> for RecordState {
+    fn from(state: UIRecordState) -> Self {
+        match state {
+            UIRecordState::NotStarted => RecordState::NotStarted,
+            UIRecordState::Running => RecordState::Running,
+            UIRecordState::Finished => RecordState::Finished,
+            UIRecordState::Giveup => RecordState::Giveup,
+            UIRecordState::Timeout => RecordState::Timeout,
+        }
+    }
+}
+
+impl From<RecordState> for UIRecordState {
+    fn from(state: RecordState) -> Self {
+        match state {
+            RecordState::NotStarted => UIRecordState::NotStarted,
+            RecordState::Running => UIRecordState::Running,
+            RecordState::Finished => UIRecordState::Finished,
+            RecordState::Giveup => UIRecordState::Giveup,
+            RecordState::Timeout => UIRecordState::Timeout,
+        }
+    }
+}
+
+// Ordered from the lowest to the highest, so the records can be sorted by it
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default,
+)]
+pub enum RecordPriority {
+    #[default]
+    None,
+    Low,
+    Medium,
+    High,
+    Urgent,
+}
+
+impl RecordPriority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecordPriority::None => "None",
+            RecordPriority::Low => "Low",
+            RecordPriority::Medium => "Medium",
+            RecordPriority::High => "High",
+            RecordPriority::Urgent => "Urgent",
         }
     }
 }
 
-struct RecordState;
-impl SerializeAs<UIRecordState> for RecordState {
-    fn serialize_as<S>(source: &UIRecordState, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let status = match source {
-            UIRecordState::NotStarted => "NotStarted",
-            UIRecordState::Running => "Running",
-            UIRecordState::Finished => "Finished",
-            UIRecordState::Giveup => "Giveup",
-            UIRecordState::Timeout => "Timeout",
-        };
-
-        serializer.serialize_str(status)
-    }
-}
-
-impl<'de> DeserializeAs<'de, UIRecordState> for RecordState {
-    fn deserialize_as<D>(deserializer: D) -> Result<UIRecordState, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let status = String::deserialize(deserializer)?;
-        let status = match status.as_str() {
-            "NotStarted" => UIRecordState::NotStarted,
-            "Running" => UIRecordState::Running,
-            "Finished" => UIRecordState::Finished,
-            "Giveup" => UIRecordState::Giveup,
-            "Timeout" => UIRecordState::Timeout,
-            _ => unreachable!(),
-        };
-        Ok(status)
+impl From<UIRecordPriority> for RecordPriority {
+    fn from(priority: UIRecordPriority) -> Self {
+        match priority {
+            UIRecordPriority::None => RecordPriority::None,
+            UIRecordPriority::Low => RecordPriority::Low,
+            UIRecordPriority::Medium => RecordPriority::Medium,
+            UIRecordPriority::High => RecordPriority::High,
+            UIRecordPriority::Urgent => RecordPriority::Urgent,
+        }
+    }
+}
+
+impl From<RecordPriority> for UIRecordPriority {
+    fn from(priority: RecordPriority) -> Self {
+        match priority {
+            RecordPriority::None => UIRecordPriority::None,
+            RecordPriority::Low => UIRecordPriority::Low,
+            RecordPriority::Medium => UIRecordPriority::Medium,
+            RecordPriority::High => UIRecordPriority::High,
+            RecordPriority::Urgent => UIRecordPriority::Urgent,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FieldKind {
+    #[default]
+    Text,
+    Number,
+    Date,
+    Select,
+}
+
+impl FieldKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FieldKind::Text => "Text",
+            FieldKind::Number => "Number",
+            FieldKind::Date => "Date",
+            FieldKind::Select => "Select",
+        }
+    }
+}
+
+impl From<UIFieldKind> for FieldKind {
+    fn from(kind: UIFieldKind) -> Self {
+        match kind {
+            UIFieldKind::Text => FieldKind::Text,
+            UIFieldKind::Number => FieldKind::Number,
+            UIFieldKind::Date => FieldKind::Date,
+            UIFieldKind::Select => FieldKind::Select,
+        }
+    }
+}
+
+impl From<FieldKind> for UIFieldKind {
+    fn from(kind: FieldKind) -> Self {
+        match kind {
+            FieldKind::Text => UIFieldKind::Text,
+            FieldKind::Number => UIFieldKind::Number,
+            FieldKind::Date => UIFieldKind::Date,
+            FieldKind::Select => UIFieldKind::Select,
+        }
+    }
+}
+
+// A custom field defined for the database. `options` are the values a select
+// field can take, see `core::field`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct FieldEntry {
+    pub uuid: String,
+    pub name: String,
+    pub kind: FieldKind,
+    pub options: Vec<String>,
+}
+
+impl From<UIFieldEntry> for FieldEntry {
+    fn from(entry: UIFieldEntry) -> Self {
+        FieldEntry {
+            uuid: entry.uuid.into(),
+            name: entry.name.into(),
+            kind: entry.kind.into(),
+            options: entry.options.iter().map(|item| item.into()).collect(),
+        }
+    }
+}
+
+impl From<FieldEntry> for UIFieldEntry {
+    fn from(entry: FieldEntry) -> Self {
+        UIFieldEntry {
+            uuid: entry.uuid.into(),
+            name: entry.name.into(),
+            kind: entry.kind.into(),
+            options: ModelRc::new(VecModel::from_slice(
+                &entry
+                    .options
+                    .into_iter()
+                    .map(|item| item.into())
+                    .collect::<Vec<_>>(),
+            )),
+        }
+    }
+}
+
+// `color` is a `#rrggbb` value, see `core::project`
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct ProjectEntry {
+    pub uuid: String,
+    pub name: String,
+    pub color: String,
+    pub description: String,
+    pub is_archived: bool,
+}
+
+impl From<UIProjectEntry> for ProjectEntry {
+    fn from(entry: UIProjectEntry) -> Self {
+        ProjectEntry {
+            uuid: entry.uuid.into(),
+            name: entry.name.into(),
+            color: project::color_hex([entry.color.red(), entry.color.green(), entry.color.blue()]),
+            description: entry.description.into(),
+            is_archived: entry.is_archived,
+        }
+    }
+}
+
+impl From<ProjectEntry> for UIProjectEntry {
+    fn from(entry: ProjectEntry) -> Self {
+        let [red, green, blue] = project::parse_color(&entry.color)
+            .or_else(|| project::parse_color(project::COLORS[0].1))
+            .unwrap_or_default();
+
+        UIProjectEntry {
+            uuid: entry.uuid.into(),
+            name: entry.name.into(),
+            color: Color::from_rgb_u8(red, green, blue),
+            description: entry.description.into(),
+            is_archived: entry.is_archived,
+        }
+    }
+}
+
+// A row of the record or archive table which can not be deserialized. It is
+// kept in the corrupt table until it is repaired or removed.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct CorruptEntry {
+    pub id: String,
+    pub table: String,
+    pub uuid: String,
+    pub data: String,
+    pub error: String,
+    pub created_at: String,
+}
+
+impl From<CorruptEntry> for UICorruptEntry {
+    fn from(entry: CorruptEntry) -> Self {
+        UICorruptEntry {
+            id: entry.id.into(),
+            table: entry.table.into(),
+            uuid: entry.uuid.into(),
+            data: entry.data.into(),
+            error: entry.error.into(),
+            created_at: entry.created_at.into(),
+        }
+    }
+}
+
+impl From<UICorruptEntry> for CorruptEntry {
+    fn from(entry: UICorruptEntry) -> Self {
+        CorruptEntry {
+            id: entry.id.into(),
+            table: entry.table.into(),
+            uuid: entry.uuid.into(),
+            data: entry.data.into(),
+            error: entry.error.into(),
+            created_at: entry.created_at.into(),
+        }
+    }
+}
+
+// A removed record or archive. The row keeps the uuid of the entry and is
+// moved back to `table` when it is restored.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct TrashEntry {
+    pub table: String,
+    pub deleted_at: i64,
+    pub entry: RecordEntry,
+}
+
+impl From<TrashEntry> for UITrashEntry {
+    fn from(entry: TrashEntry) -> Self {
+        UITrashEntry {
+            table: entry.table.into(),
+            deleted_at: cutil::time::timestamp_to_local(entry.deleted_at, "%Y-%m-%d %H:%M").into(),
+            entry: entry.entry.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_entry_deserialize() {
+        let entry = serde_json::from_str::<RecordEntry>(
+            r#"{"uuid": "uuid-1", "title": "hello", "plan": [{"detail": "step"}]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(entry.title, "hello");
+        assert_eq!(entry.state, RecordState::NotStarted);
+        assert!(!entry.plan[0].is_finished);
+        assert!(entry.tags.is_empty());
+        assert_eq!(entry.priority, RecordPriority::None);
+        assert!(entry.fields.is_empty());
+
+        assert!(serde_json::from_str::<RecordEntry>(r#"{"state": "Unknown"}"#).is_err());
+        assert!(serde_json::from_str::<RecordEntry>(r#"[1, 2]"#).is_err());
     }
 }
@@ -0,0 +1,116 @@
+use super::def::{
+    ARCHIVE_TABLE, CHANGE_TABLE, CORRUPT_TABLE, FIELD_TABLE, PROJECT_TABLE, RECORD_TABLE,
+    SESSION_TABLE, TRASH_TABLE,
+};
+use sqldb::{entry, fts, migration::Migration};
+
+// Columns derived from the json `data` of the record and archive tables. They
+// are generated by sqlite, so the existing rows get them without being
+// rewritten and they never get out of sync with `data`.
+const RECORD_COLUMNS: [(&str, &str); 5] = [
+    ("state", "$.state"),
+    ("start_date", "$.start_date"),
+    ("end_date", "$.end_date"),
+    ("title", "$.title"),
+    ("tags", "$.tags"),
+];
+
+// The full text search columns of the record and archive tables. Only the
+// plan items and tags which have the expected json types are indexed.
+pub const SEARCH_COLUMNS: [(&str, &str); 3] = [
+    ("title", "json_extract({data}, '$.title')"),
+    (
+        "plan",
+        "(SELECT group_concat(json_extract(value, '$.detail'), char(10))
+          FROM json_each({data}, '$.plan') WHERE type = 'object')",
+    ),
+    (
+        "tags",
+        "(SELECT group_concat(value, char(10))
+          FROM json_each({data}, '$.tags') WHERE type = 'text')",
+    ),
+];
+
+// Append new migrations to the end and never change the released ones
+pub fn migrations() -> Vec<Migration> {
+    vec![
+        Migration::new(
+            1,
+            "create record and archive tables",
+            vec![
+                entry::create_table_sql(RECORD_TABLE),
+                entry::create_table_sql(ARCHIVE_TABLE),
+            ],
+        ),
+        Migration::new(
+            2,
+            "add typed record columns and indexes",
+            [RECORD_TABLE, ARCHIVE_TABLE]
+                .into_iter()
+                .flat_map(record_columns_sqls)
+                .collect(),
+        ),
+        Migration::new(
+            3,
+            "create corrupt table",
+            vec![entry::create_table_sql(CORRUPT_TABLE)],
+        ),
+        Migration::new(
+            4,
+            "add full text search of records",
+            [RECORD_TABLE, ARCHIVE_TABLE]
+                .into_iter()
+                .flat_map(|table| fts::create_sqls(table, &SEARCH_COLUMNS))
+                .collect(),
+        ),
+        Migration::new(
+            5,
+            "create session table",
+            [entry::create_table_sql(SESSION_TABLE)]
+                .into_iter()
+                .chain(column_sqls(SESSION_TABLE, "record_uuid", "$.record_uuid"))
+                .collect(),
+        ),
+        Migration::new(
+            6,
+            "create trash table",
+            vec![entry::create_table_sql(TRASH_TABLE)],
+        ),
+        Migration::new(
+            7,
+            "create change table",
+            [entry::create_table_sql(CHANGE_TABLE)]
+                .into_iter()
+                .chain(column_sqls(CHANGE_TABLE, "record_uuid", "$.record_uuid"))
+                .collect(),
+        ),
+        Migration::new(
+            8,
+            "create field table",
+            vec![entry::create_table_sql(FIELD_TABLE)],
+        ),
+        Migration::new(
+            9,
+            "create project table",
+            vec![entry::create_table_sql(PROJECT_TABLE)],
+        ),
+    ]
+}
+
+fn record_columns_sqls(table: &str) -> Vec<String> {
+    RECORD_COLUMNS
+        .iter()
+        .flat_map(|(column, path)| column_sqls(table, column, path))
+        .collect()
+}
+
+// An indexed column generated from the json `data`
+fn column_sqls(table: &str, column: &str, path: &str) -> [String; 2] {
+    [
+        format!(
+            "ALTER TABLE {table} ADD COLUMN {column} TEXT GENERATED ALWAYS AS
+             (CASE WHEN json_valid(data) THEN json_extract(data, '{path}') END) VIRTUAL"
+        ),
+        format!("CREATE INDEX IF NOT EXISTS {table}_{column}_idx ON {table} ({column})"),
+    ]
+}
@@ -1,16 +1,60 @@
 pub mod def;
 
-#[allow(unused)]
-pub use sqldb::{create_db, entry};
+#[cfg(feature = "database")]
+pub mod migration;
 
-pub async fn init(db_path: &str) {
-    create_db(db_path).await.expect("create db");
+#[cfg(all(feature = "web", target_arch = "wasm32"))]
+mod web;
+
+use once_cell::sync::OnceCell;
+use std::sync::Arc;
+
+pub use sqldb::{Storage, WriteOp};
+
+#[cfg(all(feature = "web", target_arch = "wasm32"))]
+pub use web::{load_setting, save_setting};
+
+static STORAGE: OnceCell<Arc<dyn Storage>> = OnceCell::new();
 
-    entry::new(def::RECORD_TABLE)
+#[cfg(feature = "database")]
+static SQLITE: OnceCell<sqldb::SqliteStorage> = OnceCell::new();
+
+// The storage opened by `init`
+pub fn storage() -> Arc<dyn Storage> {
+    STORAGE.get().expect("the storage is not set").clone()
+}
+
+// The same database as `storage`, for the settings only sqlite has
+#[cfg(feature = "database")]
+pub fn sqlite() -> &'static sqldb::SqliteStorage {
+    SQLITE.get().expect("the database is not opened")
+}
+
+// Only the first storage is kept
+fn set_storage(storage: Arc<dyn Storage>) {
+    if STORAGE.set(storage).is_err() {
+        log::warn!("the storage is already set");
+    }
+}
+
+#[cfg(feature = "database")]
+pub async fn init(db_path: &str) {
+    let storage = sqldb::SqliteStorage::open(db_path)
         .await
-        .expect("record table failed");
+        .expect("create db");
 
-    entry::new(def::ARCHIVE_TABLE)
+    let version = sqldb::migration::run(&storage, &migration::migrations())
         .await
-        .expect("archive table failed");
+        .expect("migrate db failed");
+
+    log::debug!("db schema version: {version}");
+
+    _ = SQLITE.set(storage.clone());
+    set_storage(Arc::new(storage));
+}
+
+// The browser has no database file, the tables live in the local storage
+#[cfg(all(feature = "web", target_arch = "wasm32"))]
+pub fn init(app_name: &str) {
+    set_storage(Arc::new(web::LocalStorage::new(app_name)));
 }
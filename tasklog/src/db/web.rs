@@ -0,0 +1,225 @@
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+use sqldb::{ComEntry, Storage, WriteOp};
+use std::collections::HashMap;
+
+// The browser `localStorage`. Each table is kept as a json array of its rows
+// under the `<prefix>.<table>` key, so the rows keep their insertion order.
+#[derive(Debug, Clone)]
+pub struct LocalStorage {
+    prefix: String,
+}
+
+impl LocalStorage {
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+        }
+    }
+
+    fn key(&self, table: &str) -> String {
+        format!("{}.{table}", self.prefix)
+    }
+
+    fn load(&self, table: &str) -> Result<Vec<ComEntry>> {
+        match self.load_text(table)? {
+            Some(text) => Ok(serde_json::from_str(&text)?),
+            None => Ok(vec![]),
+        }
+    }
+
+    fn load_text(&self, table: &str) -> Result<Option<String>> {
+        local_storage()?
+            .get_item(&self.key(table))
+            .map_err(|e| anyhow!("read local storage failed. {e:?}"))
+    }
+
+    // The rows of `table` in `tables`, which are loaded on the first use
+    fn loaded<'a>(
+        &self,
+        tables: &'a mut HashMap<String, Vec<ComEntry>>,
+        table: &str,
+    ) -> Result<&'a mut Vec<ComEntry>> {
+        if !tables.contains_key(table) {
+            tables.insert(table.to_string(), self.load(table)?);
+        }
+
+        Ok(tables.get_mut(table).unwrap())
+    }
+
+    fn save(&self, table: &str, items: &[ComEntry]) -> Result<()> {
+        self.save_text(table, &serde_json::to_string(items)?)
+    }
+
+    fn save_text(&self, table: &str, text: &str) -> Result<()> {
+        local_storage()?
+            .set_item(&self.key(table), text)
+            .map_err(|e| anyhow!("write local storage failed. {e:?}"))
+    }
+
+    // Writes back the tables as they were before a failed transaction. They
+    // are removed first, so the old rows fit in the quota again.
+    fn restore(&self, tables: &[(&String, Option<String>)]) {
+        let Ok(storage) = local_storage() else {
+            return;
+        };
+
+        for (table, _) in tables.iter() {
+            if let Err(e) = storage.remove_item(&self.key(table)) {
+                log::warn!("remove {table} failed: {e:?}");
+            }
+        }
+
+        for (table, text) in tables.iter() {
+            if let Some(text) = text
+                && let Err(e) = self.save_text(table, text)
+            {
+                log::warn!("restore {table} failed: {e:?}");
+            }
+        }
+    }
+}
+
+// A setting of the page, which has no config file, as json under the
+// `setting.<name>` key
+pub fn load_setting(name: &str) -> Result<Option<String>> {
+    local_storage()?
+        .get_item(&format!("setting.{name}"))
+        .map_err(|e| anyhow!("read local storage failed. {e:?}"))
+}
+
+pub fn save_setting(name: &str, text: &str) -> Result<()> {
+    local_storage()?
+        .set_item(&format!("setting.{name}"), text)
+        .map_err(|e| anyhow!("write local storage failed. {e:?}"))
+}
+
+fn local_storage() -> Result<web_sys::Storage> {
+    web_sys::window()
+        .ok_or(anyhow!("no window"))?
+        .local_storage()
+        .map_err(|e| anyhow!("open local storage failed. {e:?}"))?
+        .ok_or(anyhow!("local storage is not available"))
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    async fn insert(&self, table: &str, uuid: &str, data: &str) -> Result<()> {
+        let mut items = self.load(table)?;
+        push(&mut items, table, uuid.to_string(), data.to_string())?;
+        self.save(table, &items)
+    }
+
+    async fn update(&self, table: &str, uuid: &str, data: &str) -> Result<()> {
+        let mut items = self.load(table)?;
+        if let Some(item) = items.iter_mut().find(|item| item.uuid == uuid) {
+            item.data = data.to_string();
+            self.save(table, &items)?;
+        }
+
+        Ok(())
+    }
+
+    async fn delete(&self, table: &str, uuid: &str) -> Result<()> {
+        let mut items = self.load(table)?;
+        items.retain(|item| item.uuid != uuid);
+        self.save(table, &items)
+    }
+
+    async fn delete_all(&self, table: &str) -> Result<()> {
+        self.save(table, &[])
+    }
+
+    async fn select(&self, table: &str, uuid: &str) -> Result<ComEntry> {
+        self.load(table)?
+            .into_iter()
+            .find(|item| item.uuid == uuid)
+            .ok_or(anyhow!("`{uuid}` does not exist in {table}"))
+    }
+
+    async fn select_all(&self, table: &str) -> Result<Vec<ComEntry>> {
+        self.load(table)
+    }
+
+    async fn select_where(
+        &self,
+        table: &str,
+        _condition: &str,
+        _binds: &[String],
+    ) -> Result<Vec<ComEntry>> {
+        self.load(table)
+    }
+
+    async fn row_counts(&self, table: &str) -> Result<i64> {
+        Ok(self.load(table)?.len() as i64)
+    }
+
+    async fn is_exist(&self, table: &str, uuid: &str) -> Result<()> {
+        self.select(table, uuid).await.map(|_| ())
+    }
+
+    // All the ops are applied to the loaded tables before any of them is
+    // saved, so a failed op leaves the storage as it was. A table is saved
+    // under its own key, when a later save fails, e.g. the quota is exceeded,
+    // the tables saved before it are restored.
+    async fn transaction(&self, ops: Vec<WriteOp>) -> Result<()> {
+        let mut tables = HashMap::new();
+
+        for op in ops {
+            match op {
+                WriteOp::Insert { table, uuid, data } => {
+                    push(self.loaded(&mut tables, &table)?, &table, uuid, data)?
+                }
+                WriteOp::Update { table, uuid, data } => {
+                    if let Some(item) = self
+                        .loaded(&mut tables, &table)?
+                        .iter_mut()
+                        .find(|item| item.uuid == uuid)
+                    {
+                        item.data = data;
+                    }
+                }
+                WriteOp::Delete { table, uuid } => self
+                    .loaded(&mut tables, &table)?
+                    .retain(|item| item.uuid != uuid),
+                WriteOp::Move {
+                    from,
+                    to,
+                    uuid,
+                    data,
+                } => {
+                    let items = self.loaded(&mut tables, &from)?;
+                    let Some(index) = items.iter().position(|item| item.uuid == uuid) else {
+                        bail!("`{uuid}` does not exist in {from}");
+                    };
+
+                    items.remove(index);
+                    push(self.loaded(&mut tables, &to)?, &to, uuid, data)?;
+                }
+            }
+        }
+
+        let mut olds = vec![];
+        for table in tables.keys() {
+            olds.push((table, self.load_text(table)?));
+        }
+
+        for (index, (table, _)) in olds.iter().enumerate() {
+            if let Err(e) = self.save(table, &tables[*table]) {
+                self.restore(&olds[..index]);
+                return Err(e);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn push(items: &mut Vec<ComEntry>, table: &str, uuid: String, data: String) -> Result<()> {
+    if items.iter().any(|item| item.uuid == uuid) {
+        bail!("`{uuid}` already exists in {table}");
+    }
+
+    items.push(ComEntry { uuid, data });
+    Ok(())
+}
@@ -25,9 +25,18 @@ mod config;
 ))]
 mod version;
 
-#[cfg(feature = "database")]
+#[cfg(any(feature = "database", feature = "web"))]
 mod db;
 
+#[cfg(any(feature = "database", feature = "web"))]
+mod core;
+
+#[cfg(all(
+    feature = "database",
+    any(target_os = "windows", target_os = "linux", target_os = "macos")
+))]
+mod command;
+
 mod logic;
 
 #[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
@@ -87,7 +96,7 @@ async fn ui_before() {
     config::init();
 
     #[cfg(feature = "database")]
-    db::init(config::db_path().to_str().expect("invalid db path")).await;
+    db_init().await;
 
     #[cfg(target_os = "linux")]
     {
@@ -95,9 +104,41 @@ async fn ui_before() {
     }
 }
 
+// The entries which can not be loaded are quarantined, the user is told about
+// them by the corrupt panel and the command line
+#[cfg(all(
+    feature = "database",
+    any(
+        target_os = "windows",
+        target_os = "linux",
+        target_os = "macos",
+        target_os = "android"
+    )
+))]
+async fn db_init() {
+    db::init(config::db_path().to_str().expect("invalid db path")).await;
+    core::security::init();
+
+    let retention_days = config::preference().trash_retention_days;
+    match core::TaskStore::new().purge_trash(retention_days).await {
+        Ok(counts) if counts > 0 => log::info!("purge {counts} trash entries"),
+        Err(e) => log::warn!("purge trash failed: {e:?}"),
+        _ => (),
+    }
+
+    match core::TaskStore::new().check().await {
+        Ok(counts) if counts > 0 => log::warn!("quarantine {counts} corrupt entries"),
+        Err(e) => log::warn!("check db failed: {e:?}"),
+        _ => (),
+    }
+}
+
 #[cfg(target_arch = "wasm32")]
 fn ui_before() {
     init_logger();
+
+    #[cfg(feature = "web")]
+    db::init("tasklog");
 }
 
 fn ui_after(ui: &AppWindow) {
@@ -138,6 +179,37 @@ pub async fn desktop_main() {
     log::debug!("exit...");
 }
 
+#[cfg(all(
+    feature = "database",
+    any(target_os = "windows", target_os = "linux", target_os = "macos")
+))]
+pub async fn cli_main() {
+    init_logger();
+    config::init();
+
+    if let Ok(password) = std::env::var("TASKLOG_PASSWORD")
+        && let Err(e) = config::unlock(&password)
+    {
+        eprintln!("Error: unlock failed. {e:#}");
+        std::process::exit(1);
+    }
+
+    db_init().await;
+    if let Ok(entries) = core::TaskStore::new().corrupt_entries().await
+        && !entries.is_empty()
+    {
+        eprintln!(
+            "Warning: {} corrupt entries are in the quarantine, see `tasklog-cli corrupt ls`",
+            entries.len()
+        );
+    }
+
+    if let Err(e) = command::run().await {
+        eprintln!("Error: {e:#}");
+        std::process::exit(1);
+    }
+}
+
 #[cfg(target_arch = "wasm32")]
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen::prelude::wasm_bindgen(start))]
 pub fn main() {
@@ -1,11 +1,9 @@
-use super::{toast, tr::tr};
+use super::{history, record::refresh_dependencies, spawn, toast, tr::tr};
 use crate::{
-    db::{
-        self,
-        def::{RecordEntry, ARCHIVE_TABLE as DB_TABLE},
-    },
+    core::{TaskStore, record, search::Query},
+    db::def::RecordEntry,
     slint_generatedAppWindow::{AppWindow, Logic, RecordEntry as UIRecordEntry, Store},
-    toast_success,
+    store_current_record_entries, toast_success, toast_warn,
 };
 use slint::{ComponentHandle, Model, VecModel};
 
@@ -23,11 +21,11 @@ macro_rules! store_current_archive_entries {
 pub fn init(ui: &AppWindow) {
     archive_init(ui);
 
+    // The entry has been persisted by the archive operation already
     let ui_handle = ui.as_weak();
     ui.global::<Logic>().on_add_archive(move |entry| {
         let ui = ui_handle.unwrap();
-        store_current_archive_entries!(ui).push(entry.clone());
-        add_db_entry(&ui, entry.into());
+        store_current_archive_entries!(ui).push(entry);
     });
 
     let ui_handle = ui.as_weak();
@@ -35,11 +33,17 @@ pub fn init(ui: &AppWindow) {
         let ui = ui_handle.unwrap();
         let index = index as usize;
 
-        let entry = store_current_archive_entries!(ui).row_data(index).unwrap();
-        store_current_archive_entries!(ui).remove(index);
-        delete_db_entry(&ui, entry.uuid.clone().into());
+        let origin: RecordEntry = store_current_archive_entries!(ui)
+            .row_data(index)
+            .unwrap()
+            .into();
+        let mut entry = origin.clone();
+        record::refresh_state(&mut entry, cutil::time::timestamp());
 
-        ui.global::<Logic>().invoke_new_record(entry);
+        store_current_archive_entries!(ui).remove(index);
+        store_current_record_entries!(ui).insert(0, entry.clone().into());
+        refresh_dependencies(&ui);
+        recover_db_entry(&ui, entry, origin, index);
         toast_success!(ui, tr("Recover entry successfully"));
     });
 
@@ -51,22 +55,41 @@ pub fn init(ui: &AppWindow) {
         let entry = store_current_archive_entries!(ui).row_data(index).unwrap();
         store_current_archive_entries!(ui).remove(index);
         delete_db_entry(&ui, entry.uuid.into());
-        toast_success!(ui, tr("Remove entry successfully"));
+        history::toast_undo(&ui, tr("Remove entry successfully"));
+    });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_search_archive(move |keyword| {
+        let ui = ui_handle.unwrap();
+        ui.global::<Store>().set_archive_search_keyword(keyword);
+        load_archive_entries(&ui);
     });
 }
 
-fn archive_init(ui: &AppWindow) {
+pub fn archive_init(ui: &AppWindow) {
     store_current_archive_entries!(ui).set_vec(vec![]);
+    load_archive_entries(ui);
+}
 
-    let ui = ui.as_weak();
-    tokio::spawn(async move {
-        let entries = match db::entry::select_all(DB_TABLE).await {
-            Ok(items) => items
-                .into_iter()
-                .filter_map(|item| serde_json::from_str::<RecordEntry>(&item.data).ok())
-                .map(|item| item.into())
-                .collect(),
+// The archives found by the full text search of the keyword, an empty keyword
+// matches all of them
+fn load_archive_entries(ui: &AppWindow) {
+    let keyword = ui.global::<Store>().get_archive_search_keyword();
 
+    // Nothing matches a query with an invalid state or date
+    let query = match Query::parse(&keyword) {
+        Ok(query) => query,
+        Err(e) => {
+            log::debug!("{e:?}");
+            store_current_archive_entries!(ui).set_vec(vec![]);
+            return;
+        }
+    };
+
+    let ui = ui.as_weak();
+    spawn(async move {
+        let entries = match TaskStore::new().search_archives(&query).await {
+            Ok(entries) => entries,
             Err(e) => {
                 log::warn!("{:?}", e);
                 vec![]
@@ -74,39 +97,71 @@ fn archive_init(ui: &AppWindow) {
         };
 
         _ = slint::invoke_from_event_loop(move || {
+            let ui = ui.unwrap();
+
+            // A newer keyword is searched already
+            if ui.global::<Store>().get_archive_search_keyword() != keyword {
+                return;
+            }
+
             let entries = entries
                 .into_iter()
                 .map(|entry: RecordEntry| entry.into())
                 .collect::<Vec<UIRecordEntry>>();
 
-            store_current_archive_entries!(ui.unwrap()).set_vec(entries);
+            store_current_archive_entries!(ui).set_vec(entries);
         });
     });
 }
 
-fn add_db_entry(ui: &AppWindow, entry: RecordEntry) {
+// The entry is put back to `index` of the archive list if the recover fails
+fn recover_db_entry(ui: &AppWindow, entry: RecordEntry, origin: RecordEntry, index: usize) {
     let ui = ui.as_weak();
-    tokio::spawn(async move {
-        let data = serde_json::to_string(&entry).unwrap();
-        match db::entry::insert(DB_TABLE, &entry.uuid, &data).await {
-            Err(e) => toast::async_toast_warn(
-                ui,
-                format!("{}. {}: {e:?}", tr("Add entry failed"), tr("Reason")),
-            ),
-            _ => (),
+    spawn(async move {
+        let store = TaskStore::new();
+        let uuids = vec![entry.uuid.clone()];
+        if let Err(e) = history::track(tr("Recover entry"), uuids, store.recover(entry)).await {
+            _ = slint::invoke_from_event_loop(move || {
+                let ui = ui.unwrap();
+                rollback_recover(&ui, origin, index);
+                toast_warn!(
+                    ui,
+                    format!("{}. {}: {e:?}", tr("Recover entry failed"), tr("Reason"))
+                );
+            });
         }
     });
 }
 
-pub fn delete_db_entry(ui: &AppWindow, uuid: String) {
+fn rollback_recover(ui: &AppWindow, origin: RecordEntry, index: usize) {
+    if let Some(index) = store_current_record_entries!(ui)
+        .iter()
+        .position(|item| item.uuid == origin.uuid)
+    {
+        store_current_record_entries!(ui).remove(index);
+        refresh_dependencies(ui);
+    }
+
+    let index = index.min(store_current_archive_entries!(ui).row_count());
+    store_current_archive_entries!(ui).insert(index, origin.into());
+}
+
+fn delete_db_entry(ui: &AppWindow, uuid: String) {
     let ui = ui.as_weak();
-    tokio::spawn(async move {
-        match db::entry::delete(DB_TABLE, uuid.as_str()).await {
-            Err(e) => toast::async_toast_warn(
+    spawn(async move {
+        let store = TaskStore::new();
+        let uuids = vec![uuid.clone()];
+        if let Err(e) = history::track(
+            tr("Remove entry"),
+            uuids,
+            store.remove_archive(&uuid, vec![]),
+        )
+        .await
+        {
+            toast::async_toast_warn(
                 ui,
                 format!("{}. {}: {e:?}", tr("Remove entry failed"), tr("Reason")),
-            ),
-            _ => (),
+            )
         }
     });
 }
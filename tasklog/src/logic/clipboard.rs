@@ -3,7 +3,7 @@ use crate::{
     slint_generatedAppWindow::{AppWindow, Logic},
     toast_success, toast_warn,
 };
-use anyhow::{bail, Result};
+use anyhow::{Result, bail};
 use slint::ComponentHandle;
 
 #[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
@@ -31,10 +31,10 @@ fn copy_to_clipboard(msg: &str) -> Result<()> {
 fn paste_from_clipboard() -> Result<String> {
     #[cfg(target_os = "linux")]
     {
-        if super::util::is_wayland() {
-            if let Ok(text) = paste_from_wayland_clipboard() {
-                return Ok(text);
-            }
+        if super::util::is_wayland()
+            && let Ok(text) = paste_from_wayland_clipboard()
+        {
+            return Ok(text);
         }
     }
 
@@ -111,6 +111,8 @@ pub fn init(ui: &AppWindow) {
 mod tests {
     use super::*;
 
+    #[test]
+    #[ignore = "needs the clipboard of a desktop session"]
     fn test_clipboard() -> Result<()> {
         let msg = "hello world";
         copy_to_clipboard(msg)?;
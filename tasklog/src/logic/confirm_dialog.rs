@@ -19,10 +19,21 @@ pub fn init(ui: &AppWindow) {
                     let current_index = user_data.parse::<i32>().unwrap();
                     ui.global::<Logic>().invoke_remove_record(current_index);
                 }
+                "archive-record" => {
+                    let current_index = user_data.parse::<i32>().unwrap();
+                    ui.global::<Logic>().invoke_archive_record(current_index);
+                }
                 "remove-archive" => {
                     let current_index = user_data.parse::<i32>().unwrap();
                     ui.global::<Logic>().invoke_remove_archive(current_index);
                 }
+                "remove-trash" => {
+                    let current_index = user_data.parse::<i32>().unwrap();
+                    ui.global::<Logic>().invoke_remove_trash(current_index);
+                }
+                "remove-all-trash" => {
+                    ui.global::<Logic>().invoke_remove_all_trash();
+                }
                 "ai-generate-record-plans" => {
                     ui.global::<Logic>().invoke_ai_generate_record_plans();
                 }
@@ -34,6 +45,26 @@ pub fn init(ui: &AppWindow) {
                 "remove-all-record-plans" => {
                     ui.global::<Logic>().invoke_remove_all_record_plans();
                 }
+                "remove-corrupt-entry" => {
+                    let current_index = user_data.parse::<i32>().unwrap();
+                    ui.global::<Logic>()
+                        .invoke_remove_corrupt_entry(current_index);
+                }
+                "remove-all-corrupt-entries" => {
+                    ui.global::<Logic>().invoke_remove_all_corrupt_entries();
+                }
+                "remove-field" => {
+                    let current_index = user_data.parse::<i32>().unwrap();
+                    ui.global::<Logic>().invoke_remove_field(current_index);
+                }
+                "archive-project" => {
+                    let current_index = user_data.parse::<i32>().unwrap();
+                    ui.global::<Logic>().invoke_archive_project(current_index);
+                }
+                "remove-project" => {
+                    let current_index = user_data.parse::<i32>().unwrap();
+                    ui.global::<Logic>().invoke_remove_project(current_index);
+                }
                 _ => (),
             }
         });
@@ -0,0 +1,184 @@
+use super::{archive, record, toast, tr::tr};
+use crate::{
+    config,
+    core::TaskStore,
+    db::def::CorruptEntry,
+    slint_generatedAppWindow::{AppWindow, CorruptEntry as UICorruptEntry, Logic, Store},
+    toast_success, toast_warn,
+};
+use slint::{ComponentHandle, Model, VecModel};
+
+#[macro_export]
+macro_rules! store_corrupt_entries {
+    ($ui:expr) => {
+        $ui.global::<Store>()
+            .get_corrupt_entries()
+            .as_any()
+            .downcast_ref::<VecModel<UICorruptEntry>>()
+            .expect("We know we set a VecModel<UICorruptEntry> earlier")
+    };
+}
+
+pub fn init(ui: &AppWindow) {
+    corrupt_init(ui, true);
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_corrupt_init(move || {
+        let ui = ui_handle.unwrap();
+        corrupt_init(&ui, false);
+    });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_retry_corrupt_entries(move || {
+        let ui = ui_handle.unwrap().as_weak();
+
+        tokio::spawn(async move {
+            match TaskStore::new().retry_corrupt().await {
+                Ok(counts) => {
+                    _ = slint::invoke_from_event_loop(move || {
+                        let ui = ui.unwrap();
+                        reload(&ui);
+                        toast_success!(ui, format!("{}: {counts}", tr("Restored entries")));
+                    });
+                }
+                Err(e) => toast::async_toast_warn(
+                    ui,
+                    format!("{}. {}: {e:?}", tr("Restore entries failed"), tr("Reason")),
+                ),
+            }
+        });
+    });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>()
+        .on_repair_corrupt_entry(move |index, data| {
+            let ui = ui_handle.unwrap();
+            let entry: CorruptEntry = store_corrupt_entries!(ui)
+                .row_data(index as usize)
+                .unwrap()
+                .into();
+
+            let ui = ui.as_weak();
+            tokio::spawn(async move {
+                match TaskStore::new().repair_corrupt(&entry, &data).await {
+                    Ok(_) => {
+                        _ = slint::invoke_from_event_loop(move || {
+                            let ui = ui.unwrap();
+                            reload(&ui);
+                            toast_success!(ui, tr("Repair entry successfully"));
+                        });
+                    }
+                    Err(e) => toast::async_toast_warn(
+                        ui,
+                        format!("{}. {}: {e}", tr("Repair entry failed"), tr("Reason")),
+                    ),
+                }
+            });
+        });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_remove_corrupt_entry(move |index| {
+        let ui = ui_handle.unwrap();
+        let index = index as usize;
+
+        let entry = store_corrupt_entries!(ui).row_data(index).unwrap();
+        store_corrupt_entries!(ui).remove(index);
+
+        let ui = ui.as_weak();
+        tokio::spawn(async move {
+            if let Err(e) = TaskStore::new().remove_corrupt(&entry.id).await {
+                toast::async_toast_warn(
+                    ui,
+                    format!("{}. {}: {e:?}", tr("Remove entry failed"), tr("Reason")),
+                )
+            }
+        });
+    });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_remove_all_corrupt_entries(move || {
+        let ui = ui_handle.unwrap();
+        store_corrupt_entries!(ui).set_vec(vec![]);
+
+        let ui = ui.as_weak();
+        tokio::spawn(async move {
+            if let Err(e) = TaskStore::new().remove_all_corrupt().await {
+                toast::async_toast_warn(
+                    ui,
+                    format!("{}. {}: {e:?}", tr("Remove entry failed"), tr("Reason")),
+                )
+            }
+        });
+    });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_export_corrupt_entries(move || {
+        let ui = ui_handle.unwrap();
+        let entries = store_corrupt_entries!(ui)
+            .iter()
+            .map(|entry| entry.into())
+            .collect::<Vec<CorruptEntry>>();
+
+        let path = config::db_path().with_file_name(format!(
+            "corrupt-{}.json",
+            cutil::time::local_now("%Y%m%d%H%M%S")
+        ));
+
+        let result = serde_json::to_string_pretty(&entries)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| Ok(std::fs::write(&path, data)?));
+
+        match result {
+            Ok(_) => toast_success!(
+                ui,
+                format!("{}: {}", tr("Export successfully"), path.display())
+            ),
+            Err(e) => toast_warn!(
+                ui,
+                format!("{}. {}: {e:?}", tr("Export failed"), tr("Reason"))
+            ),
+        }
+    });
+}
+
+fn corrupt_init(ui: &AppWindow, is_startup: bool) {
+    let ui = ui.as_weak();
+    tokio::spawn(async move {
+        let entries = match TaskStore::new().corrupt_entries().await {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::warn!("{:?}", e);
+                vec![]
+            }
+        };
+
+        _ = slint::invoke_from_event_loop(move || {
+            let ui = ui.unwrap();
+            let counts = entries.len();
+
+            let entries = entries
+                .into_iter()
+                .map(|entry| entry.into())
+                .collect::<Vec<UICorruptEntry>>();
+            store_corrupt_entries!(ui).set_vec(entries);
+
+            if is_startup && counts > 0 {
+                toast_warn!(
+                    ui,
+                    format!(
+                        "{}: {counts}. {}",
+                        tr("Corrupted entries"),
+                        tr("Please check them in the data setting")
+                    )
+                );
+            }
+        });
+    });
+}
+
+// The repaired entries are moved back to the record and archive tables
+fn reload(ui: &AppWindow) {
+    corrupt_init(ui, false);
+    record::record_init(ui);
+    archive::archive_init(ui);
+}
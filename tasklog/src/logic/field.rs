@@ -0,0 +1,216 @@
+use super::{record, spawn, toast, tr::tr};
+use crate::{
+    core::{TaskStore, field, sort::SortKey},
+    db::def::{FieldEntry, FieldKind, RecordEntry},
+    slint_generatedAppWindow::{AppWindow, FieldEntry as UIFieldEntry, Logic, Store},
+    toast_success, toast_warn,
+};
+use slint::{ComponentHandle, Model, ModelRc, SharedString, VecModel};
+
+#[macro_export]
+macro_rules! store_field_entries {
+    ($ui:expr) => {
+        $ui.global::<Store>()
+            .get_field_entries()
+            .as_any()
+            .downcast_ref::<VecModel<UIFieldEntry>>()
+            .expect("We know we set a VecModel<UIFieldEntry> earlier")
+    };
+}
+
+// The built-in sort keys come first in the sort options of the record list
+const SORT_KEYS: [(&str, &str); 6] = [
+    ("newest", "Newest"),
+    ("priority", "Priority"),
+    ("end_date", "End date"),
+    ("start_date", "Start date"),
+    ("title", "Task name"),
+    ("estimate", "Estimate"),
+];
+
+const FIELD_KINDS: [FieldKind; 4] = [
+    FieldKind::Text,
+    FieldKind::Number,
+    FieldKind::Date,
+    FieldKind::Select,
+];
+
+pub fn init(ui: &AppWindow) {
+    store_field_entries!(ui).set_vec(vec![]);
+    set_sort_options(ui);
+    field_init(ui);
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_field_init(move || {
+        let ui = ui_handle.unwrap();
+        field_init(&ui);
+    });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>()
+        .on_add_field(move |name, kind, options| {
+            let ui = ui_handle.unwrap();
+            let entry = FieldEntry {
+                name: name.into(),
+                kind: FIELD_KINDS.get(kind as usize).copied().unwrap_or_default(),
+                options: options.split(',').map(|item| item.to_string()).collect(),
+                ..Default::default()
+            };
+
+            if let Err(e) = field::check_field(&entry) {
+                toast_warn!(
+                    ui,
+                    format!("{}. {}: {e}", tr("Add field failed"), tr("Reason"))
+                );
+                return;
+            }
+
+            add_db_entry(&ui, entry);
+        });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_remove_field(move |index| {
+        let ui = ui_handle.unwrap();
+        let index = index as usize;
+
+        let entry = store_field_entries!(ui).row_data(index).unwrap();
+        store_field_entries!(ui).remove(index);
+        set_sort_options(&ui);
+        delete_db_entry(&ui, entry.name.into());
+        toast_success!(ui, tr("Remove field successfully"));
+    });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>()
+        .on_set_edit_record_field(move |name, value| {
+            let ui = ui_handle.unwrap();
+            let mut entry: RecordEntry = ui.global::<Store>().get_edit_record_entry().into();
+            field::set_value(&mut entry, &name, &value);
+            ui.global::<Store>().set_edit_record_entry(entry.into());
+        });
+
+    ui.global::<Logic>().on_record_field_value(|entry, name| {
+        entry
+            .fields
+            .iter()
+            .find(|item| item.name.eq_ignore_ascii_case(name.trim()))
+            .map(|item| item.value)
+            .unwrap_or_default()
+    });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_check_record_fields(move |entry| {
+        let ui = ui_handle.unwrap();
+        let mut entry: RecordEntry = entry.into();
+
+        match field::normalize(&mut entry, &fields(&ui)) {
+            Ok(_) => SharedString::default(),
+            Err(e) => format!("{}. {}: {e}", tr("Invalid field"), tr("Reason")).into(),
+        }
+    });
+}
+
+pub fn field_init(ui: &AppWindow) {
+    let ui = ui.as_weak();
+    spawn(async move {
+        let entries = match TaskStore::new().fields().await {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::warn!("{:?}", e);
+                vec![]
+            }
+        };
+
+        _ = slint::invoke_from_event_loop(move || {
+            let ui = ui.unwrap();
+            let entries = entries
+                .into_iter()
+                .map(|entry: FieldEntry| entry.into())
+                .collect::<Vec<UIFieldEntry>>();
+
+            store_field_entries!(ui).set_vec(entries);
+            set_sort_options(&ui);
+        });
+    });
+}
+
+pub fn fields(ui: &AppWindow) -> Vec<FieldEntry> {
+    store_field_entries!(ui)
+        .iter()
+        .map(|entry| entry.into())
+        .collect()
+}
+
+pub fn sort_key(ui: &AppWindow) -> SortKey {
+    let index = ui.global::<Store>().get_record_sort_index().max(0) as usize;
+
+    match SORT_KEYS.get(index) {
+        Some((key, _)) => SortKey::parse(key),
+        None => match store_field_entries!(ui).row_data(index - SORT_KEYS.len()) {
+            Some(entry) => SortKey::Field(entry.name.into()),
+            None => SortKey::Default,
+        },
+    }
+}
+
+// Keeps the selected option if it still exists
+fn set_sort_options(ui: &AppWindow) {
+    let options = ui.global::<Store>().get_record_sort_options();
+    let current = options.row_data(ui.global::<Store>().get_record_sort_index() as usize);
+
+    let options = SORT_KEYS
+        .iter()
+        .map(|(_, label)| tr(label))
+        .chain(
+            store_field_entries!(ui)
+                .iter()
+                .map(|entry| entry.name.into()),
+        )
+        .map(SharedString::from)
+        .collect::<Vec<_>>();
+
+    let index = current
+        .and_then(|current| options.iter().position(|item| *item == current))
+        .unwrap_or_default();
+
+    ui.global::<Store>()
+        .set_record_sort_options(ModelRc::new(VecModel::from(options)));
+    ui.global::<Store>().set_record_sort_index(index as i32);
+}
+
+fn add_db_entry(ui: &AppWindow, entry: FieldEntry) {
+    let ui = ui.as_weak();
+    spawn(async move {
+        match TaskStore::new().add_field(entry).await {
+            Ok(_) => {
+                _ = slint::invoke_from_event_loop(move || {
+                    let ui = ui.unwrap();
+                    field_init(&ui);
+                    toast_success!(ui, tr("Add field successfully"));
+                });
+            }
+            Err(e) => toast::async_toast_warn(
+                ui,
+                format!("{}. {}: {e:?}", tr("Add field failed"), tr("Reason")),
+            ),
+        }
+    });
+}
+
+// The list is sorted again if the removed field was the sort key
+fn delete_db_entry(ui: &AppWindow, name: String) {
+    let ui = ui.as_weak();
+    spawn(async move {
+        match TaskStore::new().remove_field(&name).await {
+            Ok(_) => {
+                _ = slint::invoke_from_event_loop(move || {
+                    record::sort_record_entries(&ui.unwrap());
+                });
+            }
+            Err(e) => toast::async_toast_warn(
+                ui,
+                format!("{}. {}: {e:?}", tr("Remove field failed"), tr("Reason")),
+            ),
+        }
+    });
+}
@@ -0,0 +1,120 @@
+use super::{archive, record, spawn, toast, tr::tr, trash};
+use crate::{
+    core::{
+        TaskStore,
+        history::{Change, History, Operation},
+    },
+    slint_generatedAppWindow::{AppWindow, Logic, ToastSetting},
+    toast_info, toast_success,
+};
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use slint::ComponentHandle;
+use std::{future::Future, sync::Mutex};
+
+// The operations of the whole session
+static HISTORY: Lazy<Mutex<History>> = Lazy::new(|| Mutex::new(History::default()));
+
+pub fn init(ui: &AppWindow) {
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_undo(move || {
+        let ui = ui_handle.unwrap();
+        let operation = HISTORY.lock().unwrap().undo();
+
+        match operation {
+            Some(operation) => restore(&ui, operation, true),
+            None => toast_info!(ui, tr("Nothing to undo")),
+        }
+    });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_redo(move || {
+        let ui = ui_handle.unwrap();
+        let operation = HISTORY.lock().unwrap().redo();
+
+        match operation {
+            Some(operation) => restore(&ui, operation, false),
+            None => toast_info!(ui, tr("Nothing to redo")),
+        }
+    });
+}
+
+// Runs `write` and logs the changes of the `uuids` records, so they can be
+// undone. The write still runs if the records can not be read.
+pub async fn track<T>(
+    label: String,
+    uuids: Vec<String>,
+    write: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    let store = TaskStore::new();
+    let mut befores = vec![];
+
+    for uuid in uuids.iter() {
+        match store.snapshot(uuid).await {
+            Ok(snapshot) => befores.push(snapshot),
+            Err(e) => {
+                log::warn!("snapshot {uuid} failed: {e:?}");
+                return write.await;
+            }
+        }
+    }
+
+    let result = write.await?;
+    let mut changes = vec![];
+
+    for (uuid, before) in uuids.into_iter().zip(befores) {
+        match store.snapshot(&uuid).await {
+            Ok(after) => changes.push(Change {
+                uuid,
+                before,
+                after,
+            }),
+            Err(e) => {
+                log::warn!("snapshot {uuid} failed: {e:?}");
+                return Ok(result);
+            }
+        }
+    }
+
+    HISTORY.lock().unwrap().push(Operation { label, changes });
+
+    Ok(result)
+}
+
+// Shows `msg` with an undo button
+pub fn toast_undo(ui: &AppWindow, msg: String) {
+    toast_success!(ui, msg);
+    ui.global::<ToastSetting>().set_action(tr("Undo").into());
+}
+
+fn restore(ui: &AppWindow, operation: Operation, undo: bool) {
+    let ui = ui.as_weak();
+    spawn(async move {
+        let (title, failed) = if undo {
+            (tr("Undo"), tr("Undo failed"))
+        } else {
+            (tr("Redo"), tr("Redo failed"))
+        };
+
+        if let Err(e) = TaskStore::new().restore(&operation.changes, undo).await {
+            // Moves the operation back to where it was
+            let mut history = HISTORY.lock().unwrap();
+            if undo {
+                history.redo();
+            } else {
+                history.undo();
+            }
+
+            toast::async_toast_warn(ui, format!("{failed}. {}: {e:?}", tr("Reason")));
+            return;
+        }
+
+        _ = slint::invoke_from_event_loop(move || {
+            let ui = ui.unwrap();
+            record::record_init(&ui);
+            archive::archive_init(&ui);
+            trash::trash_init(&ui);
+            toast_success!(ui, format!("{title}: {}", operation.label));
+        });
+    });
+}
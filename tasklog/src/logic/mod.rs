@@ -39,15 +39,53 @@ mod toast;
 #[allow(unused)]
 mod tr;
 
-#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
-mod record;
+#[cfg(any(feature = "database", feature = "web"))]
+pub mod record;
 
-#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+#[cfg(any(feature = "database", feature = "web"))]
 mod archive;
 
-#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+#[cfg(any(feature = "database", feature = "web"))]
+mod trash;
+
+#[cfg(any(feature = "database", feature = "web"))]
+mod field;
+
+#[cfg(any(feature = "database", feature = "web"))]
+mod project;
+
+#[cfg(any(feature = "database", feature = "web"))]
 mod statistic;
 
+#[cfg(any(feature = "database", feature = "web"))]
+mod history;
+
+#[cfg(feature = "database")]
+mod corrupt;
+
+#[cfg(feature = "database")]
+mod transfer;
+
+#[cfg(feature = "database")]
+mod security;
+
+// The browser runs the futures on its own event loop
+#[cfg(not(target_arch = "wasm32"))]
+pub fn spawn<F>(future: F)
+where
+    F: std::future::Future<Output = ()> + Send + 'static,
+{
+    tokio::spawn(future);
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn spawn<F>(future: F)
+where
+    F: std::future::Future<Output = ()> + 'static,
+{
+    wasm_bindgen_futures::spawn_local(future);
+}
+
 pub fn init(ui: &AppWindow) {
     #[cfg(any(
         target_os = "windows",
@@ -66,10 +104,23 @@ pub fn init(ui: &AppWindow) {
     confirm_dialog::init(ui);
     popup_action::init(ui);
 
-    #[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+    #[cfg(feature = "database")]
+    security::init(ui);
+
+    #[cfg(any(feature = "database", feature = "web"))]
     {
+        field::init(ui);
+        project::init(ui);
         record::init(ui);
         archive::init(ui);
+        trash::init(ui);
         statistic::init(ui);
+        history::init(ui);
+    }
+
+    #[cfg(feature = "database")]
+    {
+        corrupt::init(ui);
+        transfer::init(ui);
     }
 }
@@ -23,7 +23,7 @@ pub fn init(ui: &AppWindow) {
                     ui.global::<ConfirmDialogSetting>().invoke_set(
                         true,
                         tr("Warning").into(),
-                        tr("Delete or not?").into(),
+                        tr("Move to trash or not?").into(),
                         "remove-record".into(),
                         user_data,
                     );
@@ -32,6 +32,26 @@ pub fn init(ui: &AppWindow) {
                     let current_index = user_data.parse::<i32>().unwrap();
                     ui.global::<Logic>().invoke_archive_record(current_index);
                 }
+                "archive-blocking-record" => {
+                    ui.global::<ConfirmDialogSetting>().invoke_set(
+                        true,
+                        tr("Warning").into(),
+                        tr("Other tasks are blocked by this task. Remove the links and archive it?")
+                            .into(),
+                        "archive-record".into(),
+                        user_data,
+                    );
+                }
+                "remove-blocking-record" => {
+                    ui.global::<ConfirmDialogSetting>().invoke_set(
+                        true,
+                        tr("Warning").into(),
+                        tr("Other tasks are blocked by this task. Remove the links and move it to trash?")
+                            .into(),
+                        "remove-record".into(),
+                        user_data,
+                    );
+                }
                 "plan-record" => {
                     let current_index = user_data.parse::<i32>().unwrap();
                     ui.global::<Logic>()
@@ -41,7 +61,7 @@ pub fn init(ui: &AppWindow) {
                     ui.global::<ConfirmDialogSetting>().invoke_set(
                         true,
                         tr("Warning").into(),
-                        tr("Delete or not?").into(),
+                        tr("Move to trash or not?").into(),
                         "remove-archive".into(),
                         user_data,
                     );
@@ -50,6 +70,19 @@ pub fn init(ui: &AppWindow) {
                     let current_index = user_data.parse::<i32>().unwrap();
                     ui.global::<Logic>().invoke_recover_archive(current_index);
                 }
+                "restore-trash" => {
+                    let current_index = user_data.parse::<i32>().unwrap();
+                    ui.global::<Logic>().invoke_restore_trash(current_index);
+                }
+                "remove-trash" => {
+                    ui.global::<ConfirmDialogSetting>().invoke_set(
+                        true,
+                        tr("Warning").into(),
+                        tr("Delete permanently or not?").into(),
+                        "remove-trash".into(),
+                        user_data,
+                    );
+                }
                 _ => (),
             }
         });
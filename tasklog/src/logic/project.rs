@@ -0,0 +1,357 @@
+use super::{archive, record, spawn, statistic, toast, tr::tr};
+use crate::{
+    core::{
+        TaskStore,
+        project::{self, Filter},
+    },
+    db::def::ProjectEntry,
+    slint_generatedAppWindow::{AppWindow, Logic, ProjectEntry as UIProjectEntry, Store},
+    toast_success, toast_warn,
+};
+use slint::{ComponentHandle, Model, ModelRc, SharedString, VecModel};
+
+#[macro_export]
+macro_rules! store_project_entries {
+    ($ui:expr) => {
+        $ui.global::<Store>()
+            .get_project_entries()
+            .as_any()
+            .downcast_ref::<VecModel<UIProjectEntry>>()
+            .expect("We know we set a VecModel<UIProjectEntry> earlier")
+    };
+}
+
+pub fn init(ui: &AppWindow) {
+    store_project_entries!(ui).set_vec(vec![]);
+    set_project_options(ui, Filter::All, Filter::All);
+    project_init(ui);
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_project_init(move || {
+        let ui = ui_handle.unwrap();
+        project_init(&ui);
+    });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>()
+        .on_add_project(move |name, color, description| {
+            let ui = ui_handle.unwrap();
+            let entry = ProjectEntry {
+                name: name.into(),
+                color: color_of(color),
+                description: description.into(),
+                ..Default::default()
+            };
+
+            if let Err(e) = project::check_project(&entry) {
+                toast_warn!(
+                    ui,
+                    format!("{}. {}: {e}", tr("Add project failed"), tr("Reason"))
+                );
+                return;
+            }
+
+            let ui = ui.as_weak();
+            spawn(async move {
+                match TaskStore::new().add_project(entry).await {
+                    Ok(_) => {
+                        _ = slint::invoke_from_event_loop(move || {
+                            let ui = ui.unwrap();
+                            project_init(&ui);
+                            toast_success!(ui, tr("Add project successfully"));
+                        });
+                    }
+                    Err(e) => toast::async_toast_warn(
+                        ui,
+                        format!("{}. {}: {e:?}", tr("Add project failed"), tr("Reason")),
+                    ),
+                }
+            });
+        });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>()
+        .on_update_project(move |index, name, color, description| {
+            let ui = ui_handle.unwrap();
+            let Some(entry) = store_project_entries!(ui).row_data(index as usize) else {
+                return;
+            };
+
+            let mut entry: ProjectEntry = entry.into();
+            entry.name = name.into();
+            entry.description = description.into();
+            if color >= 0 {
+                entry.color = color_of(color);
+            }
+
+            let ui = ui.as_weak();
+            spawn(async move {
+                match TaskStore::new().update_project(entry).await {
+                    Ok(_) => {
+                        _ = slint::invoke_from_event_loop(move || {
+                            let ui = ui.unwrap();
+                            project_init(&ui);
+                            toast_success!(ui, tr("Save successfully"));
+                        });
+                    }
+                    Err(e) => toast::async_toast_warn(
+                        ui,
+                        format!("{}. {}: {e:?}", tr("Save failed"), tr("Reason")),
+                    ),
+                }
+            });
+        });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_archive_project(move |index| {
+        let ui = ui_handle.unwrap();
+        if let Some(entry) = store_project_entries!(ui).row_data(index as usize) {
+            move_project(&ui, entry.uuid.into(), true);
+        }
+    });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_recover_project(move |index| {
+        let ui = ui_handle.unwrap();
+        if let Some(entry) = store_project_entries!(ui).row_data(index as usize) {
+            move_project(&ui, entry.uuid.into(), false);
+        }
+    });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_remove_project(move |index| {
+        let ui = ui_handle.unwrap();
+        let Some(entry) = store_project_entries!(ui).row_data(index as usize) else {
+            return;
+        };
+
+        let ui = ui.as_weak();
+        spawn(async move {
+            match TaskStore::new().remove_project(&entry.uuid).await {
+                Ok(_) => {
+                    _ = slint::invoke_from_event_loop(move || {
+                        let ui = ui.unwrap();
+                        project_init(&ui);
+                        record::record_init(&ui);
+                        archive::archive_init(&ui);
+                        statistic::statistic_init(&ui);
+                        toast_success!(ui, tr("Remove project successfully"));
+                    });
+                }
+                Err(e) => toast::async_toast_warn(
+                    ui,
+                    format!("{}. {}: {e:?}", tr("Remove project failed"), tr("Reason")),
+                ),
+            }
+        });
+    });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_switch_project(move |index| {
+        let ui = ui_handle.unwrap();
+        ui.global::<Store>().set_record_project_index(index);
+        ui.global::<Store>().set_record_project_uuid(
+            ui.global::<Store>()
+                .get_project_option_uuids()
+                .row_data((index - 1).max(0) as usize)
+                .unwrap_or_default(),
+        );
+        record::record_init(&ui);
+    });
+
+    ui.global::<Logic>().on_project_of(|entries, uuid| {
+        entries
+            .iter()
+            .find(|entry| !uuid.is_empty() && entry.uuid == uuid)
+            .unwrap_or_default()
+    });
+
+    ui.global::<Logic>().on_project_color_index(|entry| {
+        let entry: ProjectEntry = entry.into();
+        project::COLORS
+            .iter()
+            .position(|(_, hex)| *hex == entry.color)
+            .map_or(-1, |index| index as i32)
+    });
+}
+
+pub fn project_init(ui: &AppWindow) {
+    let ui = ui.as_weak();
+    spawn(async move {
+        let entries = match TaskStore::new().projects().await {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::warn!("{:?}", e);
+                vec![]
+            }
+        };
+
+        _ = slint::invoke_from_event_loop(move || {
+            let ui = ui.unwrap();
+            let entries = entries
+                .into_iter()
+                .map(|entry: ProjectEntry| entry.into())
+                .collect::<Vec<UIProjectEntry>>();
+
+            let (filter, statistic_filter) = (filter(&ui), statistic_filter(&ui));
+            store_project_entries!(ui).set_vec(entries);
+            set_project_options(&ui, filter, statistic_filter);
+        });
+    });
+}
+
+pub fn projects(ui: &AppWindow) -> Vec<ProjectEntry> {
+    store_project_entries!(ui)
+        .iter()
+        .map(|entry| entry.into())
+        .collect()
+}
+
+// The records shown in the record list
+pub fn filter(ui: &AppWindow) -> Filter {
+    match ui.global::<Store>().get_record_project_index() {
+        index if index <= 0 => Filter::All,
+        1 => Filter::NoProject,
+        _ => Filter::Project(ui.global::<Store>().get_record_project_uuid().into()),
+    }
+}
+
+// The records counted by the statistic, the archived projects can be chosen
+pub fn statistic_filter(ui: &AppWindow) -> Filter {
+    let uuid = ui.global::<Store>().get_statistic_project_uuid();
+    match ui.global::<Store>().get_statistic_project_index() {
+        1 => Filter::NoProject,
+        _ if uuid.is_empty() => Filter::All,
+        _ => Filter::Project(uuid.into()),
+    }
+}
+
+// The uuid of the statistic project option
+pub fn statistic_project_uuid(ui: &AppWindow, index: i32) -> String {
+    if index < 2 {
+        return String::default();
+    }
+
+    store_project_entries!(ui)
+        .row_data(index as usize - 2)
+        .map(|entry| entry.uuid.into())
+        .unwrap_or_default()
+}
+
+// The selected projects are kept if they are still in the options
+fn set_project_options(ui: &AppWindow, filter: Filter, statistic_filter: Filter) {
+    let entries = projects(ui);
+    let active = entries
+        .iter()
+        .filter(|entry| !entry.is_archived)
+        .collect::<Vec<_>>();
+
+    let options = [tr("No project")]
+        .into_iter()
+        .chain(active.iter().map(|entry| entry.name.clone()))
+        .map(SharedString::from)
+        .collect::<Vec<_>>();
+    let uuids = [String::default()]
+        .into_iter()
+        .chain(active.iter().map(|entry| entry.uuid.clone()))
+        .map(SharedString::from)
+        .collect::<Vec<_>>();
+
+    let record_options = [SharedString::from(tr("All projects"))]
+        .into_iter()
+        .chain(options.iter().cloned())
+        .collect::<Vec<_>>();
+    let statistic_options = [tr("All projects"), tr("No project")]
+        .into_iter()
+        .chain(entries.iter().map(|entry| entry.name.clone()))
+        .map(SharedString::from)
+        .collect::<Vec<_>>();
+
+    let record_index = match &filter {
+        Filter::All => 0,
+        Filter::NoProject => 1,
+        Filter::Project(uuid) => uuids
+            .iter()
+            .position(|item| item == uuid.as_str())
+            .map_or(0, |index| index + 1),
+    };
+
+    let statistic_index = match &statistic_filter {
+        Filter::All => 0,
+        Filter::NoProject => 1,
+        Filter::Project(uuid) => entries
+            .iter()
+            .position(|entry| entry.uuid == *uuid)
+            .map_or(0, |index| index + 2),
+    };
+
+    ui.global::<Store>()
+        .set_project_options(ModelRc::new(VecModel::from(options)));
+    ui.global::<Store>()
+        .set_project_option_uuids(ModelRc::new(VecModel::from(uuids)));
+    ui.global::<Store>()
+        .set_record_project_options(ModelRc::new(VecModel::from(record_options)));
+    ui.global::<Store>()
+        .set_statistic_project_options(ModelRc::new(VecModel::from(statistic_options)));
+    ui.global::<Store>()
+        .set_statistic_project_index(statistic_index as i32);
+    if statistic_index == 0 && statistic_filter != Filter::All {
+        ui.global::<Store>().set_statistic_project_uuid("".into());
+        statistic::statistic_init(ui);
+    }
+
+    // The list is loaded again if its project is removed or archived
+    ui.global::<Store>()
+        .set_record_project_index(record_index as i32);
+    if record_index == 0 && filter != Filter::All {
+        ui.global::<Store>().set_record_project_uuid("".into());
+        record::record_init(ui);
+    }
+}
+
+fn color_of(index: i32) -> String {
+    project::COLORS
+        .get(index.max(0) as usize)
+        .map(|(_, hex)| hex.to_string())
+        .unwrap_or_default()
+}
+
+// Archives or recovers the project with all its records
+fn move_project(ui: &AppWindow, uuid: String, is_archive: bool) {
+    let ui = ui.as_weak();
+    spawn(async move {
+        let store = TaskStore::new();
+        let result = if is_archive {
+            store.archive_project(&uuid).await
+        } else {
+            store.recover_project(&uuid).await
+        };
+
+        match result {
+            Ok(entries) => {
+                _ = slint::invoke_from_event_loop(move || {
+                    let ui = ui.unwrap();
+                    project_init(&ui);
+                    record::record_init(&ui);
+                    archive::archive_init(&ui);
+                    statistic::statistic_init(&ui);
+
+                    let text = if is_archive {
+                        tr("Archive project successfully")
+                    } else {
+                        tr("Recover project successfully")
+                    };
+                    toast_success!(ui, format!("{text}: {}", entries.len()));
+                });
+            }
+            Err(e) => {
+                let text = if is_archive {
+                    tr("Archive project failed")
+                } else {
+                    tr("Recover project failed")
+                };
+                toast::async_toast_warn(ui, format!("{text}. {}: {e:?}", tr("Reason")));
+            }
+        }
+    });
+}
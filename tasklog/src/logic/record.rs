@@ -1,26 +1,24 @@
-use super::{toast, tr::tr};
+use super::{field, history, project, spawn, toast, tr::tr};
 use crate::{
-    config,
+    core::{
+        TaskStore, change, dependency, plan, project as core_project, record,
+        recurrence::{self, Recurrence},
+        search::Query,
+        session, sort,
+        sort::SortKey,
+    },
     db::{
-        self,
-        def::{RecordEntry, RECORD_TABLE as DB_TABLE},
+        WriteOp,
+        def::{ChangeEntry, ProjectEntry, RecordEntry, RecordPlanEntry, RecordState},
     },
     slint_generatedAppWindow::{
-        AppWindow, Logic, PopupIndex, RecordEntry as UIRecordEntry,
-        RecordPlanEntry as UIRecordPlanEntry, RecordState as UIRecordState, Store,
+        AppWindow, Logic, PopupIndex, RecordChange as UIRecordChange, RecordEntry as UIRecordEntry,
+        RecordPlanEntry as UIRecordPlanEntry, Store,
     },
-    toast_success, toast_warn,
+    store_current_archive_entries, toast_success, toast_warn,
 };
 use anyhow::Result;
-use async_openai::{
-    types::{
-        ChatCompletionRequestSystemMessageArgs, ChatCompletionRequestUserMessageArgs,
-        CreateChatCompletionRequestArgs,
-    },
-    Client,
-};
-use regex::Regex;
-use slint::{ComponentHandle, Model, ModelRc, VecModel, Weak};
+use slint::{ComponentHandle, Model, ModelRc, SharedString, VecModel, Weak};
 use uuid::Uuid;
 
 #[macro_export]
@@ -59,31 +57,65 @@ pub fn init(ui: &AppWindow) {
     record_init(ui);
 
     let ui_handle = ui.as_weak();
-    ui.global::<Logic>().on_new_record(move |mut entry| {
+    ui.global::<Logic>().on_new_record(move |entry| {
         let ui = ui_handle.unwrap();
-        entry.uuid = Uuid::new_v4().to_string().into();
+        let mut entry: RecordEntry = entry.into();
+        entry.uuid = Uuid::new_v4().to_string();
+        record::refresh_state(&mut entry, cutil::time::timestamp());
+
+        if let Err(e) = recurrence::normalize(&mut entry) {
+            toast_warn!(
+                ui,
+                format!("{}. {}: {e}", tr("Invalid repeat rule"), tr("Reason"))
+            );
+            return;
+        }
 
-        if let Some(state) = calc_state(&entry.start_date, &entry.end_date, entry.state) {
-            entry.state = state;
+        if let Err(e) = dependency::check(&mut entry, &current_entries(&ui)) {
+            toast_warn!(ui, format!("{}. {}: {e}", tr("Invalid link"), tr("Reason")));
+            return;
         }
 
-        store_current_record_entries!(ui).insert(0, entry.clone());
-        add_db_entry(&ui, entry.into());
+        // A record of another project is saved without being shown
+        if project::filter(&ui).matches(&entry, &project::projects(&ui)) {
+            store_current_record_entries!(ui).insert(0, entry.clone().into());
+        }
+        refresh_dependencies(&ui);
+        sort_record_entries(&ui);
+        add_db_entry(&ui, entry);
     });
 
     let ui_handle = ui.as_weak();
-    ui.global::<Logic>().on_update_record(move |mut entry| {
+    ui.global::<Logic>().on_update_record(move |entry| {
         let ui = ui_handle.unwrap();
         if let Some(index) = store_current_record_entries!(ui)
             .iter()
-            .position(|item| item.uuid == &entry.uuid)
+            .position(|item| item.uuid == entry.uuid)
         {
-            if let Some(state) = calc_state(&entry.start_date, &entry.end_date, entry.state) {
-                entry.state = state;
+            let mut entry: RecordEntry = entry.into();
+            record::refresh_state(&mut entry, cutil::time::timestamp());
+
+            if let Err(e) = recurrence::normalize(&mut entry) {
+                toast_warn!(
+                    ui,
+                    format!("{}. {}: {e}", tr("Invalid repeat rule"), tr("Reason"))
+                );
+                return;
             }
 
-            store_current_record_entries!(ui).set_row_data(index, entry.clone());
-            update_db_entry(&ui, entry.into());
+            if let Err(e) = dependency::check(&mut entry, &current_entries(&ui)) {
+                toast_warn!(ui, format!("{}. {}: {e}", tr("Invalid link"), tr("Reason")));
+                return;
+            }
+
+            if project::filter(&ui).matches(&entry, &project::projects(&ui)) {
+                store_current_record_entries!(ui).set_row_data(index, entry.clone().into());
+            } else {
+                store_current_record_entries!(ui).remove(index);
+            }
+            refresh_dependencies(&ui);
+            sort_record_entries(&ui);
+            update_db_entry(&ui, entry, None, vec![]);
         }
     });
 
@@ -93,9 +125,15 @@ pub fn init(ui: &AppWindow) {
         let index = index as usize;
 
         let entry = store_current_record_entries!(ui).row_data(index).unwrap();
+        if entry.is_timing {
+            ui.global::<Store>().set_timing_record_uuid("".into());
+        }
+
         store_current_record_entries!(ui).remove(index);
-        delete_db_entry(&ui, entry.uuid.into());
-        toast_success!(ui, tr("Remove entry successfully"));
+        let dependents = unlink_entries(&ui, &entry.uuid);
+        refresh_dependencies(&ui);
+        delete_db_entry(&ui, entry.uuid.into(), dependents);
+        history::toast_undo(&ui, tr("Remove entry successfully"));
     });
 
     let ui_handle = ui.as_weak();
@@ -103,18 +141,49 @@ pub fn init(ui: &AppWindow) {
         let ui = ui_handle.unwrap();
         let index = index as usize;
 
-        let entry = store_current_record_entries!(ui).row_data(index).unwrap();
+        let origin: RecordEntry = store_current_record_entries!(ui)
+            .row_data(index)
+            .unwrap()
+            .into();
+        let mut entry = origin.clone();
+        record::refresh_state(&mut entry, cutil::time::timestamp());
+        let (next, ops) = next_occurrence(&ui, &mut entry);
+
+        // The store stops the running timer of the archived record
+        if entry.is_timing {
+            ui.global::<Store>().set_timing_record_uuid("".into());
+        }
+
         store_current_record_entries!(ui).remove(index);
-        delete_db_entry(&ui, entry.uuid.clone().into());
+        show_next_occurrence(&ui, next.as_ref());
+        let dependents = unlink_entries(&ui, &entry.uuid);
+        refresh_dependencies(&ui);
 
-        ui.global::<Logic>().invoke_add_archive(entry);
-        toast_success!(ui, tr("Archive entry successfully"));
+        let mut archive_entry = entry.clone();
+        archive_entry.is_timing = false;
+        ui.global::<Logic>()
+            .invoke_add_archive(archive_entry.into());
+        archive_db_entry(&ui, entry, origin, index, dependents, (next, ops));
+        history::toast_undo(&ui, tr("Archive entry successfully"));
     });
 
     let ui_handle = ui.as_weak();
     ui.global::<Logic>().on_open_record_dialog(move |index| {
         let ui = ui_handle.unwrap();
 
+        ui.global::<Store>()
+            .set_record_changes(ModelRc::new(VecModel::from(vec![])));
+
+        let uuid = if index < 0 {
+            String::default()
+        } else {
+            store_current_record_entries!(ui)
+                .row_data(index as usize)
+                .map(|entry| entry.uuid.into())
+                .unwrap_or_default()
+        };
+        set_link_options(&ui, &uuid);
+
         if index < 0 {
             ui.global::<Store>()
                 .set_edit_record_entry(Default::default());
@@ -123,6 +192,7 @@ pub fn init(ui: &AppWindow) {
                 .row_data(index as usize)
                 .unwrap();
 
+            load_record_changes(&ui, entry.uuid.to_string());
             ui.global::<Store>().set_edit_record_entry(entry);
         }
 
@@ -133,6 +203,8 @@ pub fn init(ui: &AppWindow) {
     let ui_handle = ui.as_weak();
     ui.global::<Logic>().on_search_record(move |keyword| {
         let ui = ui_handle.unwrap();
+        ui.global::<Store>()
+            .set_record_search_keyword(keyword.clone());
 
         if keyword.is_empty() {
             let entries = store_current_record_entries_cache!(ui)
@@ -151,22 +223,33 @@ pub fn init(ui: &AppWindow) {
             store_current_record_entries_cache!(ui).set_vec(entries);
         }
 
-        let entries = store_current_record_entries_cache!(ui)
-            .iter()
-            .collect::<Vec<UIRecordEntry>>();
+        // Nothing matches a query with an invalid state or date
+        match Query::parse(&keyword) {
+            Ok(query) => search_db_entries(&ui, keyword, query),
+            Err(e) => {
+                log::debug!("{e:?}");
+                store_current_record_entries!(ui).set_vec(vec![]);
+            }
+        }
+    });
 
-        let filter_entries = entries
-            .iter()
-            .filter_map(|entry| {
-                if entry.title.contains(keyword.as_str()) {
-                    Some(entry.clone())
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<_>>();
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_sort_records(move |index| {
+        let ui = ui_handle.unwrap();
+        ui.global::<Store>().set_record_sort_index(index);
+
+        // The default order is the loading order
+        if field::sort_key(&ui) == SortKey::Default {
+            record_init(&ui);
+        } else {
+            sort_record_entries(&ui);
+        }
+    });
 
-        store_current_record_entries!(ui).set_vec(filter_entries);
+    ui.global::<Logic>().on_parse_estimate(|text| {
+        session::parse_duration(&text)
+            .map(|seconds| seconds as i32)
+            .unwrap_or(-1)
     });
 
     let ui_handle = ui.as_weak();
@@ -181,105 +264,125 @@ pub fn init(ui: &AppWindow) {
         .on_update_record_state(move |index, state| {
             let ui = ui_handle.unwrap();
             let index = index as usize;
-            let mut entry = store_current_record_entries!(ui).row_data(index).unwrap();
-            let old_state = entry.state;
-
-            log::debug!("{old_state:?} -> {state:?}");
-
-            match state {
-                UIRecordState::Running => {
-                    entry.state = UIRecordState::Running;
-
-                    match old_state {
-                        UIRecordState::NotStarted => {
-                            entry.start_date = cutil::time::local_now("%Y-%m-%d").into();
-                        }
-                        UIRecordState::Finished
-                        | UIRecordState::Timeout
-                        | UIRecordState::Giveup => {
-                            entry.end_date = cutil::time::local_now("%Y-%m-%d").into();
-                        }
-                        _ => (),
-                    }
-                }
-                UIRecordState::Finished => {
-                    entry.state = UIRecordState::Finished;
-                    entry.end_date = cutil::time::local_now("%Y-%m-%d").into();
+            let entry = store_current_record_entries!(ui).row_data(index).unwrap();
+            let mut entry: RecordEntry = entry.into();
+            let state: RecordState = state.into();
 
-                    if old_state == UIRecordState::NotStarted {
-                        entry.start_date = entry.end_date.clone();
-                    }
-                }
-                UIRecordState::Giveup => {
-                    entry.state = UIRecordState::Giveup;
-                    entry.end_date = cutil::time::local_now("%Y-%m-%d").into();
+            log::debug!("{:?} -> {state:?}", entry.state);
 
-                    if old_state == UIRecordState::NotStarted {
-                        entry.start_date = entry.end_date.clone();
-                    }
-                }
-                _ => return,
+            if state == RecordState::Running
+                && let Some(item) = dependency::blockers(&entry, &current_entries(&ui)).first()
+            {
+                toast_warn!(
+                    ui,
+                    format!("{}: {}", tr("This task is blocked by"), item.title)
+                );
+                return;
             }
 
-            if let Ok(diff_days) =
-                cutil::time::diff_dates_to_days(&entry.start_date, &entry.end_date)
-                && diff_days < 0
-            {
-                entry.end_date = entry.start_date.clone();
+            // The next occurrence is shifted from the planned dates
+            let (next, ops) = if state == RecordState::Finished && entry.state != state {
+                next_occurrence(&ui, &mut entry)
+            } else {
+                (None, vec![])
+            };
+
+            if !record::update_state(&mut entry, state, &record::today()) {
+                return;
             }
+            show_next_occurrence(&ui, next.as_ref());
 
-            store_current_record_entries!(ui).set_row_data(index, entry.clone());
-            update_db_entry(&ui, entry.into());
+            let index = store_current_record_entries!(ui)
+                .iter()
+                .position(|item| item.uuid == entry.uuid)
+                .unwrap_or(index);
+            store_current_record_entries!(ui).set_row_data(index, entry.clone().into());
+            refresh_dependencies(&ui);
+            update_db_entry(&ui, entry, next, ops);
         });
 
-    ui.global::<Logic>().on_record_progress(|entry| {
-        let row_count = store_current_record_plan!(entry.plan).row_count();
-        if row_count > 0 {
-            let mut finished_counts = 0;
-            for item in entry.plan.iter() {
-                if item.is_finished {
-                    finished_counts += 1;
-                }
-            }
+    // ============================== record link ========================== //
 
-            return finished_counts as f32 / row_count as f32;
-        } else {
-            let current_date = cutil::time::local_now("%Y-%m-%d");
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_add_edit_record_link(move |index| {
+        let ui = ui_handle.unwrap();
+        let Some(uuid) = ui
+            .global::<Store>()
+            .get_record_link_uuids()
+            .row_data(index as usize)
+        else {
+            return;
+        };
 
-            let diff_1 = cutil::time::diff_dates_to_days(&entry.start_date, &current_date)
-                .unwrap_or_default()
-                .max(0);
+        let mut entry: RecordEntry = ui.global::<Store>().get_edit_record_entry().into();
+        if !entry.blocked_by.iter().any(|item| uuid == item) {
+            entry.blocked_by.push(uuid.into());
+            ui.global::<Store>().set_edit_record_entry(entry.into());
+        }
+    });
 
-            let diff_2 = cutil::time::diff_dates_to_days(&entry.start_date, &entry.end_date)
-                .unwrap_or_default()
-                .max(1);
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>()
+        .on_remove_edit_record_link(move |index| {
+            let ui = ui_handle.unwrap();
+            let mut entry: RecordEntry = ui.global::<Store>().get_edit_record_entry().into();
 
-            if diff_1 >= diff_2 {
-                return 1.0_f32;
-            } else {
-                diff_1 as f32 / diff_2 as f32
+            if (index as usize) < entry.blocked_by.len() {
+                entry.blocked_by.remove(index as usize);
+                ui.global::<Store>().set_edit_record_entry(entry.into());
             }
-        }
+        });
+
+    // A link to an archived or removed record shows its uuid prefix
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_record_link_titles(move |uuids| {
+        let ui = ui_handle.unwrap();
+        let entries = current_entries(&ui);
+
+        let titles = uuids
+            .iter()
+            .map(|uuid| {
+                entries
+                    .iter()
+                    .find(|entry| entry.uuid == uuid.as_str())
+                    .map(|entry| entry.title.clone())
+                    .unwrap_or_else(|| uuid.chars().take(8).collect())
+                    .into()
+            })
+            .collect::<Vec<SharedString>>();
+
+        ModelRc::new(VecModel::from(titles))
     });
 
-    ui.global::<Logic>().on_remain_days(|start_date, end_date| {
-        cutil::time::diff_dates_to_days(&start_date, &end_date)
-            .unwrap_or_default()
-            .max(0) as i32
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_check_record_links(move |entry| {
+        let ui = ui_handle.unwrap();
+        let mut entry: RecordEntry = entry.into();
+
+        match dependency::check(&mut entry, &current_entries(&ui)) {
+            Ok(_) => SharedString::default(),
+            Err(e) => format!("{}. {}: {e}", tr("Invalid link"), tr("Reason")).into(),
+        }
     });
 
+    ui.global::<Logic>()
+        .on_is_valid_recurrence(|text| text.trim().is_empty() || Recurrence::parse(&text).is_ok());
+
+    ui.global::<Logic>()
+        .on_record_progress(|entry| record::progress(&entry.into(), &record::today()));
+
+    ui.global::<Logic>()
+        .on_remain_days(|start_date, end_date| record::remain_days(&start_date, &end_date) as i32);
+
     ui.global::<Logic>()
         .on_remain_days_numbers(|start_date, end_date| {
-            let days = cutil::time::diff_dates_to_days(&start_date, &end_date)
-                .unwrap_or_default()
-                .max(0) as i32;
+            let days = record::remain_days(&start_date, &end_date) as i32;
 
             let days_numbers = if days < 10 {
                 vec![0, days]
             } else {
                 format!("{days}")
                     .chars()
-                    .into_iter()
                     .map(|n| n.to_digit(10).unwrap_or_default() as i32)
                     .collect::<Vec<i32>>()
             };
@@ -287,6 +390,47 @@ pub fn init(ui: &AppWindow) {
             ModelRc::new(VecModel::from_slice(&days_numbers))
         });
 
+    // ============================== record timer ========================= //
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_toggle_record_timer(move |index| {
+        let ui = ui_handle.unwrap();
+        let entry = store_current_record_entries!(ui)
+            .row_data(index as usize)
+            .unwrap();
+
+        let ui = ui.as_weak();
+        spawn(async move {
+            let store = TaskStore::new();
+            let result = if entry.is_timing {
+                store.stop_timer().await.map(|_| ())
+            } else {
+                store.start_timer(&entry.uuid, "").await.map(|_| ())
+            };
+
+            match result {
+                Ok(_) => {
+                    _ = slint::invoke_from_event_loop(move || {
+                        refresh_tracked_time(&ui.unwrap());
+                    });
+                }
+                Err(e) => toast::async_toast_warn(
+                    ui,
+                    format!("{}. {}: {e:?}", tr("Timer failed"), tr("Reason")),
+                ),
+            }
+        });
+    });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_refresh_record_timer(move || {
+        let ui = ui_handle.unwrap();
+        refresh_tracked_time(&ui);
+    });
+
+    ui.global::<Logic>()
+        .on_format_tracked_time(|seconds| session::format_duration(seconds as i64).into());
+
     // ============================== record plan ========================== //
 
     let ui_handle = ui.as_weak();
@@ -315,18 +459,10 @@ pub fn init(ui: &AppWindow) {
     let ui_handle = ui.as_weak();
     ui.global::<Logic>().on_remove_record_plan(move |index| {
         let ui = ui_handle.unwrap();
-        let record_entry = ui.global::<Store>().get_record_plan_entry();
-        let plan_entries = store_current_record_plan!(record_entry.plan);
-
-        let mut plan_entries_duplicate = plan_entries
-            .iter()
-            .map(|item| item.clone())
-            .collect::<Vec<_>>();
-
-        plan_entries_duplicate.remove(index as usize);
-        plan_entries.set_vec(plan_entries_duplicate);
-        ui.global::<Store>().set_next_record_plan_item_pos_y(0.0);
-        ui.global::<Logic>().invoke_update_record(record_entry);
+        edit_plan(&ui, |entries| {
+            plan::remove(entries, index as usize);
+            true
+        });
     });
 
     let ui_handle = ui.as_weak();
@@ -338,6 +474,10 @@ pub fn init(ui: &AppWindow) {
         plan_entries.set_vec(vec![]);
         ui.global::<Store>().set_next_record_plan_item_pos_y(0.0);
         ui.global::<Logic>().invoke_update_record(record_entry);
+
+        if !ui.global::<Store>().get_is_ai_generate_record_plans() {
+            history::toast_undo(&ui, tr("Remove all plans successfully"));
+        }
     });
 
     let ui_handle = ui.as_weak();
@@ -346,11 +486,37 @@ pub fn init(ui: &AppWindow) {
             let ui = ui_handle.unwrap();
             let record_entry = ui.global::<Store>().get_record_plan_entry();
             let plan_entries = store_current_record_plan!(record_entry.plan);
+            let Some(old_entry) = plan_entries.row_data(index as usize) else {
+                return;
+            };
+
+            // Toggling a step also toggles its sub-steps and its parents
+            if old_entry.is_finished != entry.is_finished {
+                let is_finished = entry.is_finished;
+                plan_entries.set_row_data(index as usize, entry);
+                edit_plan(&ui, |entries| {
+                    plan::set_finished(entries, index as usize, is_finished);
+                    true
+                });
+                return;
+            }
 
             plan_entries.set_row_data(index as usize, entry);
             ui.global::<Logic>().invoke_update_record(record_entry);
         });
 
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_indent_record_plan(move |index| {
+        let ui = ui_handle.unwrap();
+        edit_plan(&ui, |entries| plan::indent(entries, index as usize));
+    });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_outdent_record_plan(move |index| {
+        let ui = ui_handle.unwrap();
+        edit_plan(&ui, |entries| plan::outdent(entries, index as usize));
+    });
+
     let ui_handle = ui.as_weak();
     ui.global::<Logic>()
         .on_move_record_plan(move |start_index, y, item_height| {
@@ -361,26 +527,17 @@ pub fn init(ui: &AppWindow) {
             let ui = ui_handle.unwrap();
             let start_index = start_index as usize;
             let record_entry = ui.global::<Store>().get_record_plan_entry();
-            let plan_entries = store_current_record_plan!(record_entry.plan);
-            let row_count = plan_entries.row_count();
+            let row_count = store_current_record_plan!(record_entry.plan).row_count();
             let end_index = (y / item_height).clamp(0.0, (row_count - 1) as f32) as usize;
 
             log::debug!("{start_index} => {end_index}");
 
-            let mut plan_entries_duplicate = plan_entries
-                .iter()
-                .map(|item| item.clone())
-                .collect::<Vec<_>>();
-            let moving_plan_entry = plan_entries_duplicate[start_index].clone();
-
-            if start_index != end_index {
-                plan_entries_duplicate.remove(start_index);
-                plan_entries_duplicate.insert(end_index, moving_plan_entry);
-            }
-
-            plan_entries.set_vec(plan_entries_duplicate);
-            ui.global::<Store>().set_next_record_plan_item_pos_y(0.0);
-            ui.global::<Logic>().invoke_update_record(record_entry);
+            // A step moves with its sub-steps. The items are laid out again
+            // even if nothing moved, since the dragged one left its place.
+            edit_plan(&ui, |entries| {
+                plan::move_item(entries, start_index, end_index);
+                true
+            });
         });
 
     let ui_handle = ui.as_weak();
@@ -392,19 +549,80 @@ pub fn init(ui: &AppWindow) {
     });
 }
 
-fn record_init(ui: &AppWindow) {
+// Applies `f` to the plan of the record in the plan dialog and saves it if
+// `f` returns true. The items are laid out again as their count or levels may
+// have changed, and the parents take the finished flags of their sub-steps.
+fn edit_plan(ui: &AppWindow, f: impl FnOnce(&mut Vec<RecordPlanEntry>) -> bool) {
+    let record_entry = ui.global::<Store>().get_record_plan_entry();
+    let plan_entries = store_current_record_plan!(record_entry.plan);
+    let mut entries = plan_entries
+        .iter()
+        .map(RecordPlanEntry::from)
+        .collect::<Vec<_>>();
+
+    if !f(&mut entries) {
+        return;
+    }
+    plan::refresh_parents(&mut entries);
+
+    plan_entries.set_vec(
+        entries
+            .into_iter()
+            .map(UIRecordPlanEntry::from)
+            .collect::<Vec<_>>(),
+    );
+    ui.global::<Store>().set_next_record_plan_item_pos_y(0.0);
+    ui.global::<Logic>().invoke_update_record(record_entry);
+}
+
+pub fn record_init(ui: &AppWindow) {
     store_current_record_entries!(ui).set_vec(vec![]);
     store_current_record_entries_cache!(ui).set_vec(vec![]);
 
     let ui = ui.as_weak();
-    tokio::spawn(async move {
-        let entries = match db::entry::select_all(DB_TABLE).await {
-            Ok(items) => items
+    spawn(async move {
+        let entries = match TaskStore::new().records().await {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::warn!("{:?}", e);
+                vec![]
+            }
+        };
+
+        _ = slint::invoke_from_event_loop(move || {
+            let ui = ui.unwrap();
+            ui.global::<Store>()
+                .set_timing_record_uuid(timing_record_uuid(&entries).into());
+
+            // The links and blocked flags only see the records of the shown
+            // project
+            let (filter, projects) = (project::filter(&ui), project::projects(&ui));
+            let entries = entries
                 .into_iter()
-                .filter_map(|item| serde_json::from_str::<RecordEntry>(&item.data).ok())
-                .map(|item| item.into())
-                .collect(),
+                .filter(|entry| filter.matches(entry, &projects))
+                .map(|entry: RecordEntry| entry.into())
+                .collect::<Vec<UIRecordEntry>>();
+
+            // The plan dialog shows the reloaded entry, e.g. after an undo
+            let plan_uuid = ui.global::<Store>().get_record_plan_entry().uuid;
+            if let Some(entry) = entries.iter().find(|entry| entry.uuid == plan_uuid) {
+                ui.global::<Store>().set_record_plan_entry(entry.clone());
+            }
+
+            store_current_record_entries!(ui).set_vec(entries);
+            sort_record_entries(&ui);
+        });
+    });
+}
 
+// Sorts the shown entries and the cache of the search by the selected key
+// The matches are found by the full text search of the store. The list keeps
+// the shown project and the sort order.
+fn search_db_entries(ui: &AppWindow, keyword: SharedString, query: Query) {
+    let ui = ui.as_weak();
+    spawn(async move {
+        let entries = match TaskStore::new().search_records(&query).await {
+            Ok(entries) => entries,
             Err(e) => {
                 log::warn!("{:?}", e);
                 vec![]
@@ -412,138 +630,439 @@ fn record_init(ui: &AppWindow) {
         };
 
         _ = slint::invoke_from_event_loop(move || {
+            let ui = ui.unwrap();
+
+            // A newer keyword is searched already
+            if ui.global::<Store>().get_record_search_keyword() != keyword {
+                return;
+            }
+
+            let (filter, projects) = (project::filter(&ui), project::projects(&ui));
             let entries = entries
                 .into_iter()
-                .map(|mut entry: RecordEntry| {
-                    if let Some(state) = calc_state(
-                        entry.start_date.as_str(),
-                        entry.end_date.as_str(),
-                        entry.state,
-                    ) {
-                        entry.state = state;
-                    }
-                    entry
-                })
+                .filter(|entry| filter.matches(entry, &projects))
                 .map(|entry: RecordEntry| entry.into())
-                .rev()
                 .collect::<Vec<UIRecordEntry>>();
 
-            store_current_record_entries!(ui.unwrap()).set_vec(entries);
+            store_current_record_entries!(ui).set_vec(entries);
+            refresh_dependencies(&ui);
+            sort_record_entries(&ui);
         });
     });
 }
 
-fn calc_state(
-    start_date: &str,
-    end_date: &str,
-    current_state: UIRecordState,
-) -> Option<UIRecordState> {
-    match current_state {
-        UIRecordState::Giveup | UIRecordState::Finished => None,
-        UIRecordState::NotStarted => match cutil::time::date_str_to_timestamp(end_date) {
-            Ok(end_timestamp) => {
-                let current_timestamp = cutil::time::timestamp();
-                if current_timestamp > end_timestamp {
-                    Some(UIRecordState::Timeout)
-                } else {
-                    match cutil::time::date_str_to_timestamp(start_date) {
-                        Ok(start_timestamp) => {
-                            if current_timestamp >= start_timestamp {
-                                Some(UIRecordState::Running)
-                            } else {
-                                None
-                            }
-                        }
-                        _ => None,
-                    }
-                }
+pub fn sort_record_entries(ui: &AppWindow) {
+    let key = field::sort_key(ui);
+    if key == SortKey::Default {
+        return;
+    }
+
+    let fields = field::fields(ui);
+    for model in [
+        store_current_record_entries!(ui),
+        store_current_record_entries_cache!(ui),
+    ] {
+        let mut entries = model
+            .iter()
+            .map(|entry| entry.into())
+            .collect::<Vec<RecordEntry>>();
+
+        sort::sort(&mut entries, &key, &fields);
+        model.set_vec(
+            entries
+                .into_iter()
+                .map(|entry| entry.into())
+                .collect::<Vec<UIRecordEntry>>(),
+        );
+    }
+}
+
+// All the records, the list only shows the matched ones while searching
+fn current_entries(ui: &AppWindow) -> Vec<RecordEntry> {
+    if store_current_record_entries_cache!(ui).row_count() > 0 {
+        store_current_record_entries_cache!(ui)
+            .iter()
+            .map(|entry| entry.into())
+            .collect()
+    } else {
+        store_current_record_entries!(ui)
+            .iter()
+            .map(|entry| entry.into())
+            .collect()
+    }
+}
+
+// The blocked flags depend on the other records, so they are filled again
+// after the list changes
+pub fn refresh_dependencies(ui: &AppWindow) {
+    let mut entries = current_entries(ui);
+    dependency::fill(&mut entries);
+
+    for model in [
+        store_current_record_entries!(ui),
+        store_current_record_entries_cache!(ui),
+    ] {
+        let items = model.iter().collect::<Vec<_>>();
+
+        for (index, mut item) in items.into_iter().enumerate() {
+            if let Some(entry) = entries.iter().find(|entry| item.uuid == entry.uuid)
+                && (item.is_blocked != entry.is_blocked
+                    || item.blocking_count as i64 != entry.blocking_count)
+            {
+                item.is_blocked = entry.is_blocked;
+                item.blocking_count = entry.blocking_count as i32;
+                model.set_row_data(index, item);
             }
-            _ => None,
-        },
-        UIRecordState::Running => match cutil::time::date_str_to_timestamp(start_date) {
-            Ok(start_timestamp) => {
-                let current_timestamp = cutil::time::timestamp();
-                if current_timestamp < start_timestamp {
-                    Some(UIRecordState::NotStarted)
-                } else {
-                    match cutil::time::date_str_to_timestamp(end_date) {
-                        Ok(end_timestamp) => {
-                            if current_timestamp > end_timestamp {
-                                Some(UIRecordState::Timeout)
-                            } else {
-                                None
-                            }
-                        }
-                        _ => None,
-                    }
-                }
+        }
+    }
+}
+
+// Removes the links to `uuid` from the shown records, the same as the store
+// does. Returns the uuids of the unlinked records.
+fn unlink_entries(ui: &AppWindow, uuid: &str) -> Vec<String> {
+    let mut uuids = vec![];
+
+    for model in [
+        store_current_record_entries!(ui),
+        store_current_record_entries_cache!(ui),
+    ] {
+        let items = model.iter().collect::<Vec<_>>();
+
+        for (index, item) in items.into_iter().enumerate() {
+            let mut entry: RecordEntry = item.into();
+            if !entry.blocked_by.iter().any(|item| item == uuid) {
+                continue;
             }
-            _ => None,
-        },
-        UIRecordState::Timeout => match cutil::time::date_str_to_timestamp(start_date) {
-            Ok(start_timestamp) => {
-                let current_timestamp = cutil::time::timestamp();
-                if current_timestamp < start_timestamp {
-                    Some(UIRecordState::NotStarted)
-                } else {
-                    match cutil::time::date_str_to_timestamp(end_date) {
-                        Ok(end_timestamp) => {
-                            if current_timestamp <= end_timestamp {
-                                Some(UIRecordState::Running)
-                            } else {
-                                None
-                            }
-                        }
-                        _ => None,
+
+            entry.blocked_by.retain(|item| item != uuid);
+            if !uuids.contains(&entry.uuid) {
+                uuids.push(entry.uuid.clone());
+            }
+            model.set_row_data(index, entry.into());
+        }
+    }
+
+    uuids
+}
+
+// Undoes `unlink_entries` for the `dependents`
+fn relink_entries(ui: &AppWindow, uuid: &str, dependents: &[String]) {
+    for model in [
+        store_current_record_entries!(ui),
+        store_current_record_entries_cache!(ui),
+    ] {
+        let items = model.iter().collect::<Vec<_>>();
+
+        for (index, item) in items.into_iter().enumerate() {
+            let mut entry: RecordEntry = item.into();
+            if !dependents.contains(&entry.uuid) || entry.blocked_by.iter().any(|item| item == uuid)
+            {
+                continue;
+            }
+
+            entry.blocked_by.push(uuid.to_string());
+            model.set_row_data(index, entry.into());
+        }
+    }
+}
+
+// The other records which the edited record can be blocked by
+fn set_link_options(ui: &AppWindow, uuid: &str) {
+    let entries = current_entries(ui)
+        .into_iter()
+        .filter(|entry| entry.uuid != uuid)
+        .collect::<Vec<_>>();
+
+    let titles = entries
+        .iter()
+        .map(|entry| entry.title.clone().into())
+        .collect::<Vec<SharedString>>();
+    let uuids = entries
+        .into_iter()
+        .map(|entry| entry.uuid.into())
+        .collect::<Vec<SharedString>>();
+
+    ui.global::<Store>()
+        .set_record_link_options(ModelRc::new(VecModel::from(titles)));
+    ui.global::<Store>()
+        .set_record_link_uuids(ModelRc::new(VecModel::from(uuids)));
+}
+
+// Only updates the tracked time of the entries, so the edits which are still
+// being saved are not overwritten.
+fn refresh_tracked_time(ui: &AppWindow) {
+    let ui = ui.as_weak();
+    spawn(async move {
+        let entries = match TaskStore::new().records().await {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::warn!("{:?}", e);
+                return;
+            }
+        };
+
+        _ = slint::invoke_from_event_loop(move || {
+            let ui = ui.unwrap();
+            ui.global::<Store>()
+                .set_timing_record_uuid(timing_record_uuid(&entries).into());
+
+            for model in [
+                store_current_record_entries!(ui),
+                store_current_record_entries_cache!(ui),
+            ] {
+                let items = model.iter().collect::<Vec<_>>();
+
+                for (index, mut item) in items.into_iter().enumerate() {
+                    if let Some(entry) = entries.iter().find(|entry| item.uuid == entry.uuid) {
+                        item.tracked_seconds = entry.tracked_seconds as i32;
+                        item.is_timing = entry.is_timing;
+                        model.set_row_data(index, item);
                     }
                 }
             }
-            _ => None,
-        },
+        });
+    });
+}
+
+// The timeline of the record edit dialog
+fn load_record_changes(ui: &AppWindow, uuid: String) {
+    let ui = ui.as_weak();
+    spawn(async move {
+        let changes = match TaskStore::new().changes(&uuid).await {
+            Ok(changes) => changes,
+            Err(e) => {
+                log::warn!("{:?}", e);
+                vec![]
+            }
+        };
+
+        _ = slint::invoke_from_event_loop(move || {
+            let ui = ui.unwrap();
+
+            // The dialog may show another record by now
+            if ui.global::<Store>().get_edit_record_entry().uuid != uuid {
+                return;
+            }
+
+            let projects = project::projects(&ui);
+            let items = changes
+                .into_iter()
+                .rev()
+                .map(|change| record_change(change, &projects))
+                .collect::<Vec<_>>();
+
+            ui.global::<Store>()
+                .set_record_changes(ModelRc::new(VecModel::from(items)));
+        });
+    });
+}
+
+// A removed project is shown by its uuid
+fn record_change(change: ChangeEntry, projects: &[ProjectEntry]) -> UIRecordChange {
+    let field = match change.field.as_str() {
+        change::FIELD_TITLE => "Task name",
+        change::FIELD_START_DATE => "Start date",
+        change::FIELD_END_DATE => "End date",
+        change::FIELD_STATE => "State",
+        change::FIELD_TAGS => "Tags",
+        change::FIELD_RECURRENCE => "Repeat",
+        change::FIELD_PLAN => "Plan",
+        change::FIELD_PRIORITY => "Priority",
+        change::FIELD_ESTIMATE => "Estimate",
+        change::FIELD_BLOCKED_BY => "Blocked by",
+        change::FIELD_PROJECT => "Project",
+        field => field
+            .strip_prefix(change::CUSTOM_FIELD_PREFIX)
+            .unwrap_or(field),
+    };
+
+    let value = |value: String| {
+        if change.field == change::FIELD_STATE || change.field == change::FIELD_PRIORITY {
+            tr(&value)
+        } else if change.field == change::FIELD_PROJECT {
+            core_project::find_by_uuid(projects, &value).map_or(value, |item| item.name.clone())
+        } else {
+            value
+        }
+    };
+
+    UIRecordChange {
+        time: cutil::time::timestamp_to_local(change.timestamp, "%Y-%m-%d %H:%M").into(),
+        field: tr(field).into(),
+        old_value: value(change.old_value.clone()).into(),
+        new_value: value(change.new_value.clone()).into(),
+    }
+}
+
+fn timing_record_uuid(entries: &[RecordEntry]) -> String {
+    entries
+        .iter()
+        .find(|entry| entry.is_timing)
+        .map(|entry| entry.uuid.clone())
+        .unwrap_or_default()
+}
+
+// The repeat rule moves from `entry` to its next occurrence. The returned ops
+// add it in the same transaction as `entry`.
+fn next_occurrence(ui: &AppWindow, entry: &mut RecordEntry) -> (Option<RecordEntry>, Vec<WriteOp>) {
+    match TaskStore::recur(entry) {
+        Ok(item) => item,
+        Err(e) => {
+            toast_warn!(
+                ui,
+                format!("{}. {}: {e}", tr("Invalid repeat rule"), tr("Reason"))
+            );
+            (None, vec![])
+        }
+    }
+}
+
+// The next occurrence is shown first, the same as a new record
+fn show_next_occurrence(ui: &AppWindow, next: Option<&RecordEntry>) {
+    if let Some(next) = next {
+        store_current_record_entries!(ui).insert(0, next.clone().into());
+    }
+}
+
+fn remove_next_occurrence(ui: &AppWindow, next: Option<&RecordEntry>) {
+    if let Some(next) = next
+        && let Some(index) = store_current_record_entries!(ui)
+            .iter()
+            .position(|item| item.uuid == next.uuid)
+    {
+        store_current_record_entries!(ui).remove(index);
     }
 }
 
 fn add_db_entry(ui: &AppWindow, entry: RecordEntry) {
     let ui = ui.as_weak();
-    tokio::spawn(async move {
-        let data = serde_json::to_string(&entry).unwrap();
-        match db::entry::insert(DB_TABLE, &entry.uuid, &data).await {
-            Err(e) => toast::async_toast_warn(
+    spawn(async move {
+        let store = TaskStore::new();
+        let uuids = vec![entry.uuid.clone()];
+        if let Err(e) = history::track(tr("Add entry"), uuids, store.add(entry)).await {
+            toast::async_toast_warn(
                 ui,
                 format!("{}. {}: {e:?}", tr("Add entry failed"), tr("Reason")),
-            ),
-            _ => (),
+            )
         }
     });
 }
 
-fn update_db_entry(ui: &AppWindow, entry: RecordEntry) {
+// `ops` add `next`, the next occurrence of a finished repeating record. The
+// next occurrence is removed from the list if the update fails.
+fn update_db_entry(
+    ui: &AppWindow,
+    entry: RecordEntry,
+    next: Option<RecordEntry>,
+    ops: Vec<WriteOp>,
+) {
     let ui = ui.as_weak();
-    tokio::spawn(async move {
-        let data = serde_json::to_string(&entry).unwrap();
-        match db::entry::update(DB_TABLE, &entry.uuid, &data).await {
-            Err(e) => toast::async_toast_warn(
-                ui,
-                format!("{}. {}: {e:?}", tr("Update entry failed"), tr("Reason")),
-            ),
-            _ => (),
+    spawn(async move {
+        let store = TaskStore::new();
+        let mut uuids = vec![entry.uuid.clone()];
+        uuids.extend(next.iter().map(|item| item.uuid.clone()));
+
+        let write = store.update_with(entry, ops);
+        if let Err(e) = history::track(tr("Update entry"), uuids, write).await {
+            _ = slint::invoke_from_event_loop(move || {
+                let ui = ui.unwrap();
+                remove_next_occurrence(&ui, next.as_ref());
+                toast_warn!(
+                    ui,
+                    format!("{}. {}: {e:?}", tr("Update entry failed"), tr("Reason"))
+                );
+            });
         }
     });
 }
 
-pub fn delete_db_entry(ui: &AppWindow, uuid: String) {
+// The links of the `dependents` are removed with the trash, an undo restores
+// them too
+fn delete_db_entry(ui: &AppWindow, uuid: String, dependents: Vec<String>) {
     let ui = ui.as_weak();
-    tokio::spawn(async move {
-        match db::entry::delete(DB_TABLE, uuid.as_str()).await {
-            Err(e) => toast::async_toast_warn(
+    spawn(async move {
+        let store = TaskStore::new();
+        let mut uuids = vec![uuid.clone()];
+        uuids.extend(dependents);
+
+        let write = async {
+            let (_, ops) = store.unlink_ops(&uuid).await?;
+            store.remove(&uuid, ops).await
+        };
+
+        if let Err(e) = history::track(tr("Remove entry"), uuids, write).await {
+            toast::async_toast_warn(
                 ui,
                 format!("{}. {}: {e:?}", tr("Remove entry failed"), tr("Reason")),
-            ),
-            _ => (),
+            )
+        }
+    });
+}
+
+// The record is put back to `index` of the list if the archive fails, and its
+// next occurrence which `ops` add is removed
+fn archive_db_entry(
+    ui: &AppWindow,
+    entry: RecordEntry,
+    origin: RecordEntry,
+    index: usize,
+    dependents: Vec<String>,
+    (next, ops): (Option<RecordEntry>, Vec<WriteOp>),
+) {
+    let ui = ui.as_weak();
+    spawn(async move {
+        let store = TaskStore::new();
+        let mut uuids = vec![entry.uuid.clone()];
+        uuids.extend(dependents.clone());
+        uuids.extend(next.iter().map(|item| item.uuid.clone()));
+
+        let write = async {
+            let (_, mut unlink) = store.unlink_ops(&entry.uuid).await?;
+            unlink.extend(ops);
+            store.archive(entry, unlink).await
+        };
+
+        if let Err(e) = history::track(tr("Archive entry"), uuids, write).await {
+            _ = slint::invoke_from_event_loop(move || {
+                let ui = ui.unwrap();
+                rollback_archive(&ui, origin, index, &dependents, next.as_ref());
+                toast_warn!(
+                    ui,
+                    format!("{}. {}: {e:?}", tr("Archive entry failed"), tr("Reason"))
+                );
+            });
         }
     });
 }
 
+// The links of the `dependents` are kept by the failed transaction
+fn rollback_archive(
+    ui: &AppWindow,
+    origin: RecordEntry,
+    index: usize,
+    dependents: &[String],
+    next: Option<&RecordEntry>,
+) {
+    remove_next_occurrence(ui, next);
+    relink_entries(ui, &origin.uuid, dependents);
+
+    if let Some(index) = store_current_archive_entries!(ui)
+        .iter()
+        .position(|item| item.uuid == origin.uuid)
+    {
+        store_current_archive_entries!(ui).remove(index);
+    }
+
+    if origin.is_timing {
+        ui.global::<Store>()
+            .set_timing_record_uuid(origin.uuid.clone().into());
+    }
+
+    let index = index.min(store_current_record_entries!(ui).row_count());
+    store_current_record_entries!(ui).insert(index, origin.into());
+    refresh_dependencies(ui);
+}
+
 fn ai_generate_record_plans(ui: &AppWindow) {
     let entry = ui.global::<Store>().get_record_plan_entry();
 
@@ -553,7 +1072,7 @@ fn ai_generate_record_plans(ui: &AppWindow) {
             let uuid = entry.uuid.to_string();
             let task = entry.title.to_string();
 
-            tokio::spawn(async move {
+            spawn(async move {
                 match send_record_plan_question_to_ai(ui.clone(), days as u32, &task).await {
                     Ok(plans) => {
                         _ = slint::invoke_from_event_loop(move || {
@@ -615,11 +1134,22 @@ fn ai_generate_record_plans(ui: &AppWindow) {
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 async fn send_record_plan_question_to_ai(
     ui: Weak<AppWindow>,
     days: u32,
     task: &str,
 ) -> Result<Vec<String>> {
+    use crate::config;
+    use async_openai::{
+        Client,
+        types::{
+            ChatCompletionRequestSystemMessageArgs, ChatCompletionRequestUserMessageArgs,
+            CreateChatCompletionRequestArgs,
+        },
+    };
+    use regex::Regex;
+
     let preference_setting = config::preference();
     let model_setting = config::model();
     let re = Regex::new(r"(?s)```[\s]*(.*?)[\s]*```").unwrap();
@@ -628,10 +1158,7 @@ async fn send_record_plan_question_to_ai(
         || model_setting.model_name.is_empty()
         || model_setting.api_base_url.is_empty()
     {
-        toast::async_toast_info(
-            ui,
-            format!("{}", tr("Please configure model setting firstly")),
-        );
+        toast::async_toast_info(ui, tr("Please configure model setting firstly").to_string());
     }
 
     let is_cn = preference_setting.language == "cn";
@@ -659,8 +1186,7 @@ Output format:
         serde_json::json!({
             "days": days,
             "task": task,
-        })
-        .to_string(),
+        }),
         if is_cn { "Chinese" } else { "English" }
     );
 
@@ -685,8 +1211,7 @@ Output format:
 
     let content = response
         .choices
-        .iter()
-        .next()
+        .first()
         .ok_or(anyhow::anyhow!("No response content"))?
         .message
         .content
@@ -701,9 +1226,19 @@ Output format:
         .filter_map(|cap| cap.get(1).map(|m| m.as_str().trim().to_string()))
         .collect::<Vec<_>>();
 
-    if task.len() > 0 {
+    if !task.is_empty() {
         Ok(serde_json::from_str::<Vec<String>>(&task[0])?)
     } else {
         Err(anyhow::anyhow!("No response content"))
     }
 }
+
+// The model setting lives in the config file, which the browser build has not
+#[cfg(target_arch = "wasm32")]
+async fn send_record_plan_question_to_ai(
+    _ui: Weak<AppWindow>,
+    _days: u32,
+    _task: &str,
+) -> Result<Vec<String>> {
+    anyhow::bail!("generating task plans is not supported in the browser")
+}
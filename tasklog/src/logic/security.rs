@@ -0,0 +1,106 @@
+use super::{archive, record, toast, tr::tr};
+use crate::{
+    core::security,
+    slint_generatedAppWindow::{AppWindow, Logic, Store},
+    toast_success, toast_warn,
+};
+use slint::ComponentHandle;
+
+pub fn init(ui: &AppWindow) {
+    update_status(ui);
+
+    if ui.global::<Store>().get_is_secrets_locked() {
+        toast_warn!(
+            ui,
+            tr("The secrets are locked, enter the master password in the data setting")
+        );
+    }
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_unlock_secrets(move |password| {
+        let ui = ui_handle.unwrap();
+
+        match security::unlock(&password) {
+            Ok(_) => {
+                update_status(&ui);
+                reload(&ui);
+                toast_success!(ui, tr("Unlock successfully"));
+            }
+            Err(e) => toast_warn!(
+                ui,
+                format!("{}. {}: {e:?}", tr("Unlock failed"), tr("Reason"))
+            ),
+        }
+    });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>()
+        .on_set_master_password(move |password| {
+            let ui = ui_handle.unwrap().as_weak();
+
+            tokio::spawn(async move {
+                // An empty password switches back to the key file
+                let result = if password.is_empty() {
+                    security::set_master_password(None, None).await
+                } else {
+                    security::set_master_password(Some(&password), None).await
+                };
+
+                match result {
+                    Ok(_) => {
+                        _ = slint::invoke_from_event_loop(move || {
+                            let ui = ui.unwrap();
+                            update_status(&ui);
+                            toast_success!(ui, tr("Save successfully"));
+                        });
+                    }
+                    Err(e) => toast::async_toast_warn(
+                        ui,
+                        format!("{}. {}: {e:?}", tr("Save failed"), tr("Reason")),
+                    ),
+                }
+            });
+        });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>()
+        .on_set_database_encryption(move |enabled| {
+            let ui = ui_handle.unwrap().as_weak();
+
+            tokio::spawn(async move {
+                let result = security::set_database_encryption(enabled).await;
+
+                _ = slint::invoke_from_event_loop(move || {
+                    let ui = ui.unwrap();
+                    update_status(&ui);
+
+                    match result {
+                        Ok(counts) => {
+                            toast_success!(ui, format!("{}: {counts}", tr("Rewritten entries")))
+                        }
+                        Err(e) => toast_warn!(
+                            ui,
+                            format!("{}. {}: {e:?}", tr("Save failed"), tr("Reason"))
+                        ),
+                    }
+                });
+            });
+        });
+}
+
+fn update_status(ui: &AppWindow) {
+    let status = security::status();
+
+    ui.global::<Store>().set_is_secrets_locked(status.is_locked);
+    ui.global::<Store>()
+        .set_is_master_password(status.is_master_password);
+    ui.global::<Store>()
+        .set_is_database_encrypted(status.is_database_encrypted);
+}
+
+// The encrypted entries can not be loaded before the secrets are unlocked
+fn reload(ui: &AppWindow) {
+    record::record_init(ui);
+    archive::archive_init(ui);
+    ui.global::<Logic>().invoke_corrupt_init();
+}
@@ -2,6 +2,7 @@ use super::tr::tr;
 use crate::{
     config,
     slint_generatedAppWindow::{AppWindow, Logic, SettingModel, Store, Theme},
+    toast_warn,
 };
 use slint::ComponentHandle;
 
@@ -42,6 +43,10 @@ pub fn init(ui: &AppWindow) {
             all.preference.always_on_top = setting.always_on_top;
             all.preference.no_frame = setting.no_frame;
             all.preference.is_dark = setting.is_dark;
+            all.preference.trash_retention_days = setting
+                .trash_retention_days
+                .parse()
+                .unwrap_or(all.preference.trash_retention_days);
             _ = config::save(all);
 
             if cfg!(feature = "desktop") && !ui.window().is_maximized() {
@@ -87,12 +92,21 @@ pub fn init(ui: &AppWindow) {
         }
     });
 
+    let ui_handle = ui.as_weak();
     ui.global::<Logic>().on_set_setting_model(move |setting| {
         let mut all = config::all();
         all.model.api_base_url = setting.api_base_url.into();
         all.model.model_name = setting.model_name.into();
         all.model.api_key = setting.api_key.into();
-        _ = config::save(all);
+
+        // Fails if the api key can not be sealed, e.g. the secrets are locked
+        if let Err(e) = config::save(all) {
+            let ui = ui_handle.unwrap();
+            toast_warn!(
+                ui,
+                format!("{}. {}: {e:?}", tr("Save failed"), tr("Reason"))
+            );
+        }
     });
 }
 
@@ -109,6 +123,7 @@ fn init_setting(ui: &AppWindow) {
     setting.always_on_top = config.always_on_top;
     setting.no_frame = config.no_frame;
     setting.is_dark = config.is_dark;
+    setting.trash_retention_days = slint::format!("{}", config.trash_retention_days);
 
     ui.global::<Theme>().invoke_set_dark(config.is_dark);
     ui.global::<Store>().set_setting_preference(setting);
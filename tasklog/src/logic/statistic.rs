@@ -1,15 +1,24 @@
-use super::tr::tr;
+use super::{project, spawn, tr::tr};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::config::{self, Statistic as ScopeSetting};
 use crate::{
-    db::{
-        self,
-        def::{RecordEntry, ARCHIVE_TABLE, RECORD_TABLE},
+    core::{
+        TaskStore,
+        project::Filter,
+        record,
+        stats::{self, Aggregate, Range, Scope},
     },
+    db::def::{RecordEntry, RecordPriority, RecordState},
     slint_generatedAppWindow::{
-        AppWindow, ChartBarEntry as UIChartBarEntry, Logic, RecordEntry as UIRecordEntry,
-        RecordState as UIRecordState, Store,
+        AppWindow, ChartBarEntry as UIChartBarEntry, HeatmapEntry as UIHeatmapEntry, Logic,
+        RecordEntry as UIRecordEntry, RecordPriority as UIRecordPriority,
+        RecordState as UIRecordState, StatisticScope, Store,
     },
+    toast_warn,
 };
-use slint::{Brush::SolidColor, ComponentHandle, Model, ModelRc, VecModel};
+use anyhow::Result;
+use chrono::NaiveDate;
+use slint::{Brush::SolidColor, Color, ComponentHandle, Model, ModelRc, SharedString, VecModel};
 
 #[macro_export]
 macro_rules! store_statistic_entries {
@@ -23,179 +32,204 @@ macro_rules! store_statistic_entries {
 }
 
 pub fn init(ui: &AppWindow) {
+    restore_scope(ui);
+
     let ui_handle = ui.as_weak();
     ui.global::<Logic>().on_statistic_init(move || {
         let ui = ui_handle.unwrap();
         statistic_init(&ui);
     });
 
+    let ui_handle = ui.as_weak();
     ui.global::<Logic>()
-        .on_statistic_total_days_spent(move |entries| {
-            let mut days = 0;
-            let current_timestamp = cutil::time::timestamp();
-            let day_seconds = 24 * 60 * 60;
+        .on_statistic_switch_project(move |index| {
+            let ui = ui_handle.unwrap();
+            ui.global::<Store>().set_statistic_project_index(index);
+            ui.global::<Store>()
+                .set_statistic_project_uuid(project::statistic_project_uuid(&ui, index).into());
+            save_scope(&ui);
+            statistic_init(&ui);
+        });
 
-            for entry in entries.iter() {
-                let start_timestamp = cutil::time::date_str_to_timestamp(&entry.start_date);
-                let end_timestamp = cutil::time::date_str_to_timestamp(&entry.end_date);
-                if start_timestamp.is_err() || end_timestamp.is_err() {
-                    continue;
-                }
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_statistic_set_range(move |index| {
+        let ui = ui_handle.unwrap();
+        let mut scope = ui.global::<Store>().get_statistic_scope();
+        scope.range = index;
+        ui.global::<Store>().set_statistic_scope(scope);
+        save_scope(&ui);
+        statistic_init(&ui);
+    });
 
-                let start_timestamp = start_timestamp.unwrap();
-                let end_timestamp = end_timestamp.unwrap();
-                if current_timestamp < start_timestamp {
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>()
+        .on_statistic_set_custom_range(move |start_date, end_date| {
+            let ui = ui_handle.unwrap();
+
+            // An empty date leaves that side of the range open
+            let mut dates = vec![];
+            for date in [start_date.trim(), end_date.trim()] {
+                if date.is_empty() {
+                    dates.push(String::default());
                     continue;
                 }
 
-                if current_timestamp < end_timestamp {
-                    days += (current_timestamp - start_timestamp) / day_seconds;
-                } else {
-                    days += (end_timestamp - start_timestamp) / day_seconds;
+                match cutil::time::date_str_add_days(date, 0) {
+                    Ok(date) => dates.push(date),
+                    _ => {
+                        toast_warn!(ui, format!("{}: {date}", tr("Invalid date")));
+                        return;
+                    }
                 }
             }
 
-            return days as i32;
+            let mut scope = ui.global::<Store>().get_statistic_scope();
+            scope.start_date = dates[0].clone().into();
+            scope.end_date = dates[1].clone().into();
+            ui.global::<Store>().set_statistic_scope(scope);
+            save_scope(&ui);
+            statistic_init(&ui);
         });
 
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_statistic_toggle_tag(move |tag| {
+        let ui = ui_handle.unwrap();
+        let mut scope = ui.global::<Store>().get_statistic_scope();
+        let mut tags = scope.tags.iter().collect::<Vec<_>>();
+        match tags.iter().position(|item| *item == tag) {
+            Some(index) => _ = tags.remove(index),
+            None => tags.push(tag),
+        }
+        scope.tags = ModelRc::new(VecModel::from(tags));
+        ui.global::<Store>().set_statistic_scope(scope);
+        save_scope(&ui);
+        statistic_init(&ui);
+    });
+
     let ui_handle = ui.as_weak();
     ui.global::<Logic>()
-        .on_statistic_chart_tasks_count(move |entries| {
+        .on_statistic_toggle_state(move |state| {
             let ui = ui_handle.unwrap();
+            let mut scope = ui.global::<Store>().get_statistic_scope();
+            let mut states = scope.states.iter().collect::<Vec<_>>();
+            match states.iter().position(|item| *item == state) {
+                Some(index) => _ = states.remove(index),
+                None => states.push(state),
+            }
+            scope.states = ModelRc::new(VecModel::from(states));
+            ui.global::<Store>().set_statistic_scope(scope);
+            save_scope(&ui);
+            statistic_init(&ui);
+        });
 
-            let mut items = vec![
-                UIChartBarEntry {
-                    label: tr("NotStarted").into(),
-                    value: 0,
-                    color: SolidColor(
-                        ui.global::<Logic>()
-                            .invoke_state_color(UIRecordState::NotStarted),
-                    ),
-                },
-                UIChartBarEntry {
-                    label: tr("Running").into(),
-                    value: 0,
-                    color: SolidColor(
-                        ui.global::<Logic>()
-                            .invoke_state_color(UIRecordState::Running),
-                    ),
-                },
-                UIChartBarEntry {
-                    label: tr("Finished").into(),
-                    value: 0,
-                    color: SolidColor(
-                        ui.global::<Logic>()
-                            .invoke_state_color(UIRecordState::Finished),
-                    ),
-                },
-                UIChartBarEntry {
-                    label: tr("Giveup").into(),
-                    value: 0,
-                    color: SolidColor(
-                        ui.global::<Logic>()
-                            .invoke_state_color(UIRecordState::Giveup),
-                    ),
-                },
-                UIChartBarEntry {
-                    label: tr("Timeout").into(),
-                    value: 0,
-                    color: SolidColor(
-                        ui.global::<Logic>()
-                            .invoke_state_color(UIRecordState::Timeout),
-                    ),
-                },
+    ui.global::<Logic>()
+        .on_statistic_tag_selected(|tags, tag| tags.iter().any(|item| item == tag));
+
+    ui.global::<Logic>()
+        .on_statistic_state_selected(|states, state| states.iter().any(|item| item == state));
+
+    ui.global::<Logic>()
+        .on_statistic_total_days_spent(move |entries| {
+            stats::aggregate(&record_entries(entries), cutil::time::timestamp())
+                .days
+                .total() as i32
+        });
+
+    ui.global::<Logic>()
+        .on_statistic_total_hours_tracked(move |entries| {
+            let seconds = entries
+                .iter()
+                .map(|entry| entry.tracked_seconds as i64)
+                .sum::<i64>();
+
+            hours(seconds)
+        });
+
+    ui.global::<Logic>()
+        .on_statistic_total_deadline_extensions(move |entries| {
+            entries
+                .iter()
+                .map(|entry| entry.deadline_extensions)
+                .sum::<i32>()
+        });
+
+    ui.global::<Logic>()
+        .on_statistic_total_hours_estimated(move |entries| {
+            let seconds = entries
+                .iter()
+                .map(|entry| entry.estimate_seconds as i64)
+                .sum::<i64>();
+
+            hours(seconds)
+        });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>()
+        .on_statistic_chart_tasks_priority(move |entries| {
+            let ui = ui_handle.unwrap();
+
+            let priorities = [
+                UIRecordPriority::None,
+                UIRecordPriority::Low,
+                UIRecordPriority::Medium,
+                UIRecordPriority::High,
+                UIRecordPriority::Urgent,
             ];
 
+            let mut counts = [0; 5];
             for entry in entries.iter() {
-                let index = match entry.state {
-                    UIRecordState::NotStarted => 0,
-                    UIRecordState::Running => 1,
-                    UIRecordState::Finished => 2,
-                    UIRecordState::Giveup => 3,
-                    UIRecordState::Timeout => 4,
-                };
-
-                items[index].value += 1;
+                if let Some(index) = priorities
+                    .iter()
+                    .position(|priority| *priority == entry.priority)
+                {
+                    counts[index] += 1;
+                }
             }
 
+            let items = priorities
+                .into_iter()
+                .zip(counts)
+                .map(|(priority, count)| UIChartBarEntry {
+                    label: tr(RecordPriority::from(priority).as_str()).into(),
+                    value: count,
+                    color: SolidColor(ui.global::<Logic>().invoke_priority_color(priority)),
+                })
+                .collect::<Vec<_>>();
+
             ModelRc::new(VecModel::from_slice(&items))
         });
 
+    // The records without a project or in a removed project come last
     let ui_handle = ui.as_weak();
     ui.global::<Logic>()
-        .on_statistic_chart_days_spent(move |entries| {
+        .on_statistic_chart_tasks_project(move |entries| {
             let ui = ui_handle.unwrap();
+            let projects = ui.global::<Store>().get_project_entries();
 
-            let mut items = vec![
-                UIChartBarEntry {
-                    label: tr("Running").into(),
-                    value: 0,
-                    color: SolidColor(
-                        ui.global::<Logic>()
-                            .invoke_state_color(UIRecordState::Running),
-                    ),
-                },
-                UIChartBarEntry {
-                    label: tr("Finished").into(),
+            let mut items = projects
+                .iter()
+                .map(|entry| UIChartBarEntry {
+                    label: entry.name,
                     value: 0,
-                    color: SolidColor(
-                        ui.global::<Logic>()
-                            .invoke_state_color(UIRecordState::Finished),
-                    ),
-                },
-                UIChartBarEntry {
-                    label: tr("Giveup").into(),
-                    value: 0,
-                    color: SolidColor(
-                        ui.global::<Logic>()
-                            .invoke_state_color(UIRecordState::Giveup),
-                    ),
-                },
-                UIChartBarEntry {
-                    label: tr("Timeout").into(),
-                    value: 0,
-                    color: SolidColor(
-                        ui.global::<Logic>()
-                            .invoke_state_color(UIRecordState::Timeout),
-                    ),
-                },
-            ];
-
-            let current_timestamp = cutil::time::timestamp();
-            let day_seconds = 24 * 60 * 60;
+                    color: SolidColor(entry.color),
+                })
+                .collect::<Vec<_>>();
+            let mut none = UIChartBarEntry {
+                label: tr("No project").into(),
+                value: 0,
+                color: SolidColor(Color::from_rgb_u8(0x9c, 0xa3, 0xaf)),
+            };
 
             for entry in entries.iter() {
-                if entry.state == UIRecordState::NotStarted {
-                    continue;
-                }
-
-                let start_timestamp = cutil::time::date_str_to_timestamp(&entry.start_date);
-                let end_timestamp = cutil::time::date_str_to_timestamp(&entry.end_date);
-                if start_timestamp.is_err() || end_timestamp.is_err() {
-                    continue;
-                }
-
-                let start_timestamp = start_timestamp.unwrap();
-                let end_timestamp = end_timestamp.unwrap();
-                if current_timestamp < start_timestamp {
-                    continue;
+                match projects.iter().position(|item| item.uuid == entry.project) {
+                    Some(index) => items[index].value += 1,
+                    None => none.value += 1,
                 }
+            }
 
-                let days = if current_timestamp < end_timestamp {
-                    (current_timestamp - start_timestamp) / day_seconds
-                } else {
-                    (end_timestamp - start_timestamp) / day_seconds
-                };
-
-                let index = match entry.state {
-                    UIRecordState::Running => 0,
-                    UIRecordState::Finished => 1,
-                    UIRecordState::Giveup => 2,
-                    UIRecordState::Timeout => 3,
-                    _ => unreachable!(),
-                };
-
-                items[index].value += days as i32;
+            items.retain(|item| item.value > 0);
+            if none.value > 0 {
+                items.push(none);
             }
 
             ModelRc::new(VecModel::from_slice(&items))
@@ -203,136 +237,442 @@ pub fn init(ui: &AppWindow) {
 
     let ui_handle = ui.as_weak();
     ui.global::<Logic>()
-        .on_statistic_chart_mean_days_spent(move |entries| {
+        .on_statistic_chart_hours_tracked(move |entries| {
             let ui = ui_handle.unwrap();
+            state_bars(&ui, entries, &stats::STATES, |item| {
+                hours(item.tracked_seconds.total())
+            })
+        });
 
-            let mut counts = vec![0; 4];
-            let mut items = vec![
-                UIChartBarEntry {
-                    label: tr("Running").into(),
-                    value: 0,
-                    color: SolidColor(
-                        ui.global::<Logic>()
-                            .invoke_state_color(UIRecordState::Running),
-                    ),
-                },
-                UIChartBarEntry {
-                    label: tr("Finished").into(),
-                    value: 0,
-                    color: SolidColor(
-                        ui.global::<Logic>()
-                            .invoke_state_color(UIRecordState::Finished),
-                    ),
-                },
-                UIChartBarEntry {
-                    label: tr("Giveup").into(),
-                    value: 0,
-                    color: SolidColor(
-                        ui.global::<Logic>()
-                            .invoke_state_color(UIRecordState::Giveup),
-                    ),
-                },
-                UIChartBarEntry {
-                    label: tr("Timeout").into(),
-                    value: 0,
-                    color: SolidColor(
-                        ui.global::<Logic>()
-                            .invoke_state_color(UIRecordState::Timeout),
-                    ),
-                },
-            ];
+    // A day out of the range is only shown to fill its week
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>()
+        .on_statistic_chart_activity(move |entries| {
+            let ui = ui_handle.unwrap();
+            let (entries, range, today) = chart_inputs(&ui, entries);
+            let (start, end) = stats::window(&range, &entries, today, HEATMAP_DAYS);
+
+            let items = stats::activity(&entries, start, end, today);
+            let max = items
+                .iter()
+                .map(|item| item.started + item.finished)
+                .max()
+                .unwrap_or_default()
+                .max(1);
+
+            let items = items
+                .into_iter()
+                .map(|item| {
+                    let value = item.started + item.finished;
+                    UIHeatmapEntry {
+                        label: format!(
+                            "{}  {} {}  {} {}",
+                            item.date.format(record::DATE_FORMAT),
+                            tr("Started"),
+                            item.started,
+                            tr("Finished"),
+                            item.finished
+                        )
+                        .into(),
+                        value: value as i32,
+                        level: if (start..=end).contains(&item.date) {
+                            (value * 4).div_ceil(max) as i32
+                        } else {
+                            -1
+                        },
+                    }
+                })
+                .collect::<Vec<_>>();
 
-            let current_timestamp = cutil::time::timestamp();
-            let day_seconds = 24 * 60 * 60;
+            ModelRc::new(VecModel::from_slice(&items))
+        });
 
-            for entry in entries.iter() {
-                if entry.state == UIRecordState::NotStarted {
-                    continue;
-                }
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>()
+        .on_statistic_chart_throughput(move |entries| {
+            let ui = ui_handle.unwrap();
+            let (entries, range, today) = chart_inputs(&ui, entries);
+            let (start, end) = stats::window(&range, &entries, today, TREND_DAYS);
+            let color = ui
+                .global::<Logic>()
+                .invoke_state_color(UIRecordState::Finished);
 
-                let start_timestamp = cutil::time::date_str_to_timestamp(&entry.start_date);
-                let end_timestamp = cutil::time::date_str_to_timestamp(&entry.end_date);
-                if start_timestamp.is_err() || end_timestamp.is_err() {
-                    continue;
-                }
+            let items = stats::throughput(&entries, start, end, today)
+                .into_iter()
+                .map(|(week, count)| UIChartBarEntry {
+                    label: week.format("%m-%d").to_string().into(),
+                    value: count as i32,
+                    color: SolidColor(color),
+                })
+                .collect::<Vec<_>>();
 
-                let start_timestamp = start_timestamp.unwrap();
-                let end_timestamp = end_timestamp.unwrap();
-                if current_timestamp < start_timestamp {
-                    continue;
-                }
+            ModelRc::new(VecModel::from_slice(&items))
+        });
 
-                let days = if current_timestamp < end_timestamp {
-                    (current_timestamp - start_timestamp) / day_seconds
-                } else {
-                    (end_timestamp - start_timestamp) / day_seconds
-                };
-
-                let index = match entry.state {
-                    UIRecordState::Running => 0,
-                    UIRecordState::Finished => 1,
-                    UIRecordState::Giveup => 2,
-                    UIRecordState::Timeout => 3,
-                    _ => unreachable!(),
-                };
-
-                counts[index] += 1;
-                items[index].value += days as i32;
-            }
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>()
+        .on_statistic_chart_burndown(move |entries| {
+            let ui = ui_handle.unwrap();
+            let (entries, range, today) = chart_inputs(&ui, entries);
+            let (start, end) = stats::window(&range, &entries, today, TREND_DAYS);
+            let color = ui
+                .global::<Logic>()
+                .invoke_state_color(UIRecordState::Running);
 
-            for index in 0..4 {
-                if counts[index] <= 0 {
-                    continue;
-                }
+            let items = stats::burndown(&entries, start, end, today)
+                .into_iter()
+                .map(|(day, count)| UIChartBarEntry {
+                    label: day.format("%m-%d").to_string().into(),
+                    value: count as i32,
+                    color: SolidColor(color),
+                })
+                .collect::<Vec<_>>();
 
-                items[index].value = items[index].value / counts[index];
-            }
+            ModelRc::new(VecModel::from_slice(&items))
+        });
+
+    ui.global::<Logic>()
+        .on_statistic_on_time_rate(move |entries| {
+            stats::punctuality(&record_entries(entries), chrono::Local::now().date_naive())
+                .on_time_rate()
+                .map_or(0, percent)
+        });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>()
+        .on_statistic_chart_on_time(move |entries| {
+            let ui = ui_handle.unwrap();
+            let (entries, _, today) = chart_inputs(&ui, entries);
+            let item = stats::punctuality(&entries, today);
+
+            let items = [
+                (tr("On time"), item.on_time, UIRecordState::Finished),
+                (tr("Late"), item.late, UIRecordState::Timeout),
+            ]
+            .into_iter()
+            .map(|(label, count, state)| UIChartBarEntry {
+                label: label.into(),
+                value: count as i32,
+                color: SolidColor(ui.global::<Logic>().invoke_state_color(state)),
+            })
+            .collect::<Vec<_>>();
 
             ModelRc::new(VecModel::from_slice(&items))
         });
-}
 
-fn statistic_init(ui: &AppWindow) {
-    store_statistic_entries!(ui).set_vec(vec![]);
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>()
+        .on_statistic_chart_duration_ratio(move |entries| {
+            let ui = ui_handle.unwrap();
+            let (entries, _, today) = chart_inputs(&ui, entries);
+            let item = stats::punctuality(&entries, today);
 
-    let ui = ui.as_weak();
-    tokio::spawn(async move {
-        let mut entries = vec![];
+            // The buckets over the plan are late
+            let items = stats::DURATION_RATIO_LABELS
+                .into_iter()
+                .zip(item.duration_ratios)
+                .enumerate()
+                .map(|(index, (label, count))| UIChartBarEntry {
+                    label: label.into(),
+                    value: count as i32,
+                    color: SolidColor(ui.global::<Logic>().invoke_state_color(if index < 2 {
+                        UIRecordState::Finished
+                    } else {
+                        UIRecordState::Timeout
+                    })),
+                })
+                .collect::<Vec<_>>();
+
+            ModelRc::new(VecModel::from_slice(&items))
+        });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>()
+        .on_statistic_chart_on_time_by_tag(move |entries| {
+            let ui = ui_handle.unwrap();
+            let (entries, _, today) = chart_inputs(&ui, entries);
+            let color = ui
+                .global::<Logic>()
+                .invoke_state_color(UIRecordState::Finished);
 
-        let archive_entries = match db::entry::select_all(ARCHIVE_TABLE).await {
-            Ok(items) => items
+            let items = stats::punctuality_by_tag(&entries, today)
                 .into_iter()
-                .filter_map(|item| serde_json::from_str::<RecordEntry>(&item.data).ok())
-                .collect::<Vec<RecordEntry>>(),
+                .map(|(tag, item)| UIChartBarEntry {
+                    label: tag_label(tag).into(),
+                    value: item.on_time_rate().map_or(0, percent),
+                    color: SolidColor(color),
+                })
+                .collect::<Vec<_>>();
 
-            Err(e) => {
-                log::warn!("{:?}", e);
-                vec![]
-            }
-        };
+            ModelRc::new(VecModel::from_slice(&items))
+        });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>()
+        .on_statistic_chart_slip_by_tag(move |entries| {
+            let ui = ui_handle.unwrap();
+            let (entries, _, today) = chart_inputs(&ui, entries);
+            let color = ui
+                .global::<Logic>()
+                .invoke_state_color(UIRecordState::Timeout);
 
-        let record_entries = match db::entry::select_all(RECORD_TABLE).await {
-            Ok(items) => items
+            let items = stats::punctuality_by_tag(&entries, today)
                 .into_iter()
-                .filter_map(|item| serde_json::from_str::<RecordEntry>(&item.data).ok())
-                .collect::<Vec<RecordEntry>>(),
+                .map(|(tag, item)| UIChartBarEntry {
+                    label: tag_label(tag).into(),
+                    value: item.mean_slip_days().unwrap_or_default().round() as i32,
+                    color: SolidColor(color),
+                })
+                .collect::<Vec<_>>();
 
+            ModelRc::new(VecModel::from_slice(&items))
+        });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>()
+        .on_statistic_chart_tasks_count(move |entries| {
+            let ui = ui_handle.unwrap();
+            state_bars(&ui, entries, &stats::STATES, |item| item.count as i32)
+        });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>()
+        .on_statistic_chart_days_spent(move |entries| {
+            let ui = ui_handle.unwrap();
+            state_bars(&ui, entries, &stats::STATES[1..], |item| {
+                item.days.total() as i32
+            })
+        });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>()
+        .on_statistic_chart_mean_days_spent(move |entries| {
+            let ui = ui_handle.unwrap();
+            state_bars(&ui, entries, &stats::STATES[1..], |item| {
+                item.days.mean().unwrap_or_default().round() as i32
+            })
+        });
+}
+
+// The days of the activity calendar and of the trend charts of an open range
+const HEATMAP_DAYS: i64 = 53 * 7;
+const TREND_DAYS: i64 = 26 * 7;
+
+fn record_entries(entries: ModelRc<UIRecordEntry>) -> Vec<RecordEntry> {
+    entries.iter().map(|entry| entry.into()).collect()
+}
+
+// The records, the range of the scope and today
+fn chart_inputs(
+    ui: &AppWindow,
+    entries: ModelRc<UIRecordEntry>,
+) -> (Vec<RecordEntry>, Range, NaiveDate) {
+    (
+        record_entries(entries),
+        scope(ui).range,
+        chrono::Local::now().date_naive(),
+    )
+}
+
+// A bar of each of the states, the NotStarted one is left out of the charts
+// of the days spent
+fn state_bars(
+    ui: &AppWindow,
+    entries: ModelRc<UIRecordEntry>,
+    states: &[RecordState],
+    value: impl Fn(&Aggregate) -> i32,
+) -> ModelRc<UIChartBarEntry> {
+    let items = stats::by_state(&record_entries(entries), cutil::time::timestamp())
+        .into_iter()
+        .filter(|(state, _)| states.contains(state))
+        .map(|(state, item)| UIChartBarEntry {
+            label: tr(state.as_str()).into(),
+            value: value(&item),
+            color: SolidColor(ui.global::<Logic>().invoke_state_color(state.into())),
+        })
+        .collect::<Vec<_>>();
+
+    ModelRc::new(VecModel::from_slice(&items))
+}
+
+fn percent(ratio: f64) -> i32 {
+    (ratio * 100.0).round() as i32
+}
+
+fn tag_label(tag: String) -> String {
+    if tag.is_empty() { tr("No tag") } else { tag }
+}
+
+// Rounded to the nearest hour
+fn hours(seconds: i64) -> i32 {
+    ((seconds + 1800) / 3600) as i32
+}
+
+// The scope saved in the last session, the project is restored when the
+// projects are loaded
+fn restore_scope(ui: &AppWindow) {
+    let setting = saved_scope();
+    let range = Range::parse(&setting.range, &setting.start_date, &setting.end_date);
+
+    let tags = setting
+        .tags
+        .into_iter()
+        .map(SharedString::from)
+        .collect::<Vec<_>>();
+    let states = setting
+        .states
+        .iter()
+        .filter_map(|name| stats::parse_state(name))
+        .map(UIRecordState::from)
+        .collect::<Vec<_>>();
+
+    ui.global::<Store>().set_statistic_scope(StatisticScope {
+        range: Range::NAMES
+            .iter()
+            .position(|name| *name == range.as_str())
+            .unwrap_or_default() as i32,
+        start_date: setting.start_date.into(),
+        end_date: setting.end_date.into(),
+        tags: ModelRc::new(VecModel::from(tags)),
+        states: ModelRc::new(VecModel::from(states)),
+    });
+
+    match setting.project.as_str() {
+        "" => (),
+        "none" => ui.global::<Store>().set_statistic_project_index(1),
+        uuid => ui.global::<Store>().set_statistic_project_uuid(uuid.into()),
+    }
+}
+
+fn save_scope(ui: &AppWindow) {
+    let scope = ui.global::<Store>().get_statistic_scope();
+
+    let setting = ScopeSetting {
+        range: Range::NAMES
+            .get(scope.range.max(0) as usize)
+            .unwrap_or(&Range::NAMES[0])
+            .to_string(),
+        start_date: scope.start_date.into(),
+        end_date: scope.end_date.into(),
+        tags: scope.tags.iter().map(|tag| tag.into()).collect(),
+        states: scope
+            .states
+            .iter()
+            .map(|state| RecordState::from(state).as_str().to_string())
+            .collect(),
+        project: match project::statistic_filter(ui) {
+            Filter::All => String::default(),
+            Filter::NoProject => "none".to_string(),
+            Filter::Project(uuid) => uuid,
+        },
+    };
+
+    if let Err(e) = store_scope(setting) {
+        log::warn!("save statistic scope failed: {e:?}");
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn saved_scope() -> ScopeSetting {
+    config::statistic()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn store_scope(setting: ScopeSetting) -> Result<()> {
+    let mut all = config::all();
+    all.statistic = setting;
+    config::save(all)
+}
+
+// The browser has no config file, the scope is kept in the local storage
+// with the fields of `config::Statistic`
+#[cfg(target_arch = "wasm32")]
+#[derive(serde::Serialize, serde::Deserialize, Default)]
+#[serde(default)]
+struct ScopeSetting {
+    range: String,
+    start_date: String,
+    end_date: String,
+    tags: Vec<String>,
+    states: Vec<String>,
+    project: String,
+}
+
+#[cfg(target_arch = "wasm32")]
+fn saved_scope() -> ScopeSetting {
+    match crate::db::load_setting("statistic") {
+        Ok(Some(text)) => serde_json::from_str(&text).unwrap_or_default(),
+        Ok(None) => ScopeSetting::default(),
+        Err(e) => {
+            log::warn!("load statistic scope failed: {e:?}");
+            ScopeSetting::default()
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn store_scope(setting: ScopeSetting) -> Result<()> {
+    crate::db::save_setting("statistic", &serde_json::to_string(&setting)?)
+}
+
+pub fn scope(ui: &AppWindow) -> Scope {
+    let scope = ui.global::<Store>().get_statistic_scope();
+    let name = Range::NAMES
+        .get(scope.range.max(0) as usize)
+        .unwrap_or(&Range::NAMES[0]);
+
+    Scope {
+        range: Range::parse(name, &scope.start_date, &scope.end_date),
+        tags: scope.tags.iter().map(|tag| tag.into()).collect(),
+        states: scope.states.iter().map(|state| state.into()).collect(),
+    }
+}
+
+// Only the records of the selected project and in the scope are counted. The
+// tag options are the tags of the project, so a tag can still be chosen after
+// the scope left out all its records.
+pub fn statistic_init(ui: &AppWindow) {
+    store_statistic_entries!(ui).set_vec(vec![]);
+
+    let (filter, projects) = (project::statistic_filter(ui), project::projects(ui));
+    let scope = scope(ui);
+    let ui = ui.as_weak();
+    spawn(async move {
+        let entries = match TaskStore::new().statistic_entries().await {
+            Ok(entries) => entries,
             Err(e) => {
                 log::warn!("{:?}", e);
                 vec![]
             }
         };
 
-        entries.extend(archive_entries.into_iter());
-        entries.extend(record_entries.into_iter());
-
         _ = slint::invoke_from_event_loop(move || {
+            let ui = ui.unwrap();
+            let entries = entries
+                .into_iter()
+                .filter(|entry| filter.matches(entry, &projects))
+                .collect::<Vec<_>>();
+
+            let mut tags = entries
+                .iter()
+                .flat_map(|entry| entry.tags.iter().cloned())
+                .chain(scope.tags.iter().cloned())
+                .collect::<Vec<_>>();
+            tags.sort_by_key(|tag| tag.to_lowercase());
+            tags.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+
+            let today = record::today();
             let entries = entries
                 .into_iter()
+                .filter(|entry| scope.matches(entry, &today))
                 .map(|entry: RecordEntry| entry.into())
                 .collect::<Vec<UIRecordEntry>>();
 
-            store_statistic_entries!(ui.unwrap()).set_vec(entries);
+            ui.global::<Store>()
+                .set_statistic_tag_options(ModelRc::new(VecModel::from(
+                    tags.into_iter().map(SharedString::from).collect::<Vec<_>>(),
+                )));
+            store_statistic_entries!(ui).set_vec(entries);
         });
     });
 }
@@ -135,6 +135,7 @@ pub fn tr(text: &str) -> String {
         ("API base URL", "API基础URL"),
         ("API key", "API密钥"),
         ("Add entry failed", "添加条目失败"),
+        ("Archive entry failed", "归档条目失败"),
         ("Archive entry successfully", "归档条目成功"),
         ("Chat model", "聊天模型"),
         ("Days Spent", "花费天数"),
@@ -161,6 +162,7 @@ pub fn tr(text: &str) -> String {
             "仅支持OpenAI兼容的API",
         ),
         ("Please configure model setting firstly", "请先配置模型设置"),
+        ("Recover entry failed", "恢复条目失败"),
         ("Recover entry successfully", "恢复条目成功"),
         ("Refresh successfully", "刷新成功"),
         ("Remove all entries or not", "是否删除所有条目"),
@@ -207,6 +209,217 @@ pub fn tr(text: &str) -> String {
         ("Recover", "恢复"),
         ("Remove", "删除"),
         ("overtime", "超时"),
+        ("Corrupted entries", "损坏的条目"),
+        ("Data", "数据"),
+        (
+            "Entries which can not be loaded are moved here. Edit the data and repair it, or export it for a later fix",
+            "无法加载的条目会被移到这里。可以编辑数据并修复，或导出后再修复",
+        ),
+        ("Export", "导出"),
+        ("Export failed", "导出失败"),
+        ("Export successfully", "导出成功"),
+        (
+            "Please check them in the data setting",
+            "请在数据设置中查看",
+        ),
+        ("Remove all", "全部删除"),
+        ("Repair entry failed", "修复条目失败"),
+        ("Repair entry successfully", "修复条目成功"),
+        ("Restore entries failed", "恢复条目失败"),
+        ("Restored entries", "已恢复的条目"),
+        ("Retry", "重试"),
+        ("copy", "复制"),
+        ("delete", "删除"),
+        ("repair", "修复"),
+        ("Hours Tracked", "计时小时数"),
+        ("Start timer", "开始计时"),
+        ("Stop timer", "停止计时"),
+        ("Timer failed", "计时失败"),
+        ("Invalid repeat rule", "无效的重复规则"),
+        ("Repeat", "重复"),
+        (
+            "e.g. daily, weekly:mo,fr, monthly:15, every:3 or FREQ=WEEKLY;BYDAY=MO",
+            "例如 daily, weekly:mo,fr, monthly:15, every:3 或 FREQ=WEEKLY;BYDAY=MO",
+        ),
+        ("Import", "导入"),
+        ("Import failed", "导入失败"),
+        ("Import successfully", "导入成功"),
+        ("added", "新增"),
+        ("overwritten", "覆盖"),
+        ("skipped", "跳过"),
+        ("Export records and archives", "导出任务和归档"),
+        ("Import records and archives", "导入任务和归档"),
+        (
+            "Path of a .json, .csv, .md or .ics file",
+            ".json, .csv, .md 或 .ics 文件路径",
+        ),
+        ("Skip existing entries", "跳过已存在的条目"),
+        ("Overwrite existing entries", "覆盖已存在的条目"),
+        ("Duplicate with a new uuid", "使用新的uuid复制"),
+        (
+            "Exported files are saved in the data directory. An imported entry whose uuid already exists is skipped, overwritten or duplicated",
+            "导出的文件保存在数据目录。导入时uuid已存在的条目会被跳过、覆盖或复制",
+        ),
+        (
+            "The secrets are locked, enter the master password in the data setting",
+            "密钥已锁定，请在数据设置中输入主密码",
+        ),
+        ("Unlock", "解锁"),
+        ("Unlock successfully", "解锁成功"),
+        ("Unlock failed", "解锁失败"),
+        ("Save successfully", "保存成功"),
+        ("Save failed", "保存失败"),
+        ("Rewritten entries", "已重写的条目"),
+        ("Enter the master password to unlock", "输入主密码解锁"),
+        ("Change the master password", "修改主密码"),
+        ("Set a master password", "设置主密码"),
+        ("Master password", "主密码"),
+        ("Empty to use the key file", "留空则使用密钥文件"),
+        ("Encrypt the database", "加密数据库"),
+        (
+            "The api key is always encrypted with a key file next to the config file, or with the master password if it is set. A forgotten master password can not be recovered",
+            "API密钥总是使用配置文件旁的密钥文件加密，设置主密码后则使用主密码加密。忘记的主密码无法找回",
+        ),
+        ("Undo", "撤销"),
+        ("Redo", "重做"),
+        ("Undo failed", "撤销失败"),
+        ("Redo failed", "重做失败"),
+        ("Nothing to undo", "没有可撤销的操作"),
+        ("Nothing to redo", "没有可重做的操作"),
+        ("Add entry", "添加条目"),
+        ("Update entry", "更新条目"),
+        ("Remove entry", "删除条目"),
+        ("Archive entry", "归档条目"),
+        ("Recover entry", "恢复条目"),
+        ("Remove all plans successfully", "删除所有计划成功"),
+        ("trash", "回收站"),
+        ("Restore", "还原"),
+        ("Delete permanently", "永久删除"),
+        ("Empty trash", "清空回收站"),
+        ("Delete all permanently or not?", "是否全部永久删除？"),
+        ("Move to trash or not?", "是否移到回收站？"),
+        ("Delete permanently or not?", "是否永久删除？"),
+        ("Empty trash successfully", "清空回收站成功"),
+        ("Empty trash failed", "清空回收站失败"),
+        ("Restore entry", "还原条目"),
+        ("Restore entry successfully", "还原条目成功"),
+        ("Restore entry failed", "还原条目失败"),
+        (
+            "Days to keep removed entries in the trash",
+            "回收站条目保留天数",
+        ),
+        ("0 keeps them forever", "0 表示永久保留"),
+        ("State", "状态"),
+        ("History", "历史"),
+        ("Deadline Extensions", "延期次数"),
+        ("Newest", "最新"),
+        ("Priority", "优先级"),
+        ("Estimate", "预估"),
+        ("None", "无"),
+        ("Low", "低"),
+        ("Medium", "中"),
+        ("High", "高"),
+        ("Urgent", "紧急"),
+        ("e.g. 2h 30m, 1.5h or 45m", "例如 2h 30m、1.5h 或 45m"),
+        ("Invalid estimate", "无效的预估时间"),
+        ("Enter a number", "输入数字"),
+        ("Clear", "清除"),
+        ("Hours Estimated", "预估小时数"),
+        ("Custom fields", "自定义字段"),
+        ("Text", "文本"),
+        ("Number", "数字"),
+        ("Date", "日期"),
+        ("Select", "选项"),
+        ("Field name", "字段名称"),
+        ("Options separated by commas", "选项，以逗号分隔"),
+        ("Add", "添加"),
+        ("Add field successfully", "添加字段成功"),
+        ("Add field failed", "添加字段失败"),
+        ("Remove field successfully", "删除字段成功"),
+        ("Remove field failed", "删除字段失败"),
+        (
+            "Remove the field or not? The values of the tasks are kept",
+            "是否删除字段？任务中的值会被保留",
+        ),
+        ("Invalid field", "无效的字段"),
+        (
+            "Custom fields are saved in the database. Filter them with field:name=value in the search of the task list",
+            "自定义字段保存在数据库中。可在任务列表的搜索中使用 field:name=value 筛选",
+        ),
+        ("Blocked by", "前置任务"),
+        ("Blocked", "受阻"),
+        ("Blocking", "阻塞"),
+        ("Select a task", "选择任务"),
+        ("No other tasks", "没有其他任务"),
+        ("Invalid link", "无效的前置任务"),
+        ("This task is blocked by", "此任务受阻于"),
+        (
+            "Other tasks are blocked by this task. Remove the links and archive it?",
+            "其他任务依赖此任务。是否移除依赖并归档？",
+        ),
+        (
+            "Other tasks are blocked by this task. Remove the links and move it to trash?",
+            "其他任务依赖此任务。是否移除依赖并移到回收站？",
+        ),
+        ("Project", "项目"),
+        ("Projects", "项目"),
+        ("No project", "无项目"),
+        ("All projects", "全部项目"),
+        ("Archived", "已归档"),
+        ("Custom", "自定义"),
+        ("Save", "保存"),
+        ("Blue", "蓝色"),
+        ("Green", "绿色"),
+        ("Orange", "橙色"),
+        ("Red", "红色"),
+        ("Purple", "紫色"),
+        ("Gray", "灰色"),
+        ("Project name", "项目名称"),
+        ("Description", "描述"),
+        ("Add project successfully", "添加项目成功"),
+        ("Add project failed", "添加项目失败"),
+        ("Remove project successfully", "删除项目成功"),
+        ("Remove project failed", "删除项目失败"),
+        ("Archive project successfully", "归档项目成功"),
+        ("Archive project failed", "归档项目失败"),
+        ("Recover project successfully", "恢复项目成功"),
+        ("Recover project failed", "恢复项目失败"),
+        (
+            "Archive the project and all its tasks or not?",
+            "是否归档该项目及其全部任务？",
+        ),
+        (
+            "Remove the project or not? Its tasks are kept without a project",
+            "是否删除该项目？其任务将保留且不属于任何项目",
+        ),
+        (
+            "Switch the project in the task list. Archiving a project moves all its tasks to the archive",
+            "在任务列表中切换项目。归档项目会将其全部任务移到归档",
+        ),
+        ("Sub-step", "子步骤"),
+        ("Due date", "截止日期"),
+        ("Note", "备注"),
+        ("All time", "全部时间"),
+        ("This week", "本周"),
+        ("This month", "本月"),
+        ("Last 90 days", "最近90天"),
+        ("Custom range", "自定义范围"),
+        ("States", "状态"),
+        ("Invalid date", "无效日期"),
+        ("Started", "开始"),
+        ("Activity", "活动"),
+        ("Finished per Week", "每周完成"),
+        ("Open Plan Steps", "未完成的计划步骤"),
+        ("On time", "按时"),
+        ("Late", "延期"),
+        ("No tag", "无标签"),
+        ("Finished on", "完成于"),
+        ("On-time Rate %", "按时完成率 %"),
+        ("On Time and Late", "按时与延期"),
+        ("Actual / Planned Days", "实际/计划天数"),
+        ("On-time Rate % by Tag", "各标签按时完成率 %"),
+        ("Mean Slip Days by Tag", "各标签平均延期天数"),
+        ("Report", "报告"),
     ]);
 
     if let Some(txt) = items.get(text) {
@@ -0,0 +1,201 @@
+use super::{archive, project, record, statistic, toast, tr::tr};
+use crate::{
+    config,
+    core::{
+        TaskStore,
+        project::Filter,
+        report::{self, Report},
+        stats::{self, Range, Scope},
+        transfer::{Bundle, Conflict, Format, ImportSummary},
+    },
+    db::def::{ProjectEntry, RecordState},
+    slint_generatedAppWindow::{AppWindow, Logic, RecordState as UIRecordState},
+    toast_success,
+};
+use anyhow::{Context, Result};
+use slint::ComponentHandle;
+use std::path::{Path, PathBuf};
+
+// The same order as the format and conflict selects of the data setting
+const FORMATS: [Format; 4] = [Format::Json, Format::Csv, Format::Markdown, Format::Ical];
+const CONFLICTS: [Conflict; 3] = [Conflict::Skip, Conflict::Overwrite, Conflict::Duplicate];
+
+// The same order as the report format select of the statistic
+const REPORT_FORMATS: [report::Format; 2] = [report::Format::Markdown, report::Format::Html];
+
+pub fn init(ui: &AppWindow) {
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_export_entries(move |format| {
+        let ui = ui_handle.unwrap().as_weak();
+        let format = FORMATS.get(format as usize).copied().unwrap_or_default();
+
+        tokio::spawn(async move {
+            match export(format).await {
+                Ok(path) => {
+                    _ = slint::invoke_from_event_loop(move || {
+                        toast_success!(
+                            ui.unwrap(),
+                            format!("{}: {}", tr("Export successfully"), path.display())
+                        );
+                    });
+                }
+                Err(e) => toast::async_toast_warn(
+                    ui,
+                    format!("{}. {}: {e:?}", tr("Export failed"), tr("Reason")),
+                ),
+            }
+        });
+    });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>()
+        .on_import_entries(move |path, conflict| {
+            let ui = ui_handle.unwrap().as_weak();
+            let conflict = CONFLICTS
+                .get(conflict as usize)
+                .copied()
+                .unwrap_or_default();
+
+            tokio::spawn(async move {
+                match import(Path::new(path.trim()), conflict).await {
+                    Ok(summary) => {
+                        _ = slint::invoke_from_event_loop(move || {
+                            let ui = ui.unwrap();
+                            record::record_init(&ui);
+                            archive::archive_init(&ui);
+
+                            toast_success!(
+                                ui,
+                                format!(
+                                    "{}: {} {}, {} {}, {} {}",
+                                    tr("Import successfully"),
+                                    tr("added"),
+                                    summary.added,
+                                    tr("overwritten"),
+                                    summary.overwritten,
+                                    tr("skipped"),
+                                    summary.skipped
+                                )
+                            );
+                        });
+                    }
+                    Err(e) => toast::async_toast_warn(
+                        ui,
+                        format!("{}. {}: {e:?}", tr("Import failed"), tr("Reason")),
+                    ),
+                }
+            });
+        });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>()
+        .on_statistic_export_report(move |format| {
+            let ui = ui_handle.unwrap();
+            let format = REPORT_FORMATS
+                .get(format as usize)
+                .copied()
+                .unwrap_or_default();
+            let colors = stats::STATES.map(|state| {
+                let color = ui
+                    .global::<Logic>()
+                    .invoke_state_color(UIRecordState::from(state));
+                let color = format!(
+                    "#{:02X}{:02X}{:02X}",
+                    color.red(),
+                    color.green(),
+                    color.blue()
+                );
+                (state, color)
+            });
+            let (filter, projects) = (project::statistic_filter(&ui), project::projects(&ui));
+            let scope = statistic::scope(&ui);
+
+            let ui = ui.as_weak();
+            tokio::spawn(async move {
+                match export_report(format, filter, projects, scope, colors).await {
+                    Ok(path) => {
+                        _ = slint::invoke_from_event_loop(move || {
+                            toast_success!(
+                                ui.unwrap(),
+                                format!("{}: {}", tr("Export successfully"), path.display())
+                            );
+                        });
+                    }
+                    Err(e) => toast::async_toast_warn(
+                        ui,
+                        format!("{}. {}: {e:?}", tr("Export failed"), tr("Reason")),
+                    ),
+                }
+            });
+        });
+}
+
+// Written next to the database, e.g. `tasklog-20261018120000.csv`
+async fn export(format: Format) -> Result<PathBuf> {
+    let data = TaskStore::new().export().await?.export(format)?;
+    let path = config::db_path().with_file_name(format!(
+        "tasklog-{}.{}",
+        cutil::time::local_now("%Y%m%d%H%M%S"),
+        format.extension()
+    ));
+
+    std::fs::write(&path, data).with_context(|| format!("write `{}` failed", path.display()))?;
+    Ok(path)
+}
+
+// The report of the records of the statistic, its range is the period of the
+// report. Written next to the database, e.g. `tasklog-report-20261018120000.md`
+async fn export_report(
+    format: report::Format,
+    filter: Filter,
+    projects: Vec<ProjectEntry>,
+    scope: Scope,
+    colors: [(RecordState, String); 5],
+) -> Result<PathBuf> {
+    let store = TaskStore::new();
+    let today = chrono::Local::now().date_naive();
+    let range = scope.range.clone();
+    let scope = Scope {
+        range: Range::All,
+        ..scope
+    };
+
+    let entries = store
+        .statistic_entries()
+        .await?
+        .into_iter()
+        .filter(|entry| filter.matches(entry, &projects) && scope.matches(entry, ""))
+        .collect::<Vec<_>>();
+    let report = Report::new(
+        &entries,
+        &store.all_sessions().await?,
+        report::period(&range, &entries, today),
+        today,
+        cutil::time::timestamp(),
+    );
+    let data = report.render(format, &|state| {
+        colors
+            .iter()
+            .find(|(item, _)| *item == state)
+            .map_or_else(|| report::default_color(state), |(_, color)| color.clone())
+    });
+
+    let path = config::db_path().with_file_name(format!(
+        "tasklog-report-{}.{}",
+        cutil::time::local_now("%Y%m%d%H%M%S"),
+        format.extension()
+    ));
+    std::fs::write(&path, data).with_context(|| format!("write `{}` failed", path.display()))?;
+    Ok(path)
+}
+
+// The format is taken from the extension of the file, JSON if it is unknown
+async fn import(path: &Path, conflict: Conflict) -> Result<ImportSummary> {
+    let data = std::fs::read_to_string(path)
+        .with_context(|| format!("read `{}` failed", path.display()))?;
+    let format = Format::from_path(path).unwrap_or_default();
+
+    TaskStore::new()
+        .import(Bundle::import(&data, format)?, conflict)
+        .await
+}
@@ -0,0 +1,138 @@
+use super::{archive, history, record, spawn, toast, tr::tr};
+use crate::{
+    core::TaskStore,
+    db::def::{ARCHIVE_TABLE, TrashEntry},
+    slint_generatedAppWindow::{AppWindow, Logic, Store, TrashEntry as UITrashEntry},
+    toast_success, toast_warn,
+};
+use slint::{ComponentHandle, Model, VecModel};
+
+#[macro_export]
+macro_rules! store_trash_entries {
+    ($ui:expr) => {
+        $ui.global::<Store>()
+            .get_trash_entries()
+            .as_any()
+            .downcast_ref::<VecModel<UITrashEntry>>()
+            .expect("We know we set a VecModel<UITrashEntry> earlier")
+    };
+}
+
+pub fn init(ui: &AppWindow) {
+    store_trash_entries!(ui).set_vec(vec![]);
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_trash_init(move || {
+        let ui = ui_handle.unwrap();
+        trash_init(&ui);
+    });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_restore_trash(move |index| {
+        let ui = ui_handle.unwrap();
+        let index = index as usize;
+
+        let entry = store_trash_entries!(ui).row_data(index).unwrap();
+        store_trash_entries!(ui).remove(index);
+        restore_db_entry(&ui, entry.entry.uuid.into());
+    });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_remove_trash(move |index| {
+        let ui = ui_handle.unwrap();
+        let index = index as usize;
+
+        let entry = store_trash_entries!(ui).row_data(index).unwrap();
+        store_trash_entries!(ui).remove(index);
+        delete_db_entry(&ui, entry.entry.uuid.into());
+        toast_success!(ui, tr("Remove entry successfully"));
+    });
+
+    let ui_handle = ui.as_weak();
+    ui.global::<Logic>().on_remove_all_trash(move || {
+        let ui = ui_handle.unwrap().as_weak();
+
+        spawn(async move {
+            match TaskStore::new().remove_all_trash().await {
+                Ok(_) => {
+                    _ = slint::invoke_from_event_loop(move || {
+                        let ui = ui.unwrap();
+                        store_trash_entries!(ui).set_vec(vec![]);
+                        toast_success!(ui, tr("Empty trash successfully"));
+                    });
+                }
+                Err(e) => toast::async_toast_warn(
+                    ui,
+                    format!("{}. {}: {e:?}", tr("Empty trash failed"), tr("Reason")),
+                ),
+            }
+        });
+    });
+}
+
+pub fn trash_init(ui: &AppWindow) {
+    let ui = ui.as_weak();
+    spawn(async move {
+        let entries = match TaskStore::new().trash_entries().await {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::warn!("{:?}", e);
+                vec![]
+            }
+        };
+
+        _ = slint::invoke_from_event_loop(move || {
+            let entries = entries
+                .into_iter()
+                .map(|entry: TrashEntry| entry.into())
+                .collect::<Vec<UITrashEntry>>();
+
+            store_trash_entries!(ui.unwrap()).set_vec(entries);
+        });
+    });
+}
+
+// The trash list is reloaded if the restore fails
+fn restore_db_entry(ui: &AppWindow, uuid: String) {
+    let ui = ui.as_weak();
+    spawn(async move {
+        let store = TaskStore::new();
+        let uuids = vec![uuid.clone()];
+
+        match history::track(tr("Restore entry"), uuids, store.restore_trash(&uuid)).await {
+            Ok(trash) => {
+                _ = slint::invoke_from_event_loop(move || {
+                    let ui = ui.unwrap();
+                    if trash.table == ARCHIVE_TABLE {
+                        archive::archive_init(&ui);
+                    } else {
+                        record::record_init(&ui);
+                    }
+                    history::toast_undo(&ui, tr("Restore entry successfully"));
+                });
+            }
+            Err(e) => {
+                _ = slint::invoke_from_event_loop(move || {
+                    let ui = ui.unwrap();
+                    trash_init(&ui);
+                    toast_warn!(
+                        ui,
+                        format!("{}. {}: {e:?}", tr("Restore entry failed"), tr("Reason"))
+                    );
+                });
+            }
+        }
+    });
+}
+
+fn delete_db_entry(ui: &AppWindow, uuid: String) {
+    let ui = ui.as_weak();
+    spawn(async move {
+        if let Err(e) = TaskStore::new().remove_trash(&uuid).await {
+            toast::async_toast_warn(
+                ui,
+                format!("{}. {}: {e:?}", tr("Remove entry failed"), tr("Reason")),
+            )
+        }
+    });
+}
@@ -1 +1 @@
-pub static VERSION: &str = "v0.1.2";
\ No newline at end of file
+pub static VERSION: &str = "v0.1.2";